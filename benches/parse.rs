@@ -0,0 +1,91 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use linemd::Parser;
+
+const ALL_MD: &str = include_str!("../examples/all.md");
+
+fn parse(c: &mut Criterion) {
+    let large = ALL_MD.repeat(200);
+    // Lots of delimiters that don't close, which makes the parser backtrack.
+    let emphasis = "*a **b _c __d `e ".repeat(500);
+    // Many short lines, where the parser decides the block of each line.
+    let lines = "Some words in a line, with [a link](x) and `code`.\n".repeat(5000);
+    let fence = format!("```rust\n{}```", "let x = `a` ** 2; // **b\n".repeat(5000));
+
+    let mut group = c.benchmark_group("parse_md");
+    for (name, md) in [
+        ("all", ALL_MD),
+        ("large", large.as_str()),
+        ("emphasis", emphasis.as_str()),
+        ("fence", fence.as_str()),
+        ("lines", lines.as_str()),
+    ] {
+        group.bench_function(name, |b| b.iter(|| black_box(md.parse_md())));
+    }
+    group.finish();
+}
+
+/// Parses many small documents, like a server rendering comments does.
+fn parse_small(c: &mut Criterion) {
+    let docs: Vec<_> = (0..1000)
+        .map(|i| {
+            format!(
+                "# Comment {}\nSome *words*, with [a link](x) and `code`.\n",
+                i
+            )
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("parse_small");
+    group.bench_function("vec", |b| {
+        b.iter(|| {
+            for md in &docs {
+                black_box(md.parse_md());
+            }
+        })
+    });
+    #[cfg(feature = "bumpalo")]
+    group.bench_function("arena", |b| {
+        let mut arena = bumpalo::Bump::new();
+        b.iter(|| {
+            for md in &docs {
+                black_box(md.parse_md_in(&arena));
+                arena.reset();
+            }
+        })
+    });
+    group.finish();
+}
+
+/// Finds the token boundaries of a large document, by scanning and by parsing the tokens.
+fn scan(c: &mut Criterion) {
+    let large = ALL_MD.repeat(200);
+    // Blocks whose bodies are parsed into tokens, which scanning skips.
+    let blocks = ":::details More\nSome *words*, with [a link](x) and `code`.\n:::\n\
+        > [!NOTE]\n> See <me@example.org>.\n\n    indented code\n\n"
+        .repeat(1000);
+    let boundaries = |md: &str, next: fn(&str, usize) -> Option<usize>| {
+        let mut at = 0;
+        while let Some(nat) = next(md, at) {
+            at = nat;
+        }
+        at
+    };
+
+    let mut group = c.benchmark_group("boundaries");
+    for (name, md) in [("large", large.as_str()), ("blocks", blocks.as_str())] {
+        group.bench_function(format!("{}/scan_token", name), |b| {
+            b.iter(|| {
+                black_box(boundaries(md, |md, at| {
+                    md.scan_token(at).map(|(_, nat)| nat)
+                }))
+            })
+        });
+        group.bench_function(format!("{}/token_at", name), |b| {
+            b.iter(|| black_box(boundaries(md, |md, at| md.token_at(at).map(|(_, nat)| nat))))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, parse_small, scan);
+criterion_main!(benches);
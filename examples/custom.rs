@@ -0,0 +1,75 @@
+use core::fmt::Write;
+use linemd::{
+    html,
+    parser::{AtToken, CustomFn, Token},
+    Parser,
+};
+
+const MD: &str = "@alice :wave: welcome!\n:tada: @bob shipped `linemd` :rocket:";
+
+/// Tokens produced by the custom parsers below.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Extension<'a> {
+    /// An emoji shortcode, like `:wave:`.
+    Emoji(&'a str),
+    /// A mention of an user, like `@alice`.
+    Mention(&'a str),
+}
+
+/// Parses `:name:` emoji shortcodes.
+fn emoji<'a>(md: &'a &str, at: usize) -> Option<AtToken<'a, Extension<'a>>> {
+    let rest = md.get_range_str(at..).strip_prefix(':')?;
+    let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+    (len > 0 && rest[len..].starts_with(':'))
+        .then(|| (Token::Custom(Extension::Emoji(&rest[..len])), at + len + 2))
+}
+
+/// Parses `@name` mentions.
+fn mention<'a>(md: &'a &str, at: usize) -> Option<AtToken<'a, Extension<'a>>> {
+    let rest = md.get_range_str(at..).strip_prefix('@')?;
+    let len = rest
+        .find(|c: char| !c.is_alphanumeric())
+        .unwrap_or(rest.len());
+    (len > 0).then(|| {
+        (
+            Token::Custom(Extension::Mention(&rest[..len])),
+            at + len + 1,
+        )
+    })
+}
+
+/// Writes custom tokens as HTML.
+///
+/// Renderers only know about the tokens they produce, so custom tokens are passed to this
+/// function when rendering.
+fn render(extension: &Extension, buf: &mut dyn Write) {
+    match extension {
+        Extension::Emoji(name) => {
+            let emoji = match *name {
+                "wave" => "\u{1f44b}",
+                "tada" => "\u{1f389}",
+                "rocket" => "\u{1f680}",
+                _ => "\u{2753}",
+            };
+            buf.write_str(emoji).unwrap();
+        }
+        Extension::Mention(name) => write!(
+            buf,
+            r#"<a class="mention" href="/users/{0}">@{0}</a>"#,
+            name
+        )
+        .unwrap(),
+    }
+}
+
+fn main() {
+    // Both parsers are tried, in order, wherever a token can start. They need to be cast to
+    // function pointers, since each function has its own type.
+    let tokens = MD.parse_md_custom(&[emoji as CustomFn<_, _>, mention as CustomFn<_, _>]);
+    for token in &tokens {
+        println!("{:?}", token);
+    }
+
+    let html = html::render_as_html_custom(&tokens, html::Config::default(), render);
+    println!("{}", html);
+}
@@ -0,0 +1,67 @@
+use linemd::{
+    parser::{Text, Token},
+    visit::{fold, walk, Folder, Visitor},
+    Parser,
+};
+use std::borrow::Cow;
+
+const MD: &str = "See <http://example.org> and <https://example.com>.";
+
+/// Collects all URLs that use `http://`.
+#[derive(Default)]
+struct HttpUrls<'a>(Vec<Cow<'a, str>>);
+
+impl<'a> Visitor<'a, ()> for HttpUrls<'a> {
+    fn visit_url(
+        &mut self,
+        _name: Option<&Text<'a>>,
+        url: Cow<'a, str>,
+        _title: Option<&'a str>,
+        _is_image: bool,
+        _width: Option<usize>,
+        _height: Option<usize>,
+    ) {
+        if url.starts_with("http://") {
+            self.0.push(url);
+        }
+    }
+}
+
+/// Replaces `http://` URLs with their `https://` versions.
+struct Https;
+
+impl<'a> Folder<'a, ()> for Https {
+    fn fold_url(
+        &mut self,
+        name: Option<Text<'a>>,
+        url: Cow<'a, str>,
+        title: Option<&'a str>,
+        is_image: bool,
+        width: Option<usize>,
+        height: Option<usize>,
+    ) -> Token<'a, ()> {
+        let url = match url.strip_prefix("http://") {
+            Some(rest) => Cow::Owned(format!("https://{}", rest)),
+            None => url,
+        };
+        Token::Url {
+            name,
+            url,
+            title,
+            is_image,
+            width,
+            height,
+        }
+    }
+}
+
+fn main() {
+    let tokens = MD.parse_md();
+
+    let mut http_urls = HttpUrls::default();
+    walk(&tokens, &mut http_urls);
+    println!("Insecure URLs: {:?}", http_urls.0);
+
+    let tokens = fold(tokens, &mut Https);
+    println!("{}", linemd::render_as_html(&tokens));
+}
@@ -0,0 +1,27 @@
+//! The functions in `linemd::wasm` are plain Rust functions too, so this example runs natively.
+//!
+//! To use them from JavaScript, build the library for `wasm32-unknown-unknown` as a `cdylib`
+//! and generate the bindings with `wasm-bindgen`:
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/linemd.wasm
+//! ```
+//!
+//! Then, in a page:
+//!
+//! ```js
+//! import init, { parse_to_html, parse_to_svg } from "./pkg/linemd.js";
+//!
+//! await init();
+//! document.body.innerHTML = parse_to_html("# Hello\nFrom *WebAssembly*!");
+//! ```
+
+use linemd::wasm::{parse_to_html, parse_to_svg};
+
+const MD: &str = "# Hello\nFrom *WebAssembly*!";
+
+fn main() {
+    println!("{}", parse_to_html(MD));
+    println!("{}", parse_to_svg(MD, 400));
+}
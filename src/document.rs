@@ -0,0 +1,90 @@
+use core::{convert::Infallible, str::FromStr};
+
+use crate::parser::{Config, Token};
+
+use super::*;
+
+/// A markdown document, which owns its source.
+///
+/// Tokens borrow from the source they were parsed from, so the source has to be kept around
+/// for as long as they are used. A document keeps the source for you, and parses it whenever
+/// its tokens are needed, so it can be stored and passed around without lifetimes.
+///
+/// # Example
+/// ```
+/// # use linemd::Document;
+/// let document = Document::parse(String::from("# Title\nSome *uninspiring* text."));
+/// assert_eq!(document.tokens().len(), 6);
+/// assert_eq!(document.plain(), "Title\nSome uninspiring text.");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Document {
+    source: String,
+    config: Config,
+}
+
+impl Document {
+    /// Creates a document from markdown source, parsed with the default configuration.
+    pub fn parse(source: impl Into<String>) -> Self {
+        Self::parse_with_config(source, Config::default())
+    }
+
+    /// Creates a document from markdown source, parsed with the given configuration.
+    pub fn parse_with_config(source: impl Into<String>, config: Config) -> Self {
+        Self {
+            source: source.into(),
+            config,
+        }
+    }
+
+    /// Returns the markdown source of the document.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the parsed tokens of the document, which borrow from it.
+    ///
+    /// The source is parsed on every call, so keep the tokens around if you need them more
+    /// than once.
+    pub fn tokens(&self) -> Vec<Token<'_, ()>> {
+        self.source.parse_md_with_config(self.config)
+    }
+
+    /// Renders the document as plain text, see [`render_as_plain`].
+    pub fn plain(&self) -> String {
+        render_as_plain(self.tokens())
+    }
+
+    /// Renders the document as HTML, see [`render_as_html`].
+    #[cfg(feature = "html")]
+    pub fn html(&self) -> String {
+        render_as_html(self.tokens())
+    }
+
+    /// Renders the document as SVG, see [`render_as_svg`].
+    #[cfg(feature = "svg")]
+    pub fn svg(&self, config: SvgConfig<'_>) -> String {
+        render_as_svg(self.tokens(), config)
+    }
+}
+
+impl From<String> for Document {
+    fn from(source: String) -> Self {
+        Self::parse(source)
+    }
+}
+
+impl From<&str> for Document {
+    fn from(source: &str) -> Self {
+        Self::parse(source)
+    }
+}
+
+/// Parsing a document can't fail, so `"...".parse::<Document>()` always returns `Ok`.
+impl FromStr for Document {
+    type Err = Infallible;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(source))
+    }
+}
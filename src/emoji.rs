@@ -0,0 +1,143 @@
+use crate::parser::{AtToken, Parser, Text};
+
+/// Emoji shortcode names and their emoji, sorted by name so they can be binary searched.
+const EMOJIS: &[(&str, &str)] = &[
+    ("+1", "\u{1f44d}"),
+    ("-1", "\u{1f44e}"),
+    ("100", "\u{1f4af}"),
+    ("angry", "\u{1f620}"),
+    ("apple", "\u{1f34e}"),
+    ("art", "\u{1f3a8}"),
+    ("baby", "\u{1f476}"),
+    ("beer", "\u{1f37a}"),
+    ("bell", "\u{1f514}"),
+    ("blush", "\u{1f60a}"),
+    ("book", "\u{1f4d6}"),
+    ("boom", "\u{1f4a5}"),
+    ("broken_heart", "\u{1f494}"),
+    ("bug", "\u{1f41b}"),
+    ("bulb", "\u{1f4a1}"),
+    ("cake", "\u{1f370}"),
+    ("calendar", "\u{1f4c6}"),
+    ("cat", "\u{1f431}"),
+    ("clap", "\u{1f44f}"),
+    ("cloud", "\u{2601}\u{fe0f}"),
+    ("coffee", "\u{2615}"),
+    ("confused", "\u{1f615}"),
+    ("construction", "\u{1f6a7}"),
+    ("cool", "\u{1f192}"),
+    ("cry", "\u{1f622}"),
+    ("dog", "\u{1f436}"),
+    ("eyes", "\u{1f440}"),
+    ("fire", "\u{1f525}"),
+    ("fish", "\u{1f41f}"),
+    ("flushed", "\u{1f633}"),
+    ("frowning", "\u{1f626}"),
+    ("gear", "\u{2699}\u{fe0f}"),
+    ("ghost", "\u{1f47b}"),
+    ("gift", "\u{1f381}"),
+    ("grin", "\u{1f601}"),
+    ("grinning", "\u{1f600}"),
+    ("heart", "\u{2764}\u{fe0f}"),
+    ("heart_eyes", "\u{1f60d}"),
+    ("hourglass", "\u{231b}"),
+    ("hugs", "\u{1f917}"),
+    ("innocent", "\u{1f607}"),
+    ("joy", "\u{1f602}"),
+    ("key", "\u{1f511}"),
+    ("kiss", "\u{1f48b}"),
+    ("laughing", "\u{1f606}"),
+    ("link", "\u{1f517}"),
+    ("lock", "\u{1f512}"),
+    ("mag", "\u{1f50d}"),
+    ("memo", "\u{1f4dd}"),
+    ("moon", "\u{1f319}"),
+    ("muscle", "\u{1f4aa}"),
+    ("neutral_face", "\u{1f610}"),
+    ("no_entry", "\u{26d4}"),
+    ("ok", "\u{1f197}"),
+    ("ok_hand", "\u{1f44c}"),
+    ("package", "\u{1f4e6}"),
+    ("pencil2", "\u{270f}\u{fe0f}"),
+    ("point_right", "\u{1f449}"),
+    ("pray", "\u{1f64f}"),
+    ("question", "\u{2753}"),
+    ("rainbow", "\u{1f308}"),
+    ("raised_hands", "\u{1f64c}"),
+    ("recycle", "\u{267b}\u{fe0f}"),
+    ("rocket", "\u{1f680}"),
+    ("rofl", "\u{1f923}"),
+    ("rose", "\u{1f339}"),
+    ("scream", "\u{1f631}"),
+    ("see_no_evil", "\u{1f648}"),
+    ("shrug", "\u{1f937}"),
+    ("skull", "\u{1f480}"),
+    ("sleeping", "\u{1f634}"),
+    ("smile", "\u{1f604}"),
+    ("smiley", "\u{1f603}"),
+    ("smirk", "\u{1f60f}"),
+    ("snowflake", "\u{2744}\u{fe0f}"),
+    ("sob", "\u{1f62d}"),
+    ("sparkles", "\u{2728}"),
+    ("star", "\u{2b50}"),
+    ("stuck_out_tongue", "\u{1f61b}"),
+    ("sunglasses", "\u{1f60e}"),
+    ("sunny", "\u{2600}\u{fe0f}"),
+    ("sweat_smile", "\u{1f605}"),
+    ("tada", "\u{1f389}"),
+    ("thinking", "\u{1f914}"),
+    ("thumbsdown", "\u{1f44e}"),
+    ("thumbsup", "\u{1f44d}"),
+    ("tired_face", "\u{1f62b}"),
+    ("trophy", "\u{1f3c6}"),
+    ("unamused", "\u{1f612}"),
+    ("v", "\u{270c}\u{fe0f}"),
+    ("warning", "\u{26a0}\u{fe0f}"),
+    ("wave", "\u{1f44b}"),
+    ("white_check_mark", "\u{2705}"),
+    ("wink", "\u{1f609}"),
+    ("x", "\u{274c}"),
+    ("yum", "\u{1f60b}"),
+    ("zap", "\u{26a1}"),
+    ("zzz", "\u{1f4a4}"),
+];
+
+/// Gets the emoji for a shortcode name, without the surrounding colons.
+///
+/// # Example
+/// ```
+/// # use linemd::emoji::lookup;
+/// assert_eq!(lookup("tada"), Some("\u{1f389}"));
+/// assert_eq!(lookup("not_an_emoji"), None);
+/// ```
+pub fn lookup(name: &str) -> Option<&'static str> {
+    EMOJIS
+        .binary_search_by_key(&name, |(name, _)| name)
+        .ok()
+        .map(|at| EMOJIS[at].1)
+}
+
+/// Parses a `:name:` emoji shortcode as a naked text token containing the emoji.
+///
+/// Shortcodes that aren't known are left alone, so they are parsed as text. This can be passed
+/// to [`Parser::parse_md_custom`], along with other custom parsers; see also
+/// [`Parser::parse_md_emoji`].
+///
+/// # Example
+/// ```
+/// # use linemd::{emoji, parser::{CustomFn, Text}, Parser};
+/// let tokens = "Ship it :rocket:".parse_md_custom::<()>(&[emoji::parse_emoji as CustomFn<_, _>]);
+/// assert_eq!(tokens[1], Text::naked("\u{1f680}").into_token());
+/// ```
+pub fn parse_emoji<'a, Custom, P: Parser + ?Sized>(
+    md: &'a P,
+    at: usize,
+) -> Option<AtToken<'a, Custom>> {
+    let rest = md.get_range_str(at..).strip_prefix(':')?;
+    let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-')))?;
+    rest[len..]
+        .starts_with(':')
+        .then(|| lookup(&rest[..len]))
+        .flatten()
+        .map(|emoji| (Text::naked(emoji).into_token(), at + len + 2))
+}
@@ -1,21 +1,503 @@
-use crate::parser::{Text, Token};
+use crate::{
+    parser::{list_item_end, mark_paragraphs, needs_space, url_scheme, Definition, Text, Token},
+    toc::{line_text, slugify, Slugs, TocEntry},
+};
 
 use super::*;
-use core::fmt::Write;
+use alloc::{borrow::Cow, format, vec};
+use core::{fmt::Write, ops::Not};
+
+/// Function that renders the body of a code fence as HTML.
+///
+/// It gets passed the code and the language of the code fence (which can be empty),
+/// and the returned string is put inside of `<pre><code>` as-is.
+pub type Highlighter = fn(code: &str, lang: &str) -> String;
+
+/// How line breaks inside of a paragraph are rendered, see [`Config::soft_break`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SoftBreak {
+    /// Rendered as a space.
+    Space,
+    /// Rendered as a newline.
+    Newline,
+    /// Rendered as a `<br>`, followed by a newline.
+    ///
+    /// This is how GitHub renders comments (GFM's "hard breaks on newlines").
+    Br,
+}
+
+/// How whitespace between block elements is written, see [`Config::block_whitespace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BlockWhitespace {
+    /// Blocks are separated by the line breaks of the markdown, so blank lines are kept.
+    Source,
+    /// Every block is put on its own line, and blocks in another block (like list items in a
+    /// list) are indented by two spaces.
+    Pretty,
+    /// There is no whitespace between blocks, so the output is on a single line, except for
+    /// line breaks in text and code.
+    Compact,
+}
+
+/// Whether the output ends with a newline, see [`Config::trailing_newline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TrailingNewline {
+    /// The output ends with the line breaks at the end of the markdown, if any.
+    Source,
+    /// The output ends with exactly one newline, unless it's empty.
+    Always,
+    /// The output doesn't end with a newline.
+    Never,
+}
+
+/// HTML rendering configuration for [`render_as_html_with_config`].
+#[derive(Debug, Clone, Copy)]
+pub struct Config<'a> {
+    highlighter: Highlighter,
+    header_ids: bool,
+    semantic_emphasis: bool,
+    schemes: &'static [&'static str],
+    soft_break: SoftBreak,
+    keep_comments: bool,
+    code_block_wrapper: bool,
+    paragraph_tag: Option<&'static str>,
+    block_whitespace: BlockWhitespace,
+    loose_lists: bool,
+    default_scheme: Option<&'a str>,
+    trailing_newline: TrailingNewline,
+    base_url: Option<&'a str>,
+    raw_html: bool,
+    code_tab_width: Option<usize>,
+}
+
+impl Default for Config<'_> {
+    fn default() -> Self {
+        Self {
+            highlighter: escape_code,
+            header_ids: false,
+            semantic_emphasis: false,
+            schemes: &["http", "https", "mailto"],
+            soft_break: SoftBreak::Newline,
+            keep_comments: false,
+            code_block_wrapper: false,
+            paragraph_tag: Some("p"),
+            block_whitespace: BlockWhitespace::Source,
+            loose_lists: true,
+            default_scheme: None,
+            trailing_newline: TrailingNewline::Source,
+            base_url: None,
+            raw_html: true,
+            code_tab_width: None,
+        }
+    }
+}
+
+impl<'a> Config<'a> {
+    /// Set the highlighter used to render code fences.
+    ///
+    /// By default, code is only escaped.
+    pub const fn highlighter(mut self, value: Highlighter) -> Self {
+        self.highlighter = value;
+        self
+    }
+
+    /// Set whether headers get an `id` attribute, derived from their text.
+    ///
+    /// The ids are the same as the slugs generated by [`generate_toc`](crate::generate_toc).
+    /// Disabled by default.
+    pub const fn header_ids(mut self, value: bool) -> Self {
+        self.header_ids = value;
+        self
+    }
+
+    /// Set whether emphasis is rendered with `<em>` and `<strong>`, instead of `<i>` and `<b>`.
+    ///
+    /// Disabled by default.
+    pub const fn semantic_emphasis(mut self, value: bool) -> Self {
+        self.semantic_emphasis = value;
+        self
+    }
+
+    /// Set the URL schemes that links and images are allowed to use.
+    ///
+    /// URLs with other schemes (like `javascript:`) are replaced with `#`, except `data:` URLs
+    /// of images (ie. `data:image/...`). URLs without a scheme are always allowed.
+    /// Defaults to `http`, `https` and `mailto`.
+    pub const fn allowed_schemes(mut self, value: &'static [&'static str]) -> Self {
+        self.schemes = value;
+        self
+    }
+
+    /// Set how line breaks inside of a paragraph are rendered.
+    ///
+    /// Defaults to [`SoftBreak::Newline`].
+    pub const fn soft_break(mut self, value: SoftBreak) -> Self {
+        self.soft_break = value;
+        self
+    }
+
+    /// Set whether comments are kept in the output.
+    ///
+    /// Comments often have notes that aren't meant to be published, so they are stripped by
+    /// default.
+    pub const fn keep_comments(mut self, value: bool) -> Self {
+        self.keep_comments = value;
+        self
+    }
+
+    /// Set whether code fences are wrapped in a `<div class="code-block">`, with the language
+    /// in a `data-lang` attribute if it has one.
+    ///
+    /// This gives scripts (like ones adding a "copy" button) a hook for code blocks. Disabled
+    /// by default.
+    pub const fn code_block_wrapper(mut self, value: bool) -> Self {
+        self.code_block_wrapper = value;
+        self
+    }
+
+    /// Set the width that tabs in code fences are expanded to, or `None` to keep tabs.
+    ///
+    /// Browsers show tabs in `<pre>` 8 columns wide unless styled otherwise, so expanding them
+    /// makes the code look the same everywhere. A tab advances to the next multiple of the
+    /// width, and the tokens are left as they are. Disabled by default.
+    pub const fn code_tab_width(mut self, value: Option<usize>) -> Self {
+        self.code_tab_width = value;
+        self
+    }
+
+    /// Set the tag paragraphs are wrapped in, or `None` to not wrap them at all.
+    ///
+    /// The tag can have attributes with double quoted values, like `div class="paragraph"`.
+    /// Not wrapping paragraphs is useful when the output is put where `<p>` isn't allowed,
+    /// like inside of a `<button>`. Defaults to `p`.
+    pub const fn paragraph_tag(mut self, value: Option<&'static str>) -> Self {
+        self.paragraph_tag = value;
+        self
+    }
+
+    /// Set how whitespace between block elements is written.
+    ///
+    /// [`BlockWhitespace::Pretty`] is easier to read and diff, and [`BlockWhitespace::Compact`]
+    /// is smaller. Defaults to [`BlockWhitespace::Source`].
+    pub const fn block_whitespace(mut self, value: BlockWhitespace) -> Self {
+        self.block_whitespace = value;
+        self
+    }
+
+    /// Set whether blank lines between list items make the list loose, like in CommonMark.
+    ///
+    /// The items of a loose list have their content put in paragraphs, while the items of a
+    /// tight list don't. A list is loose if its items are separated by blank lines, or if any
+    /// of its items has a blank line in it. If disabled, a blank line between items ends the
+    /// list, and only items with blank lines in them are put in paragraphs. Enabled by
+    /// default.
+    pub const fn loose_lists(mut self, value: bool) -> Self {
+        self.loose_lists = value;
+        self
+    }
+
+    /// Set the scheme that is put before the URLs of links without one, like `https` to link
+    /// `[text](example.com)` to `https://example.com`.
+    ///
+    /// Anchors (like `#title`) and relative paths starting with `/`, `.` or `?` are left as
+    /// they are, so links to other pages need a `./` with this set. `www.` autolinks get this
+    /// scheme instead of `http`. Images are never changed. Disabled by default.
+    pub const fn default_scheme(mut self, value: Option<&'a str>) -> Self {
+        self.default_scheme = value;
+        self
+    }
+
+    /// Set whether the output ends with a newline.
+    ///
+    /// [`TrailingNewline::Always`] and [`TrailingNewline::Never`] make the end of the output
+    /// the same for any markdown, which is useful for snapshot tests. Defaults to
+    /// [`TrailingNewline::Source`].
+    pub const fn trailing_newline(mut self, value: TrailingNewline) -> Self {
+        self.trailing_newline = value;
+        self
+    }
+
+    /// Set the URL that relative URLs of links and images are resolved against, like a
+    /// browser resolves them against the URL of the page.
+    ///
+    /// This is needed when the HTML is shown somewhere else than the page, like in a feed
+    /// reader. URLs with a scheme are left as they are, and the default scheme isn't added to
+    /// resolved URLs. Disabled by default.
+    pub const fn base_url(mut self, value: Option<&'a str>) -> Self {
+        self.base_url = value;
+        self
+    }
+
+    /// Set whether raw HTML blocks (see [`ParserConfig::html`]) are written.
+    ///
+    /// Enabled by default.
+    pub const fn raw_html(mut self, value: bool) -> Self {
+        self.raw_html = value;
+        self
+    }
+}
+
+/// State kept while rendering a document.
+struct Context<'a, Custom> {
+    config: &'a Config<'a>,
+    slugs: Slugs,
+    /// Footnote definitions of the document as `(id, body)`, in the order they were defined.
+    /// Only the first definition of an id is kept.
+    footnotes: Vec<(&'a str, &'a str)>,
+    render_custom: &'a dyn Fn(&Custom, &mut dyn Write),
+    /// Whether the list being rendered is loose.
+    loose_list: bool,
+}
+
+impl<'a, Custom> Context<'a, Custom> {
+    fn new(
+        tokens: &[Token<'a, Custom>],
+        config: &'a Config<'a>,
+        render_custom: &'a dyn Fn(&Custom, &mut dyn Write),
+    ) -> Self {
+        let mut footnotes: Vec<(&str, &str)> = Vec::new();
+        for token in tokens {
+            if let Token::FootnoteDef { id, body } = token {
+                if !footnotes.iter().any(|(def_id, _)| def_id == id) {
+                    footnotes.push((id, body));
+                }
+            }
+        }
+        Self {
+            config,
+            slugs: Slugs::default(),
+            footnotes,
+            render_custom,
+            loose_list: false,
+        }
+    }
+}
 
 /// Renders parsed tokens as HTML.
 ///
+/// Inline tokens on the same line are separated by a single space, and trailing whitespace of
+/// text is removed, so `Some *uninspiring* text.` renders as
+/// `<p>Some <i>uninspiring</i> text.</p>`.
+///
 /// # Example
 /// ```
 /// # use linemd::{render_as_html, Parser};
 /// let html = render_as_html("Some uninspiring text.".parse_md());
 /// ```
 pub fn render_as_html<'a>(tokens: impl AsRef<[Token<'a, ()>]> + 'a) -> String {
+    render_as_html_with_config(tokens, Config::default())
+}
+
+/// Renders parsed tokens as HTML, using the given configuration.
+///
+/// # Example
+/// ```
+/// # use linemd::{render_as_html_with_config, HtmlConfig, Parser};
+/// let html = render_as_html_with_config("Some uninspiring text.".parse_md(), HtmlConfig::default());
+/// ```
+pub fn render_as_html_with_config<'a>(
+    tokens: impl AsRef<[Token<'a, ()>]> + 'a,
+    config: Config<'_>,
+) -> String {
     let mut buf = String::new();
-    render_to_buffer(tokens, &mut buf);
+    render_to_buffer_with_config(tokens, config, &mut buf);
     buf
 }
 
+/// Renders tokens from an iterator as HTML, using the given configuration.
+///
+/// The tokens are collected before rendering, since lists and paragraphs need to look ahead.
+/// Use [`Iterator::cloned`] to render borrowed tokens.
+///
+/// # Example
+/// ```
+/// # use linemd::{html, Parser};
+/// let tokens = "# Title\nSome uninspiring text.".parse_md();
+/// let html = html::render_iter(tokens.into_iter().skip(3), html::Config::default());
+/// assert_eq!(html, "<p>Some uninspiring text.</p>");
+/// ```
+pub fn render_iter<'a>(
+    tokens: impl IntoIterator<Item = Token<'a, ()>>,
+    config: Config<'_>,
+) -> String {
+    render_as_html_with_config(tokens.into_iter().collect::<Vec<_>>(), config)
+}
+
+/// Renders parsed tokens as HTML to a writer, using the given configuration, writing each
+/// block as soon as it is rendered.
+///
+/// The output is the same as the one of [`render_as_html_with_config`], but each block
+/// separated by a blank line (except for list items, which are written with their list) is
+/// written with a single [`Write::write_str`] call. So a writer that sends what it gets, like
+/// one flushing a response body after each call, can start sending before the whole document
+/// is rendered. Footnotes are written last, in a call of their own.
+///
+/// # Example
+/// ```
+/// # use linemd::{html, Parser};
+/// let mut html = String::new();
+/// html::render_to_writer("# Title\n\nSome text.".parse_md(), html::Config::default(), &mut html)
+///     .unwrap();
+/// assert_eq!(html, "<h1>Title</h1>\n\n<p>Some text.</p>");
+/// ```
+pub fn render_to_writer<'a, W: Write>(
+    tokens: impl AsRef<[Token<'a, ()>]> + 'a,
+    config: Config<'_>,
+    out: &mut W,
+) -> core::fmt::Result {
+    let tokens = tokens.as_ref();
+    let mut ctx = Context::new(tokens, &config, &|_, _| {});
+    let tokens = &with_paragraphs(tokens);
+
+    let mut html = String::new();
+    let mut wrote_any = false;
+    // Newlines at the end of the last block are held back, since they are only written if
+    // another block follows, when the trailing newline is configured.
+    let mut held_newlines = 0;
+    let mut write_block = |html: &str, out: &mut W| {
+        let mut block = Cow::Borrowed(html);
+        // Blocks are separated by a newline when pretty printed, like in `format_nodes`.
+        if config.block_whitespace == BlockWhitespace::Pretty && wrote_any && html.is_empty().not()
+        {
+            block = Cow::Owned(format!("\n{}", html));
+        }
+        if block.is_empty() {
+            return Ok(());
+        }
+        if config.trailing_newline != TrailingNewline::Source {
+            let len = block.trim_end_matches('\n').len();
+            if len == 0 {
+                held_newlines += block.len();
+                return Ok(());
+            }
+            let held = core::mem::replace(&mut held_newlines, block.len() - len);
+            let mut held_block = "\n".repeat(held);
+            held_block.push_str(&block[..len]);
+            block = Cow::Owned(held_block);
+        }
+        wrote_any = true;
+        out.write_str(&block)
+    };
+
+    let mut start = 0;
+    for end in block_starts(tokens, &config).chain([tokens.len()]) {
+        html.clear();
+        write_rendered(&mut html, &tokens[start..end], false, &mut ctx);
+        write_block(&html, out)?;
+        start = end;
+    }
+    html.clear();
+    write_rendered(&mut html, &[], true, &mut ctx);
+    write_block(&html, out)?;
+    if wrote_any && config.trailing_newline == TrailingNewline::Always {
+        out.write_char('\n')?;
+    }
+    Ok(())
+}
+
+/// Returns where the blocks after the first one start, for [`render_to_writer`].
+///
+/// A block starts after a blank line, with a token that starts a block element when rendered.
+/// Lists aren't split, since blank lines can be in them.
+fn block_starts<'t, 'a, Custom>(
+    tokens: &'t [Token<'a, Custom>],
+    config: &'t Config<'_>,
+) -> impl Iterator<Item = usize> + 't {
+    let mut in_list = false;
+    (1..tokens.len()).filter(move |at| {
+        let (before, token) = (&tokens[..*at], &tokens[*at]);
+        let starts_block = match token {
+            Token::ParagraphStart => config.paragraph_tag.is_some(),
+            Token::ListItem { .. } => in_list.not(),
+            Token::Html(_) => false,
+            token => token.is_block(),
+        };
+        if before.last().is_some_and(Token::is_line_break) && token.is_line_break().not() {
+            in_list = match token {
+                Token::ListItem { .. } => true,
+                Token::ListContinuation => in_list,
+                _ => false,
+            };
+        }
+        starts_block && matches!(before, [.., Token::LineBreak, Token::LineBreak])
+    })
+}
+
+/// A stylesheet for a standalone HTML document, see [`render_as_full_html`].
+#[derive(Debug, Clone, Copy)]
+pub enum Stylesheet<'a> {
+    /// CSS put in a `<style>` element as-is.
+    Inline(&'a str),
+    /// URL of a stylesheet, linked with a `<link>` element.
+    Link(&'a str),
+}
+
+/// Renders parsed tokens as a standalone HTML document, using the given configuration.
+///
+/// The rendered tokens are wrapped in a `<body>`, and the `<head>` has the given title,
+/// UTF-8 charset and the stylesheet, if any.
+///
+/// # Example
+/// ```
+/// # use linemd::{html::{self, Stylesheet}, Parser};
+/// let html = html::render_as_full_html(
+///     "Some uninspiring text.".parse_md(),
+///     html::Config::default(),
+///     "Uninspiring",
+///     Some(Stylesheet::Inline("body { margin: 0; }")),
+/// );
+/// ```
+pub fn render_as_full_html<'a>(
+    tokens: impl AsRef<[Token<'a, ()>]> + 'a,
+    config: Config<'_>,
+    title: &str,
+    stylesheet: Option<Stylesheet<'_>>,
+) -> String {
+    let mut buf = String::new();
+    buf.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>");
+    write_escaped(&mut buf, title).unwrap();
+    buf.push_str("</title>\n");
+    match stylesheet {
+        Some(Stylesheet::Inline(css)) => write!(buf, "<style>\n{}\n</style>\n", css).unwrap(),
+        Some(Stylesheet::Link(url)) => {
+            buf.push_str(r#"<link rel="stylesheet" href=""#);
+            write_escaped(&mut buf, url).unwrap();
+            buf.push_str("\">\n");
+        }
+        None => {}
+    }
+    buf.push_str("</head>\n<body>\n");
+    render_to_buffer_with_config(tokens, config, &mut buf);
+    buf.push_str("\n</body>\n</html>\n");
+    buf
+}
+
+/// Renders parsed tokens as HTML for the `<description>` of an RSS feed item.
+///
+/// Relative URLs are resolved against `base_url` (usually the URL of the item's page), since
+/// feed readers don't know where the item came from. Raw HTML blocks and comments are left
+/// out, and `]]>` is escaped, so the output can be put in a CDATA section as-is.
+///
+/// # Example
+/// ```
+/// # use linemd::{html, Parser};
+/// let html = html::render_as_rss_description(
+///     "See [the docs](../docs).".parse_md(),
+///     "https://example.org/blog/post",
+/// );
+/// assert_eq!(html, "<p>See <a href=\"https://example.org/docs\">the docs</a>.</p>");
+/// ```
+pub fn render_as_rss_description<'a>(
+    tokens: impl AsRef<[Token<'a, ()>]> + 'a,
+    base_url: &str,
+) -> String {
+    let config = Config::default()
+        .base_url(Some(base_url))
+        .raw_html(false)
+        .keep_comments(false);
+    render_as_html_with_config(tokens, config).replace("]]>", "]]&gt;")
+}
+
 /// Renders parsed tokens as HTML, to a buffer.
 ///
 /// # Example
@@ -25,156 +507,1248 @@ pub fn render_as_html<'a>(tokens: impl AsRef<[Token<'a, ()>]> + 'a) -> String {
 /// let html = html::render_to_buffer("Some uninspiring text.".parse_md(), &mut buffer);
 /// ```
 pub fn render_to_buffer<'a>(tokens: impl AsRef<[Token<'a, ()>]> + 'a, buf: &mut String) {
+    render_to_buffer_with_config(tokens, Config::default(), buf)
+}
+
+/// Renders parsed tokens as HTML, to a buffer, using the given configuration.
+///
+/// # Example
+/// ```
+/// # use linemd::{html, Parser};
+/// let mut buffer = String::new();
+/// let html = html::render_to_buffer_with_config(
+///     "Some uninspiring text.".parse_md(),
+///     html::Config::default(),
+///     &mut buffer,
+/// );
+/// ```
+pub fn render_to_buffer_with_config<'a>(
+    tokens: impl AsRef<[Token<'a, ()>]> + 'a,
+    config: Config<'_>,
+    buf: &mut String,
+) {
+    render_to_buffer_custom(tokens, config, |_, _| {}, buf)
+}
+
+/// Renders parsed tokens with custom tokens as HTML, using the given configuration.
+///
+/// Custom tokens are rendered by `render_custom`, which gets passed the custom token and
+/// the buffer to write to. They are treated like inline tokens, so they are put in paragraphs.
+///
+/// # Example
+/// ```
+/// # use linemd::{html, parser::{AtToken, CustomFn, Token}, Parser};
+/// # use core::fmt::Write;
+/// fn star<'a>(md: &'a &str, at: usize) -> Option<AtToken<'a, ()>> {
+///     md.get_range_str(at..).starts_with("(*)").then(|| (Token::Custom(()), at + 3))
+/// }
+///
+/// let tokens = "(*) Uninspiring".parse_md_custom(&[star as CustomFn<_, _>]);
+/// let html = html::render_as_html_custom(&tokens, html::Config::default(), |_, buf| {
+///     buf.write_str("<span class=\"star\"></span>").unwrap()
+/// });
+/// assert_eq!(html, "<p><span class=\"star\"></span> Uninspiring</p>");
+/// ```
+pub fn render_as_html_custom<'a, Custom: Clone + 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    config: Config<'_>,
+    render_custom: impl Fn(&Custom, &mut dyn Write),
+) -> String {
+    let mut buf = String::new();
+    render_to_buffer_custom(tokens, config, render_custom, &mut buf);
+    buf
+}
+
+/// Renders parsed tokens with custom tokens as HTML, to a buffer, using the given
+/// configuration. See [`render_as_html_custom`].
+pub fn render_to_buffer_custom<'a, Custom: Clone + 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    config: Config<'_>,
+    render_custom: impl Fn(&Custom, &mut dyn Write),
+    buf: &mut String,
+) {
+    let tokens = tokens.as_ref();
+    let mut ctx = Context::new(tokens, &config, &render_custom);
+    let start = buf.len();
+
+    write_rendered(buf, &with_paragraphs(tokens), true, &mut ctx);
+
+    if config.trailing_newline != TrailingNewline::Source {
+        let len = buf[start..].trim_end_matches('\n').len();
+        buf.truncate(start + len);
+        if config.trailing_newline == TrailingNewline::Always && len > 0 {
+            buf.push('\n');
+        }
+    }
+}
+
+/// Elements that are blocks, for formatting the whitespace between them.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+];
+
+/// Elements that have no end tag, and so no children.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Where HTML is rendered to.
+///
+/// Elements are written with [`HtmlWrite::start_tag`] and [`HtmlWrite::end_tag`], so they can
+/// be built as nodes, while text and raw HTML are written as they are, with [`Write`].
+trait HtmlWrite: Write {
+    /// Writes the start tag of an element. The attribute values are escaped when written.
+    fn start_tag(&mut self, tag: &str, attrs: &[(&str, &str)]);
+
+    /// Writes the end tag of an element.
+    fn end_tag(&mut self, tag: &str);
+}
+
+impl HtmlWrite for String {
+    fn start_tag(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        write!(self, "<{}", tag).unwrap();
+        for (name, value) in attrs {
+            write!(self, r#" {}=""#, name).unwrap();
+            write_escaped(self, value).unwrap();
+            self.push('"');
+        }
+        self.push('>');
+    }
+
+    fn end_tag(&mut self, tag: &str) {
+        write!(self, "</{}>", tag).unwrap();
+    }
+}
+
+/// Builds the nodes of rendered HTML, see [`render_as_nodes`].
+#[derive(Default)]
+struct NodeBuilder {
+    /// Elements that are open, with the children they have so far.
+    open: Vec<Node>,
+    nodes: Vec<Node>,
+}
+
+impl NodeBuilder {
+    fn push(&mut self, node: Node) {
+        match self.open.last_mut() {
+            Some(Node::Element { children, .. }) => push_node(children, node),
+            _ => push_node(&mut self.nodes, node),
+        }
+    }
+
+    /// Closes the elements after the first `len` ones that are open, which have no end tag.
+    ///
+    /// An element without an end tag is kept as its start tag as text, followed by its
+    /// children, so the nodes serialize to the same HTML.
+    fn close_unclosed(&mut self, len: usize) {
+        while self.open.len() > len {
+            if let Some(Node::Element {
+                tag,
+                attrs,
+                children,
+            }) = self.open.pop()
+            {
+                let mut start_tag = String::new();
+                write_start_tag(&mut start_tag, &tag, &attrs);
+                self.push(Node::Text(start_tag));
+                children.into_iter().for_each(|child| self.push(child));
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Node> {
+        self.close_unclosed(0);
+        self.nodes
+    }
+}
+
+impl Write for NodeBuilder {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        if s.is_empty().not() {
+            self.push(Node::Text(String::from(s)));
+        }
+        Ok(())
+    }
+}
+
+impl HtmlWrite for NodeBuilder {
+    fn start_tag(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        let element = Node::Element {
+            tag: String::from(tag),
+            attrs: attrs
+                .iter()
+                .map(|(name, value)| (String::from(*name), escape(value)))
+                .collect(),
+            children: Vec::new(),
+        };
+        if VOID_ELEMENTS.contains(&tag) {
+            self.push(element);
+        } else {
+            self.open.push(element);
+        }
+    }
+
+    fn end_tag(&mut self, tag: &str) {
+        let index = self
+            .open
+            .iter()
+            .rposition(|open| matches!(open, Node::Element { tag: open, .. } if open == tag));
+        match index {
+            Some(index) => {
+                self.close_unclosed(index + 1);
+                let element = self.open.pop().unwrap();
+                self.push(element);
+            }
+            None => write!(self, "</{}>", tag).unwrap(),
+        }
+    }
+}
+
+/// A node of an HTML tree, see [`render_as_nodes`].
+///
+/// Text and attribute values are kept as they are written in the HTML, so they are already
+/// escaped. Use [`escape`] when adding new ones.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Node {
+    /// An element, like `<p>...</p>`. Void elements (like `<br>`) have no children.
+    Element {
+        /// Name of the element, like `p`.
+        tag: String,
+        /// Attributes of the element as `(name, value)`, in the order they are written.
+        attrs: Vec<(String, String)>,
+        /// Nodes in the element.
+        children: Vec<Node>,
+    },
+    /// Text, or HTML that isn't an element rendered from a token (like raw HTML, a comment,
+    /// or the start tag of a list that isn't closed).
+    Text(String),
+}
+
+impl Node {
+    /// Serializes the node as HTML.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{html::{self, Node}, Parser};
+    /// let nodes = html::render_as_nodes("Some *uninspiring* text.".parse_md());
+    /// let html: String = nodes.iter().map(Node::to_html_string).collect();
+    /// assert_eq!(html, "<p>Some <i>uninspiring</i> text.</p>");
+    /// ```
+    pub fn to_html_string(&self) -> String {
+        let mut buf = String::new();
+        self.write_html(&mut buf);
+        buf
+    }
+
+    fn write_html(&self, buf: &mut String) {
+        match self {
+            Node::Text(text) => buf.push_str(text),
+            Node::Element {
+                tag,
+                attrs,
+                children,
+            } => {
+                write_start_tag(buf, tag, attrs);
+                if children.is_empty() && VOID_ELEMENTS.contains(&tag.as_str()) {
+                    return;
+                }
+                for child in children {
+                    child.write_html(buf);
+                }
+                write!(buf, "</{}>", tag).unwrap();
+            }
+        }
+    }
+}
+
+fn write_start_tag(buf: &mut String, tag: &str, attrs: &[(String, String)]) {
+    write!(buf, "<{}", tag).unwrap();
+    for (name, value) in attrs {
+        write!(buf, r#" {}="{}""#, name, value).unwrap();
+    }
+    buf.push('>');
+}
+
+/// Adds a node to nodes, joining it with the last one if both are text.
+fn push_node(nodes: &mut Vec<Node>, node: Node) {
+    match (nodes.last_mut(), node) {
+        (Some(Node::Text(last)), Node::Text(text)) => last.push_str(&text),
+        (_, node) => nodes.push(node),
+    }
+}
+
+fn is_block(node: &Node) -> bool {
+    matches!(node, Node::Element { tag, .. } if BLOCK_ELEMENTS.contains(&tag.as_str()))
+}
+
+/// Replaces the whitespace between the blocks of nodes. See [`BlockWhitespace`].
+///
+/// Blocks that have blocks in them get their children formatted at the next `depth`, and
+/// the other nodes are kept as they are, except for the whitespace around runs of inline
+/// nodes.
+fn format_nodes(nodes: Vec<Node>, pretty: bool, depth: usize) -> Vec<Node> {
+    let indent = |depth| {
+        let mut indent = String::from("\n");
+        (0..depth).for_each(|_| indent.push_str("  "));
+        Node::Text(indent)
+    };
+
+    let mut formatted = Vec::new();
+    let mut is_first = true;
+    let mut nodes = nodes.into_iter().peekable();
+    while let Some(node) = nodes.next() {
+        let is_block = is_block(&node);
+        let mut run = vec![node];
+        if !is_block {
+            while let Some(node) = nodes.next_if(|node| !self::is_block(node)) {
+                run.push(node);
+            }
+            if let Some(Node::Text(text)) = run.first_mut() {
+                text.drain(..text.len() - text.trim_start().len());
+            }
+            if let Some(Node::Text(text)) = run.last_mut() {
+                text.truncate(text.trim_end().len());
+            }
+            run.retain(|node| !matches!(node, Node::Text(text) if text.is_empty()));
+            if run.is_empty() {
+                continue;
+            }
+        }
+        // Text right after the start tag of a block stays on its line.
+        if pretty && !is_first && depth == 0 {
+            push_node(&mut formatted, Node::Text(String::from("\n")));
+        } else if pretty && depth > 0 && (is_first && !is_block).not() {
+            push_node(&mut formatted, indent(depth));
+        }
+        is_first = false;
+
+        for node in run {
+            let node = match node {
+                Node::Element {
+                    tag,
+                    attrs,
+                    children,
+                } if is_block && tag != "pre" && children.iter().any(self::is_block) => {
+                    let mut children = format_nodes(children, pretty, depth + 1);
+                    if pretty {
+                        push_node(&mut children, indent(depth));
+                    }
+                    Node::Element {
+                        tag,
+                        attrs,
+                        children,
+                    }
+                }
+                node => node,
+            };
+            push_node(&mut formatted, node);
+        }
+    }
+    formatted
+}
+
+/// Writes tokens (which have their paragraphs marked) and the footnotes if `footnotes`, with
+/// the whitespace between blocks formatted as configured.
+fn write_rendered<Custom: Clone>(
+    buf: &mut String,
+    tokens: &[Token<Custom>],
+    footnotes: bool,
+    ctx: &mut Context<Custom>,
+) {
+    if ctx.config.block_whitespace == BlockWhitespace::Source {
+        write_marked_blocks(buf, tokens, ctx);
+        if footnotes {
+            write_footnotes(buf, ctx);
+        }
+    } else {
+        for node in render_nodes(tokens, footnotes, ctx) {
+            node.write_html(buf);
+        }
+    }
+}
+
+/// Renders tokens (which have their paragraphs marked) and the footnotes if `footnotes` as
+/// nodes, with the whitespace between blocks formatted as configured.
+fn render_nodes<Custom: Clone>(
+    tokens: &[Token<Custom>],
+    footnotes: bool,
+    ctx: &mut Context<Custom>,
+) -> Vec<Node> {
+    let mut nodes = NodeBuilder::default();
+    write_marked_blocks(&mut nodes, tokens, ctx);
+    if footnotes {
+        write_footnotes(&mut nodes, ctx);
+    }
+    match ctx.config.block_whitespace {
+        BlockWhitespace::Source => nodes.finish(),
+        block_whitespace => format_nodes(
+            nodes.finish(),
+            block_whitespace == BlockWhitespace::Pretty,
+            0,
+        ),
+    }
+}
+
+/// Renders parsed tokens as a tree of HTML nodes.
+///
+/// This is the tree of the HTML that [`render_as_html`] renders, so it can be changed before
+/// it is serialized with [`Node::to_html_string`].
+///
+/// # Example
+/// ```
+/// # use linemd::{html::{self, Node}, Parser};
+/// let nodes = html::render_as_nodes("# Uninspiring".parse_md());
+/// assert!(matches!(&nodes[0], Node::Element { tag, .. } if tag == "h1"));
+/// ```
+pub fn render_as_nodes<'a>(tokens: impl AsRef<[Token<'a, ()>]> + 'a) -> Vec<Node> {
+    render_as_nodes_with_config(tokens, Config::default())
+}
+
+/// Renders parsed tokens as a tree of HTML nodes, using the given configuration. See
+/// [`render_as_nodes`].
+pub fn render_as_nodes_with_config<'a>(
+    tokens: impl AsRef<[Token<'a, ()>]> + 'a,
+    config: Config<'_>,
+) -> Vec<Node> {
+    let tokens = tokens.as_ref();
+    let mut ctx = Context::new(tokens, &config, &|_, _| {});
+    let mut nodes = render_nodes(&with_paragraphs(tokens), true, &mut ctx);
+
+    if config.trailing_newline != TrailingNewline::Source {
+        if let Some(Node::Text(text)) = nodes.last_mut() {
+            text.truncate(text.trim_end_matches('\n').len());
+            if text.is_empty() {
+                nodes.pop();
+            }
+        }
+        if config.trailing_newline == TrailingNewline::Always && nodes.is_empty().not() {
+            nodes.push(Node::Text(String::from("\n")));
+        }
+    }
+    nodes
+}
+
+/// Renders a table of contents as nested HTML lists of links to the headers.
+///
+/// # Example
+/// ```
+/// # use linemd::{generate_toc, html, Parser};
+/// let toc = generate_toc("# Some\n## Uninspiring headers".parse_md());
+/// let html = html::render_toc_as_html(&toc);
+/// ```
+pub fn render_toc_as_html(entries: &[TocEntry]) -> String {
+    let mut buf = String::new();
+    let mut depths: Vec<usize> = Vec::new();
+    for entry in entries {
+        match depths.last() {
+            Some(&depth) if entry.depth <= depth => {
+                buf.push_str("</li>\n");
+                while depths.len() > 1 && depths.last().is_some_and(|&d| d > entry.depth) {
+                    depths.pop();
+                    buf.push_str("</ul>\n</li>\n");
+                }
+            }
+            _ => {
+                buf.push_str("<ul>\n");
+                depths.push(entry.depth);
+            }
+        }
+        write!(buf, r##"<li><a href="#{}">"##, entry.slug).unwrap();
+        write_escaped(&mut buf, &entry.text).unwrap();
+        buf.push_str("</a>");
+    }
+    for _ in depths {
+        buf.push_str("</li>\n</ul>\n");
+    }
+    buf
+}
+
+/// Writes tokens as HTML, putting list items in lists and inline tokens in paragraphs.
+fn write_blocks<W: HtmlWrite, Custom: Clone>(
+    buf: &mut W,
+    tokens: &[Token<Custom>],
+    ctx: &mut Context<Custom>,
+) {
+    write_marked_blocks(buf, &with_paragraphs(tokens), ctx);
+}
+
+/// Returns the tokens with their paragraphs marked, unless they already are.
+fn with_paragraphs<'t, 'a, Custom: Clone>(
+    tokens: &'t [Token<'a, Custom>],
+) -> Cow<'t, [Token<'a, Custom>]> {
+    if tokens.iter().any(|t| matches!(t, Token::ParagraphStart)) {
+        Cow::Borrowed(tokens)
+    } else {
+        Cow::Owned(mark_paragraphs(tokens))
+    }
+}
+
+/// Writes tokens that have their paragraphs marked, see [`write_blocks`].
+fn write_marked_blocks<W: HtmlWrite, Custom: Clone>(
+    buf: &mut W,
+    tokens: &[Token<Custom>],
+    ctx: &mut Context<Custom>,
+) {
     let mut in_unordered_list = false;
     let mut in_ordered_list = false;
+    // The bullet of the unordered list and the numbering style of the ordered list, see
+    // `list_item_kind`.
+    let mut bullet = '-';
+    let mut ordered_style = '1';
 
     let mut was_line_break = false;
-    let mut in_paragraph = false;
 
-    let tokens = tokens.as_ref();
+    // Lists can be nested in other blocks, so the looseness of the enclosing list is restored
+    // when done.
+    let outer_loose_list = ctx.loose_list;
+
     let mut at = 0;
     while at < tokens.len() {
         let token = &tokens[at];
 
-        let is_unordered_item = matches!(token, Token::ListItem(None));
-        let is_ordered_item = matches!(token, Token::ListItem(Some(_)));
-        let is_line_break = matches!(token, Token::LineBreak);
-        let is_text = matches!(token, Token::Text(_) | Token::Url { .. });
-        let is_before_eof = at + 1 >= tokens.len();
+        // Footnote definitions are written at the end, so their lines are left out, along with
+        // the blank lines after them at the start.
+        if let Token::FootnoteDef { .. } = token {
+            let is_start = tokens[..at]
+                .iter()
+                .all(|t| matches!(t, Token::FootnoteDef { .. } | Token::LineBreak));
+            at += 1;
+            while tokens.get(at).is_some_and(Token::is_line_break) {
+                at += 1;
+                if !is_start {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        let is_unordered_item = matches!(token, Token::ListItem { place: None, .. });
+        let is_ordered_item = matches!(token, Token::ListItem { place: Some(_), .. });
+        let is_line_break = token.is_line_break();
+
+        // Blank lines between the items of a list don't end it, if lists can be loose.
+        if was_line_break && is_line_break && ctx.config.loose_lists {
+            let next = tokens[at..]
+                .iter()
+                .find(|t| !t.is_line_break())
+                .and_then(list_item_kind);
+            if (in_unordered_list && next == Some(bullet))
+                || (in_ordered_list && next == Some(ordered_style))
+            {
+                at += 1;
+                continue;
+            }
+        }
 
         // TODO: break this down further
-        if !in_unordered_list && is_unordered_item {
-            buf.push_str("<ul>\n");
-            in_unordered_list = true;
-        } else if (was_line_break || !is_line_break)
-            && (!is_unordered_item || is_line_break)
-            && in_unordered_list
+        // A list with a different bullet starts a new list.
+        let item_bullet = list_item_kind(token).filter(|_| is_unordered_item);
+        if in_unordered_list
+            && (((was_line_break || !is_line_break) && (!is_unordered_item || is_line_break))
+                || item_bullet.is_some_and(|item_bullet| item_bullet != bullet))
         {
-            buf.push_str("</ul>\n");
+            buf.end_tag("ul");
+            buf.write_char('\n').unwrap();
             in_unordered_list = false;
         }
+        if let Some(item_bullet) = item_bullet.filter(|_| !in_unordered_list) {
+            buf.start_tag("ul", &[]);
+            buf.write_char('\n').unwrap();
+            in_unordered_list = true;
+            bullet = item_bullet;
+            ctx.loose_list = ctx.config.loose_lists && is_loose_list(tokens, at);
+        }
 
-        // TODO: break this down further
-        if !in_ordered_list && is_ordered_item {
-            buf.push_str("<ol>\n");
-            in_ordered_list = true;
-        } else if (was_line_break || !is_line_break)
-            && (!is_ordered_item || is_line_break)
-            && in_ordered_list
+        // A list numbered differently starts a new list.
+        let style = list_item_kind(token).filter(|_| is_ordered_item);
+        if in_ordered_list
+            && (((was_line_break || !is_line_break) && (!is_ordered_item || is_line_break))
+                || style.is_some_and(|style| style != ordered_style))
         {
-            buf.push_str("</ol>\n");
+            buf.end_tag("ol");
+            buf.write_char('\n').unwrap();
             in_ordered_list = false;
         }
-
-        if in_paragraph {
-            if is_before_eof {
-                if !is_line_break {
-                    at = write_token_as_html(buf, tokens, at);
-                }
-                buf.push_str("</p>");
-                if is_line_break {
-                    at = write_token_as_html(buf, tokens, at);
-                }
-                in_paragraph = false;
-            } else if !is_text
-                && is_line_break
-                && matches!(tokens.get(at + 1).unwrap(), Token::LineBreak)
-            {
-                buf.push_str("</p>");
-                in_paragraph = false;
+        if let Some(style) = style.filter(|_| !in_ordered_list) {
+            if style == '1' {
+                buf.start_tag("ol", &[]);
             } else {
-                at = write_token_as_html(buf, tokens, at);
+                buf.start_tag("ol", &[("type", style.encode_utf8(&mut [0; 4]))]);
             }
-        } else if is_text {
-            buf.push_str("<p>");
-            in_paragraph = true;
-        } else {
-            at = write_token_as_html(buf, tokens, at);
+            buf.write_char('\n').unwrap();
+            in_ordered_list = true;
+            ordered_style = style;
+            ctx.loose_list = ctx.config.loose_lists && is_loose_list(tokens, at);
         }
 
+        at = write_token_as_html(buf, tokens, at, ctx);
         was_line_break = is_line_break;
     }
+
+    // Lists at the end are closed on their own line, like the ones ended by a line break.
+    if (in_unordered_list || in_ordered_list) && !was_line_break {
+        buf.write_char('\n').unwrap();
+    }
+    if in_unordered_list {
+        buf.end_tag("ul");
+    }
+    if in_ordered_list {
+        buf.end_tag("ol");
+    }
+    ctx.loose_list = outer_loose_list;
 }
 
-fn write_text<W: Write>(buf: &mut W, t: &Text) {
+/// Returns the kind of list the token belongs to, or `None` if it isn't a list item.
+///
+/// The kind is the bullet for unordered lists (`-`, `*` or `+`), and the numbering style for
+/// ordered lists, ie. `1`, `a`, `A`, `i` or `I` like the `type` of an `<ol>`.
+fn list_item_kind<Custom>(token: &Token<'_, Custom>) -> Option<char> {
+    match token {
+        Token::ListItem {
+            place: None,
+            marker,
+        } => Some(*marker),
+        Token::ListItem { marker, .. } => match marker {
+            'a' | 'A' | 'i' | 'I' => Some(*marker),
+            _ => Some('1'),
+        },
+        _ => None,
+    }
+}
+
+/// Returns `true` if the list starting with the item at `at` is loose, ie. if any of its items
+/// has a blank line in it or its items are separated by blank lines.
+fn is_loose_list<Custom>(tokens: &[Token<'_, Custom>], mut at: usize) -> bool {
+    let kind = list_item_kind(&tokens[at]);
+    loop {
+        let end = list_item_end(tokens, at);
+        if tokens[at + 1..end]
+            .windows(2)
+            .any(|w| matches!(w, [Token::LineBreak, Token::LineBreak]))
+        {
+            return true;
+        }
+
+        let breaks = tokens[end..]
+            .iter()
+            .take_while(|t| t.is_line_break())
+            .count();
+        let next = end + breaks;
+        if tokens.get(next).and_then(list_item_kind) != kind {
+            return false;
+        } else if breaks > 1 {
+            return true;
+        }
+        at = next;
+    }
+}
+
+fn write_text<W: HtmlWrite, Custom: Clone>(buf: &mut W, t: &Text, ctx: &mut Context<Custom>) {
     let Text {
         value,
         bold,
         italic,
         code,
+        subscript,
+        superscript,
+        highlight,
+        ..
     } = t;
 
-    let (bold_s, bold_e) = bold.then(|| ("<b>", "</b>")).unwrap_or_default();
-    let (italic_s, italic_e) = italic.then(|| ("<i>", "</i>")).unwrap_or_default();
-    let (code_s, code_e) = code.then(|| ("<code>", "</code>")).unwrap_or_default();
+    let (bold_tag, italic_tag) = if ctx.config.semantic_emphasis {
+        ("strong", "em")
+    } else {
+        ("b", "i")
+    };
+    let script_tag = if *subscript {
+        Some("sub")
+    } else {
+        superscript.then_some("sup")
+    };
+    let tags = [
+        script_tag,
+        highlight.then_some("mark"),
+        code.then_some("code"),
+        bold.then_some(bold_tag),
+        italic.then_some(italic_tag),
+    ];
 
-    write!(
-        buf,
-        "{}{}{}{}{}{}{} ",
-        code_s, bold_s, italic_s, value, italic_e, bold_e, code_e
-    )
-    .unwrap()
+    tags.iter()
+        .flatten()
+        .for_each(|tag| buf.start_tag(tag, &[]));
+    match t.parse_nested() {
+        Some(nested) => {
+            let mut at = 0;
+            while at < nested.len() {
+                at = write_token_as_html(buf, &nested, at, ctx);
+            }
+        }
+        None if *code => write_escaped(buf, value).unwrap(),
+        None => write_escaped_text(buf, value.trim_end()),
+    }
+    tags.iter().rev().flatten().for_each(|tag| buf.end_tag(tag));
 }
 
-fn write_until_line_break<W: Write>(buf: &mut W, tokens: &[Token<()>], mut at: usize) -> usize {
+fn write_until_line_break<W: HtmlWrite, Custom: Clone>(
+    buf: &mut W,
+    tokens: &[Token<Custom>],
+    mut at: usize,
+    ctx: &mut Context<Custom>,
+) -> usize {
     while at < tokens.len() {
-        if matches!(&tokens[at], Token::LineBreak) {
+        if tokens[at].is_line_break() {
             break;
         }
-        at = write_token_as_html(buf, tokens, at);
+        at = write_token_as_html(buf, tokens, at, ctx);
     }
     at
 }
 
-fn write_token_as_html<W: Write>(buf: &mut W, tokens: &[Token<()>], mut at: usize) -> usize {
+fn write_token_as_html<W: HtmlWrite, Custom: Clone>(
+    buf: &mut W,
+    tokens: &[Token<Custom>],
+    mut at: usize,
+    ctx: &mut Context<Custom>,
+) -> usize {
     match &tokens[at] {
-        Token::Text(t) => write_text(buf, t),
-        Token::CodeFence { code, attrs: _ } => {
-            write!(buf, "<pre><code>{}</code></pre>", code).unwrap()
+        Token::Text(t) => write_text(buf, t, ctx),
+        Token::CodeFence { code, attrs } => {
+            let lang = attrs.split(',').next().unwrap_or_default().trim();
+            if ctx.config.code_block_wrapper {
+                if lang.is_empty() {
+                    buf.start_tag("div", &[("class", "code-block")]);
+                } else {
+                    buf.start_tag("div", &[("class", "code-block"), ("data-lang", lang)]);
+                }
+            }
+            buf.start_tag("pre", &[]);
+            if lang.is_empty() {
+                buf.start_tag("code", &[]);
+            } else {
+                buf.start_tag("code", &[("class", &format!("language-{}", lang))]);
+            }
+            let code = match ctx.config.code_tab_width {
+                Some(width) if code.contains('\t') => Cow::Owned(expand_tabs(code, width)),
+                _ => Cow::Borrowed(code.as_ref()),
+            };
+            buf.write_str(&(ctx.config.highlighter)(&code, lang))
+                .unwrap();
+            buf.end_tag("code");
+            buf.end_tag("pre");
+            if ctx.config.code_block_wrapper {
+                buf.end_tag("div");
+            }
         }
         Token::Header(depth) => {
-            write!(buf, "<h{}>", depth).unwrap();
+            let tag = format!("h{}", (*depth).clamp(1, 6));
+            if ctx.config.header_ids {
+                let (text, _) = line_text(tokens, at + 1);
+                let id = ctx.slugs.unique(slugify(&text));
+                buf.start_tag(&tag, &[("id", &id)]);
+            } else {
+                buf.start_tag(&tag, &[]);
+            }
             at += 1;
-            at = write_until_line_break(buf, tokens, at);
-            write!(buf, "</h{}>", depth).unwrap();
+            at = write_until_line_break(buf, tokens, at, ctx);
+            buf.end_tag(&tag);
             return at;
         }
         Token::Url {
             name,
             url,
+            title,
             is_image,
+            width,
+            height,
         } => {
-            if *is_image {
-                write!(buf, r#"<img src="{}" alt=""#, url).unwrap();
-                if let Some(t) = name {
-                    write_text(buf, t);
-                } else {
-                    buf.write_str(url).unwrap();
+            let resolved = match ctx.config.base_url {
+                Some(base) if url.is_empty().not() && url_scheme(url).is_none() => {
+                    Cow::Owned(resolve_url(base, url))
                 }
-                buf.write_str(r#"">"#).unwrap()
+                _ => Cow::Borrowed(url.as_ref()),
+            };
+            let href = sanitize_url(&resolved, *is_image, ctx.config);
+            if *is_image {
+                let (width, height) = (
+                    width.map(|w| format!("{}", w)),
+                    height.map(|h| format!("{}", h)),
+                );
+                let mut attrs = vec![
+                    ("src", href),
+                    ("alt", name.as_ref().map_or(url.as_ref(), |t| t.value)),
+                ];
+                attrs.extend(width.as_deref().map(|width| ("width", width)));
+                attrs.extend(height.as_deref().map(|height| ("height", height)));
+                attrs.extend(title.map(|title| ("title", title)));
+                buf.start_tag("img", &attrs);
             } else {
-                write!(buf, r#"<a href="{}">"#, url).unwrap();
+                let www = www_autolink(name.as_ref(), href);
+                let href = match (ctx.config.default_scheme, ctx.config.base_url, www) {
+                    (Some(default), _, Some(www)) => Cow::Owned(format!("{}://{}", default, www)),
+                    (Some(default), None, _) if needs_default_scheme(href) => {
+                        Cow::Owned(format!("{}://{}", default, href))
+                    }
+                    _ => Cow::Borrowed(href),
+                };
+                let mut attrs = vec![("href", href.as_ref())];
+                attrs.extend(title.map(|title| ("title", title)));
+                buf.start_tag("a", &attrs);
                 if let Some(t) = name {
-                    write_text(buf, t);
+                    write_link_name(buf, t, ctx);
                 } else {
-                    buf.write_str(url).unwrap();
+                    write_escaped_text(buf, url);
                 }
-                buf.write_str("</a>").unwrap()
+                buf.end_tag("a");
             }
         }
-        Token::ListItem(place) => {
+        Token::ListItem { place, .. } => {
             if let Some(place) = place {
-                write!(buf, "<li value=\"{}\">", place).unwrap();
+                buf.start_tag("li", &[("value", &format!("{}", place))]);
             } else {
-                buf.write_str("<li>").unwrap();
+                buf.start_tag("li", &[]);
             }
-            at += 1;
-            at = write_until_line_break(buf, tokens, at);
-            buf.write_str("</li>").unwrap();
-            return at;
+            let end = list_item_end(tokens, at);
+            let content = &tokens[at + 1..end];
+            // The content of items in a loose list is put in paragraphs.
+            let is_loose = ctx.loose_list
+                || content
+                    .windows(2)
+                    .any(|w| matches!(w, [Token::LineBreak, Token::LineBreak]));
+            if is_loose {
+                let content = mark_paragraphs(content);
+                let mut at = 0;
+                while at < content.len() {
+                    at = write_token_as_html(buf, &content, at, ctx);
+                }
+            } else {
+                let mut at = 0;
+                while at < content.len() {
+                    at = write_token_as_html(buf, content, at, ctx);
+                }
+            }
+            buf.end_tag("li");
+            return end;
+        }
+        Token::Math { content, display } => {
+            let (tag, class, start, end) = if *display {
+                ("div", "math display", r"\[", r"\]")
+            } else {
+                ("span", "math inline", r"\(", r"\)")
+            };
+            buf.start_tag(tag, &[("class", class)]);
+            buf.write_str(start).unwrap();
+            write_escaped(buf, content).unwrap();
+            buf.write_str(end).unwrap();
+            buf.end_tag(tag);
+        }
+        Token::FootnoteRef(id) => match ctx.footnotes.iter().position(|(def_id, _)| def_id == id) {
+            Some(index) => {
+                buf.start_tag("sup", &[]);
+                let (href, ref_id) = (format!("#fn-{}", id), format!("fnref-{}", id));
+                buf.start_tag("a", &[("href", &href), ("id", &ref_id)]);
+                write!(buf, "{}", index + 1).unwrap();
+                buf.end_tag("a");
+                buf.end_tag("sup");
+            }
+            None => {
+                write!(buf, "[^").unwrap();
+                write_escaped(buf, id).unwrap();
+                buf.write_char(']').unwrap()
+            }
+        },
+        Token::FootnoteDef { .. } => {}
+        Token::Details { summary, body } => {
+            buf.start_tag("details", &[]);
+            buf.write_char('\n').unwrap();
+            buf.start_tag("summary", &[]);
+            write_text(buf, summary, ctx);
+            buf.end_tag("summary");
+            buf.write_char('\n').unwrap();
+            write_blocks(buf, body, ctx);
+            buf.write_char('\n').unwrap();
+            buf.end_tag("details");
         }
-        Token::LineBreak => buf.write_char('\n').unwrap(),
-        Token::Custom(_) => {}
+        Token::Alert { kind, body } => {
+            buf.start_tag("div", &[("class", &format!("alert alert-{}", kind.name()))]);
+            buf.write_char('\n').unwrap();
+            buf.start_tag("p", &[("class", "alert-title")]);
+            buf.write_str(kind.title()).unwrap();
+            buf.end_tag("p");
+            buf.write_char('\n').unwrap();
+            write_blocks(buf, body, ctx);
+            buf.write_char('\n').unwrap();
+            buf.end_tag("div");
+        }
+        Token::DefinitionList(definitions) => {
+            buf.start_tag("dl", &[]);
+            buf.write_char('\n').unwrap();
+            for Definition { term, descriptions } in definitions {
+                buf.start_tag("dt", &[]);
+                write_inline(buf, term, ctx);
+                buf.end_tag("dt");
+                buf.write_char('\n').unwrap();
+                for description in descriptions {
+                    buf.start_tag("dd", &[]);
+                    write_inline(buf, description, ctx);
+                    buf.end_tag("dd");
+                    buf.write_char('\n').unwrap();
+                }
+            }
+            buf.end_tag("dl");
+        }
+        Token::Html(html) => {
+            if ctx.config.raw_html {
+                buf.write_str(html).unwrap()
+            }
+        }
+        Token::Comment(comment) => {
+            if ctx.config.keep_comments {
+                // `needs_space` skips comments, so put back the space before them here
+                let after_space = at > 0
+                    && matches!(&tokens[at - 1], Token::Text(t) if t.value.ends_with(char::is_whitespace))
+                    && !needs_space(tokens, at - 1);
+                if after_space {
+                    buf.write_char(' ').unwrap();
+                }
+                write!(buf, "<!--{}-->", comment).unwrap()
+            }
+        }
+        Token::ListContinuation | Token::FrontMatter { .. } => {}
+        Token::LineBreak => {
+            let is_soft = at > 0
+                && tokens[at - 1].is_inline()
+                && tokens.get(at + 1).is_some_and(Token::is_inline);
+            match ctx.config.soft_break {
+                SoftBreak::Space if is_soft => buf.write_char(' ').unwrap(),
+                SoftBreak::Br if is_soft => {
+                    buf.start_tag("br", &[]);
+                    buf.write_char('\n').unwrap()
+                }
+                _ => buf.write_char('\n').unwrap(),
+            }
+        }
+        Token::ParagraphStart => {
+            if let Some(tag) = ctx.config.paragraph_tag {
+                let (name, attrs) = split_tag(tag);
+                buf.start_tag(name, &attrs);
+            }
+        }
+        Token::ParagraphEnd => {
+            if let Some(tag) = ctx.config.paragraph_tag {
+                buf.end_tag(split_tag(tag).0);
+            }
+        }
+        Token::Custom(custom) => (ctx.render_custom)(custom, buf),
+    }
+    if needs_space(tokens, at) {
+        buf.write_char(' ').unwrap();
     }
     at + 1
 }
+
+/// Returns `true` if the URL should get the default scheme, ie. if it doesn't have a scheme
+/// and isn't an anchor or a relative path. See [`Config::default_scheme`].
+fn needs_default_scheme(url: &str) -> bool {
+    !url.is_empty() && !url.starts_with(['#', '/', '.', '?']) && url_scheme(url).is_none()
+}
+
+/// Returns the URL of a `www.` autolink without the `http://` scheme it got when parsed, or
+/// `None` if the link isn't one. See [`Config::default_scheme`].
+fn www_autolink<'u>(name: Option<&Text>, url: &'u str) -> Option<&'u str> {
+    url.strip_prefix("http://")
+        .filter(|www| www.starts_with("www.") && name == Some(&Text::naked(www)))
+}
+
+/// Replaces the tabs in code with spaces up to the next multiple of `width` columns.
+fn expand_tabs(code: &str, width: usize) -> String {
+    let mut expanded = String::with_capacity(code.len());
+    let mut column = 0;
+    for c in code.chars() {
+        match c {
+            '\t' => {
+                let spaces = width - column % width.max(1);
+                expanded.extend(core::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            c => {
+                expanded.push(c);
+                column += 1;
+            }
+        }
+    }
+    expanded
+}
+
+/// Resolves a URL without a scheme against a base URL, see [`Config::base_url`].
+fn resolve_url(base: &str, url: &str) -> String {
+    let scheme_end = base.find("://").map_or(0, |at| at + 3);
+    let origin_end = base[scheme_end..]
+        .find(['/', '?', '#'])
+        .map_or(base.len(), |at| scheme_end + at);
+    let (origin, path) = base.split_at(origin_end);
+    let path = &path[..path.find(['?', '#']).unwrap_or(path.len())];
+
+    if let Some(url) = url.strip_prefix("//") {
+        return format!("{}{}", &base[..scheme_end], url);
+    }
+    if url.starts_with('/') {
+        return format!("{}{}", origin, url);
+    }
+    if url.starts_with('#') {
+        return format!("{}{}", &base[..base.find('#').unwrap_or(base.len())], url);
+    }
+    if url.starts_with('?') {
+        return format!("{}{}{}", origin, path, url);
+    }
+
+    // Relative paths are relative to the "directory" of the base, ie. up to its last `/`.
+    let mut dir = &path[..path.rfind('/').map_or(0, |at| at + 1)];
+    let mut url = url;
+    loop {
+        if let Some(rest) = url.strip_prefix("./") {
+            url = rest;
+        } else if let Some(rest) = url.strip_prefix("../") {
+            url = rest;
+            let parent = dir.trim_end_matches('/');
+            dir = &dir[..parent.rfind('/').map_or(0, |at| at + 1)];
+        } else {
+            break;
+        }
+    }
+    let slash = if dir.is_empty() { "/" } else { "" };
+    format!("{}{}{}{}", origin, slash, dir, url)
+}
+
+/// Returns the URL if its scheme is allowed, or `#` otherwise.
+fn sanitize_url<'u>(url: &'u str, is_image: bool, config: &Config) -> &'u str {
+    // Browsers ignore these, so `java\tscript:` is still `javascript:`.
+    let cleaned = url
+        .trim_start_matches(|c: char| c.is_whitespace() || c.is_control())
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect::<String>();
+    match url_scheme(&cleaned) {
+        None => url,
+        Some(scheme)
+            if config
+                .schemes
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(scheme)) =>
+        {
+            url
+        }
+        Some(scheme)
+            if is_image
+                && scheme.eq_ignore_ascii_case("data")
+                && cleaned[5..].to_ascii_lowercase().starts_with("image/") =>
+        {
+            url
+        }
+        Some(_) => "#",
+    }
+}
+
+/// Splits a tag with attributes (like `div class="paragraph"`) in its name and attributes,
+/// see [`Config::paragraph_tag`]. Attributes without a value get an empty one.
+fn split_tag(tag: &str) -> (&str, Vec<(&str, &str)>) {
+    let (name, mut rest) = tag.trim().split_once(' ').unwrap_or((tag.trim(), ""));
+    let mut attrs = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let name_len = rest.find([' ', '=']).unwrap_or(rest.len());
+        if name_len == 0 {
+            break;
+        }
+        let (attr, after) = rest.split_at(name_len);
+        let value = after
+            .strip_prefix("=\"")
+            .and_then(|value| value.split_once('"'));
+        match value {
+            Some((value, next)) => {
+                attrs.push((attr, value));
+                rest = next;
+            }
+            None => {
+                attrs.push((attr, ""));
+                rest = after;
+            }
+        }
+    }
+    (name, attrs)
+}
+
+/// Writes a string nested in a token (like a definition list term) as inline tokens.
+fn write_inline<W: HtmlWrite, Custom: Clone>(buf: &mut W, s: &str, ctx: &mut Context<Custom>) {
+    let mut tokens = Vec::new();
+    let config = ParserConfig::default();
+    let mut at = 0;
+    while let Some((token, nat)) = s.parse_inline_token(at, &config) {
+        tokens.push(token);
+        at = nat;
+    }
+    write_until_line_break(buf, &tokens, 0, ctx);
+}
+
+/// Writes the name of a link. Naked names are parsed for inline markup, like code or images.
+fn write_link_name<W: HtmlWrite, Custom: Clone>(
+    buf: &mut W,
+    name: &Text,
+    ctx: &mut Context<Custom>,
+) {
+    let naked = Text {
+        images_as_text: name.images_as_text,
+        ..Text::naked(name.value)
+    };
+    if name != &naked {
+        return write_text(buf, name, ctx);
+    }
+    let config = ParserConfig::default().images(name.images_as_text.not());
+    let mut tokens = Vec::new();
+    let mut at = 0;
+    while let Some((token, nat)) = name.value.parse_inline_token(at, &config) {
+        // Links can't contain links, so links in the name are written as their text.
+        tokens.push(match token {
+            Token::Url {
+                name: Some(name),
+                is_image: false,
+                ..
+            } => Token::Text(name),
+            Token::Url {
+                url: Cow::Borrowed(url),
+                is_image: false,
+                ..
+            } => Text::naked(url).into_token(),
+            token => token,
+        });
+        at = nat;
+    }
+    write_until_line_break(buf, &tokens, 0, ctx);
+}
+
+fn write_footnotes<W: HtmlWrite, Custom: Clone>(buf: &mut W, ctx: &mut Context<Custom>) {
+    if ctx.footnotes.is_empty() {
+        return;
+    }
+    buf.write_char('\n').unwrap();
+    buf.start_tag("section", &[("class", "footnotes")]);
+    buf.write_char('\n').unwrap();
+    buf.start_tag("ol", &[]);
+    buf.write_char('\n').unwrap();
+    // Footnotes can refer to each other, so the definitions are kept while writing them.
+    for index in 0..ctx.footnotes.len() {
+        let (id, body) = ctx.footnotes[index];
+        buf.start_tag("li", &[("id", &format!("fn-{}", id))]);
+        write_until_line_break(buf, &body.parse_md_custom(&[]), 0, ctx);
+        buf.start_tag("a", &[("href", &format!("#fnref-{}", id))]);
+        buf.write_str("↩").unwrap();
+        buf.end_tag("a");
+        buf.end_tag("li");
+        buf.write_char('\n').unwrap();
+    }
+    buf.end_tag("ol");
+    buf.write_char('\n').unwrap();
+    buf.end_tag("section");
+}
+
+/// Writes text escaped, except for the entity references in it (like `&amp;` or `&#64;`), which
+/// authors write to get the character they stand for.
+fn write_escaped_text<W: Write>(buf: &mut W, text: &str) {
+    let mut rest = text;
+    while let Some(at) = rest.find(['&', '<', '>']) {
+        buf.write_str(&rest[..at]).unwrap();
+        let entity_len = entity_len(&rest[at..]);
+        match &rest[at..at + 1] {
+            "&" if entity_len > 0 => buf.write_str(&rest[at..at + entity_len]).unwrap(),
+            "&" => buf.write_str("&amp;").unwrap(),
+            "<" => buf.write_str("&lt;").unwrap(),
+            _ => buf.write_str("&gt;").unwrap(),
+        }
+        rest = &rest[at + entity_len.max(1)..];
+    }
+    buf.write_str(rest).unwrap()
+}
+
+/// Returns the length of the entity reference at the start of the text (ie. `&name;`, `&#64;`
+/// or `&#x40;`), or zero if there isn't one.
+fn entity_len(text: &str) -> usize {
+    let Some(rest) = text.strip_prefix('&') else {
+        return 0;
+    };
+    let (digits, max_len, is_digit): (_, _, fn(&char) -> bool) = match rest.strip_prefix('#') {
+        Some(rest) => match rest.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex, 6, char::is_ascii_hexdigit),
+            None => (rest, 7, char::is_ascii_digit),
+        },
+        None => (rest, 32, char::is_ascii_alphanumeric),
+    };
+    let len = digits.chars().take_while(is_digit).count();
+    let is_name = digits.len() == rest.len();
+    let is_entity = (1..=max_len).contains(&len)
+        && digits[len..].starts_with(';')
+        && (!is_name || digits.starts_with(|c: char| c.is_ascii_alphabetic()));
+    if is_entity {
+        text.len() - digits.len() + len + 1
+    } else {
+        0
+    }
+}
+
+fn escape_code(code: &str, _lang: &str) -> String {
+    escape(code)
+}
+
+/// Escapes the characters that are special in HTML (`&`, `<`, `>`, `"` and `'`), so the
+/// input can be put in text or in a quoted attribute.
+///
+/// This is useful for rendering custom tokens; see [`render_as_html_custom`].
+///
+/// # Example
+/// ```
+/// # use linemd::html::escape;
+/// assert_eq!(escape("<a href=\"#\">"), "&lt;a href=&quot;#&quot;&gt;");
+/// ```
+pub fn escape(input: &str) -> String {
+    let mut buf = String::with_capacity(input.len());
+    write_escaped(&mut buf, input).unwrap();
+    buf
+}
+
+/// Writes the input escaped to a buffer. See [`escape`].
+pub fn write_escaped<W: Write + ?Sized>(buf: &mut W, input: &str) -> core::fmt::Result {
+    for c in input.chars() {
+        match c {
+            '&' => buf.write_str("&amp;"),
+            '<' => buf.write_str("&lt;"),
+            '>' => buf.write_str("&gt;"),
+            '"' => buf.write_str("&quot;"),
+            '\'' => buf.write_str("&#39;"),
+            c => buf.write_char(c),
+        }?
+    }
+    Ok(())
+}
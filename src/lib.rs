@@ -4,25 +4,60 @@
 extern crate alloc;
 use alloc::{string::String, vec::Vec};
 
+/// Markdown documents that own their source.
+pub mod document;
+/// Link and image extraction from tokens.
+pub mod links;
 /// Parser types used to parse markdown.
 pub mod parser;
+/// Plain text rendering of tokens.
+pub mod plain;
+/// Slack `mrkdwn` rendering of tokens.
+pub mod slack;
+/// Word count and reading time estimation from tokens.
+pub mod stats;
 #[cfg(test)]
 mod tests;
+/// Table of contents generation from tokens.
+pub mod toc;
+/// Visiting and folding over tokens.
+pub mod visit;
+/// Wrapping text into lines of a width.
+pub mod wrap;
 
+/// Emoji shortcode parsing.
+#[cfg(feature = "emoji")]
+pub mod emoji;
 /// HTML rendering of tokens.
 #[cfg(feature = "html")]
 pub mod html;
 /// SVG rendering of tokens.
 #[cfg(feature = "svg")]
 pub mod svg;
+/// Functions exported to JavaScript, for using linemd from WebAssembly.
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 #[doc(inline)]
-pub use parser::Parser;
+pub use document::Document;
+#[doc(inline)]
+pub use parser::{Config as ParserConfig, Parser};
+#[doc(inline)]
+pub use plain::{render_as_plain, render_preview};
+#[doc(inline)]
+pub use slack::render_as_slack;
+#[doc(inline)]
+pub use toc::{generate_toc, TocEntry};
+#[doc(inline)]
+pub use wrap::{display_width, wrap_text};
 
 #[cfg(feature = "svg")]
 #[doc(inline)]
-pub use svg::{render_as_svg, Config as SvgConfig, ViewportDimensions as SvgViewportDimensions};
+pub use svg::{
+    render_as_svg, Config as SvgConfig, TextAnchor as SvgTextAnchor,
+    ViewportDimensions as SvgViewportDimensions,
+};
 
 #[cfg(feature = "html")]
 #[doc(inline)]
-pub use html::render_as_html;
+pub use html::{render_as_html, render_as_html_with_config, Config as HtmlConfig};
@@ -0,0 +1,121 @@
+use crate::{
+    parser::{url_scheme, Text, Token},
+    visit::{walk, Visitor},
+};
+
+use super::*;
+use alloc::borrow::Cow;
+
+/// An image found in tokens, see [`images`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ImageRef<'a> {
+    /// URL of the image.
+    pub url: Cow<'a, str>,
+    /// Alt text of the image, unparsed. `None` if the image has no alt text.
+    pub alt: Option<&'a str>,
+}
+
+/// A link found in tokens, see [`links`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LinkRef<'a> {
+    /// URL of the link. Email and `www.` autolinks have the scheme they are linked with, see
+    /// [`Token::Url`].
+    pub url: Cow<'a, str>,
+    /// Name of the link, unparsed. `None` for autolinks (ie. `<https://example.org>`), except for
+    /// email and `www.` autolinks, which keep the address as written.
+    pub name: Option<&'a str>,
+    /// `true` if the link points to another site, ie. its URL has a scheme (like `https:`) or a
+    /// host (like `//example.org`).
+    pub is_external: bool,
+}
+
+/// Collects the links (but not images) in parsed tokens, in the order they appear.
+///
+/// Links in the bodies of details sections are collected too, but links nested in emphasis
+/// (ie. `**see [this](url)**`) aren't, since they are only parsed when the text is rendered.
+/// See [`Text::parse_nested`].
+///
+/// # Example
+/// ```
+/// # use linemd::{links::links, Parser};
+/// let tokens = "[Home](/) and [linemd](https://github.com/yusdacra/linemd)".parse_md();
+/// let external = links(&tokens)
+///     .into_iter()
+///     .filter(|link| link.is_external)
+///     .map(|link| link.url)
+///     .collect::<Vec<_>>();
+/// assert_eq!(external, ["https://github.com/yusdacra/linemd"]);
+/// ```
+pub fn links<'a, Custom>(tokens: &[Token<'a, Custom>]) -> Vec<LinkRef<'a>> {
+    struct Links<'a>(Vec<LinkRef<'a>>);
+
+    impl<'a, Custom> Visitor<'a, Custom> for Links<'a> {
+        fn visit_url(
+            &mut self,
+            name: Option<&Text<'a>>,
+            url: Cow<'a, str>,
+            _title: Option<&'a str>,
+            is_image: bool,
+            _width: Option<usize>,
+            _height: Option<usize>,
+        ) {
+            if !is_image {
+                self.0.push(LinkRef {
+                    is_external: is_external(&url),
+                    url,
+                    name: name.map(|t| t.value),
+                });
+            }
+        }
+    }
+
+    let mut links = Links(Vec::new());
+    walk(tokens, &mut links);
+    links.0
+}
+
+/// Returns `true` if the URL has a scheme or a host.
+fn is_external(url: &str) -> bool {
+    let url = url.trim();
+    url_scheme(url).is_some() || url.starts_with("//")
+}
+
+/// Collects the images in parsed tokens, in the order they appear.
+///
+/// Images in the bodies of details sections are collected too, but images nested in emphasis
+/// aren't, like in [`links`].
+///
+/// # Example
+/// ```
+/// # use linemd::{links::{images, ImageRef}, Parser};
+/// let tokens = "![A cat](cat.png) and ![](dog.png)".parse_md();
+/// let missing_alt = images(&tokens)
+///     .into_iter()
+///     .filter(|image| image.alt.is_none())
+///     .collect::<Vec<_>>();
+/// assert_eq!(missing_alt, [ImageRef { url: "dog.png".into(), alt: None }]);
+/// ```
+pub fn images<'a, Custom>(tokens: &[Token<'a, Custom>]) -> Vec<ImageRef<'a>> {
+    struct Images<'a>(Vec<ImageRef<'a>>);
+
+    impl<'a, Custom> Visitor<'a, Custom> for Images<'a> {
+        fn visit_url(
+            &mut self,
+            name: Option<&Text<'a>>,
+            url: Cow<'a, str>,
+            _title: Option<&'a str>,
+            is_image: bool,
+            _width: Option<usize>,
+            _height: Option<usize>,
+        ) {
+            if is_image {
+                let alt = name.map(|t| t.value).filter(|alt| !alt.trim().is_empty());
+                self.0.push(ImageRef { url, alt });
+            }
+        }
+    }
+
+    let mut images = Images(Vec::new());
+    walk(tokens, &mut images);
+    images.0
+}
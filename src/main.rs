@@ -1,43 +1,114 @@
-use linemd::{render_as_html, render_as_svg, Parser, SvgConfig};
+use linemd::{html, render_as_html, render_as_svg, toc, Parser, SvgConfig, SvgViewportDimensions};
 use std::io::{prelude::*, stdin};
 
 const HELP_TEXT: &str = include_str!("help.txt");
 
+#[derive(Default)]
+struct Args {
+    help: bool,
+    svg: bool,
+    standalone: bool,
+    input: Option<String>,
+    output: Option<String>,
+    width: Option<u32>,
+    font_family: Option<String>,
+    font_size: Option<String>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut parsed = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "-S" | "--svg" => parsed.svg = true,
+                "-s" | "--standalone" => parsed.standalone = true,
+                "-o" | "--output" => parsed.output = Some(expect_value(&arg, args.next())),
+                "--width" => {
+                    let value = expect_value(&arg, args.next());
+                    match value.parse() {
+                        Ok(width) => parsed.width = Some(width),
+                        Err(err) => {
+                            eprintln!("invalid value '{}' for '{}': {}", value, arg, err);
+                            std::process::exit(3);
+                        }
+                    }
+                }
+                "--font-family" => parsed.font_family = Some(expect_value(&arg, args.next())),
+                "--font-size" => parsed.font_size = Some(expect_value(&arg, args.next())),
+                _ => parsed.input = Some(arg),
+            }
+        }
+        parsed
+    }
+
+    fn svg_config(&self) -> SvgConfig<'_> {
+        let mut config = SvgConfig::default();
+        if let Some(width) = self.width {
+            config = config.dimensions(SvgViewportDimensions::OnlyWidth(width));
+        }
+        if let Some(font_family) = &self.font_family {
+            config = config.font_family(font_family);
+        }
+        if let Some(font_size) = &self.font_size {
+            config = config.font_size(font_size);
+        }
+        config
+    }
+}
+
+fn expect_value(arg: &str, value: Option<String>) -> String {
+    value.unwrap_or_else(|| {
+        eprintln!("missing value for '{}'", arg);
+        std::process::exit(3);
+    })
+}
+
 fn main() {
-    let help = std::env::args().any(|s| matches!(s.as_str(), "-h" | "--help"));
-    let read_stdin = std::env::args().last().map_or(false, |s| s == "-");
-    let svg = std::env::args().any(|s| matches!(s.as_str(), "-S" | "--svg"));
+    let args = Args::parse();
 
-    if std::env::args().len() > 1 {
-        let md = if help {
+    let input = match &args.input {
+        Some(input) if !args.help => input,
+        _ => {
             println!("{}", HELP_TEXT);
             return;
-        } else if read_stdin {
-            let mut input = String::new();
-            if let Err(err) = stdin().read_to_string(&mut input) {
-                eprintln!("failed to read from stdin: {}", err);
-                std::process::exit(2);
-            }
-            input
-        } else {
-            let arg = std::env::args().last().unwrap();
-            match std::fs::read_to_string(&arg) {
-                Ok(c) => c,
-                Err(err) => {
-                    eprintln!("failed to read file '{}': {}", arg, err);
-                    std::process::exit(1);
-                }
+        }
+    };
+
+    let md = if input == "-" {
+        let mut input = String::new();
+        if let Err(err) = stdin().read_to_string(&mut input) {
+            eprintln!("failed to read from stdin: {}", err);
+            std::process::exit(2);
+        }
+        input
+    } else {
+        match std::fs::read_to_string(input) {
+            Ok(c) => c,
+            Err(err) => {
+                eprintln!("failed to read file '{}': {}", input, err);
+                std::process::exit(1);
             }
-        };
-
-        let tokens = md.parse_md();
-        let out = if svg {
-            render_as_svg(&tokens, SvgConfig::default())
-        } else {
-            render_as_html(&tokens)
-        };
-        println!("{}", out);
+        }
+    };
+
+    let tokens = md.parse_md();
+    let out = if args.svg {
+        render_as_svg(&tokens, args.svg_config())
+    } else if args.standalone {
+        let title = toc::title(&tokens).unwrap_or_else(|| input.clone());
+        html::render_as_full_html(&tokens, html::Config::default(), &title, None)
     } else {
-        println!("{}", HELP_TEXT);
+        render_as_html(&tokens)
+    };
+    match &args.output {
+        Some(output) => {
+            if let Err(err) = std::fs::write(output, out) {
+                eprintln!("failed to write file '{}': {}", output, err);
+                std::process::exit(4);
+            }
+        }
+        None => println!("{}", out),
     }
 }
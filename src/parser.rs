@@ -1,5 +1,11 @@
 use super::*;
-use core::{ops::Not, slice::SliceIndex};
+use alloc::{borrow::Cow, format};
+use core::{
+    cell::Cell,
+    fmt::{self, Display, Formatter, Write},
+    ops::{Not, Range},
+    slice::SliceIndex,
+};
 
 /// Errors that can occur while parsing.
 #[derive(Debug, Clone, Copy)]
@@ -8,6 +14,55 @@ pub enum ParserError {
     EOF,
 }
 
+/// A 1-based line and column in the source, for reporting positions to users.
+///
+/// Columns are counted in characters, so a tab or a multi-byte character is one column wide.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// The line, starting from 1.
+    pub line: usize,
+    /// The column, starting from 1.
+    pub column: usize,
+}
+
+impl Position {
+    /// Converts a byte offset in `source` into a position.
+    ///
+    /// Offsets past the end of `source` are treated as the end, and offsets inside a
+    /// character as the start of that character. An offset pointing at a `\n` is on the line
+    /// the `\n` ends.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::parser::Position;
+    /// let source = "# Title\nSome *text*";
+    /// assert_eq!(Position::from_offset(source, 13), Position { line: 2, column: 6 });
+    /// ```
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let mut offset = offset.min(source.len());
+        while source.is_char_boundary(offset).not() {
+            offset -= 1;
+        }
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |at| at + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// A warning about markup that was parsed as literal text, see
+/// [`Parser::parse_md_with_diagnostics`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// The byte range of the markup in the input. See [`Position::from_offset`] for getting
+    /// its line and column.
+    pub span: Range<usize>,
+    /// A description of the problem, like "unclosed `*` treated as literal".
+    pub message: &'static str,
+}
+
 /// Convenience type alias that is a tuple of some type and an index.
 pub type AtWith<T> = (T, usize);
 
@@ -20,13 +75,216 @@ pub type AtToken<'a, Custom> = AtWith<Token<'a, Custom>>;
 /// Convenience type alias that is a tuple of a text and an index.
 pub type AtText<'a> = AtWith<Text<'a>>;
 
-type CustomFn<'a, Custom, S> = fn(&'a S, usize) -> Option<AtToken<'a, Custom>>;
+/// Function that parses a custom token at an index, see [`Parser::parse_md_custom`].
+pub type CustomFn<'a, Custom, S> = fn(&'a S, usize) -> Option<AtToken<'a, Custom>>;
+
+/// Block tags that start a raw HTML block, see [`Config::html`].
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Parsing configuration for [`Parser::parse_md_with_config`].
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    html: bool,
+    paragraphs: bool,
+    tab_width: usize,
+    max_header_depth: usize,
+    clamp_headers: bool,
+    max_delimiter_run: usize,
+    max_emphasis_lookahead: usize,
+    max_link_lookahead: usize,
+    max_details_depth: usize,
+    strip_fence_indent: bool,
+    headers: bool,
+    code_fences: bool,
+    images: bool,
+    // Set by `Parser::scan_token`, which only needs the kinds and ends of tokens, so the parts
+    // of tokens that would allocate are left out.
+    scan: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            html: false,
+            paragraphs: false,
+            tab_width: 4,
+            max_header_depth: 6,
+            clamp_headers: false,
+            max_delimiter_run: 32,
+            max_emphasis_lookahead: 128,
+            max_link_lookahead: 1024,
+            max_details_depth: 16,
+            strip_fence_indent: true,
+            headers: true,
+            code_fences: true,
+            images: true,
+            scan: false,
+        }
+    }
+}
+
+impl Config {
+    /// Set whether raw HTML blocks are parsed as [`Token::Html`].
+    ///
+    /// A raw HTML block is a line that starts with a block tag (like `<div>`), and it continues
+    /// until the matching closing tag or a blank line. Since renderers may output these as-is,
+    /// this is disabled by default; don't enable it for untrusted input.
+    pub const fn html(mut self, value: bool) -> Self {
+        self.html = value;
+        self
+    }
+
+    /// Set whether paragraphs are marked with [`Token::ParagraphStart`] and
+    /// [`Token::ParagraphEnd`], see [`mark_paragraphs`].
+    ///
+    /// Disabled by default.
+    pub const fn paragraphs(mut self, value: bool) -> Self {
+        self.paragraphs = value;
+        self
+    }
+
+    /// Set the width of a tab, used to compute the indentation of lines.
+    ///
+    /// A tab advances to the next tab stop (ie. the next multiple of the width), so with the
+    /// default width of 4, `\t`, `  \t` and four spaces are all as wide. Indentation decides
+    /// whether a line continues a list item (2 or more columns) or is indented code (4 or
+    /// more columns).
+    pub const fn tab_width(mut self, value: usize) -> Self {
+        self.tab_width = value;
+        self
+    }
+
+    /// Set the deepest header that is parsed, ie. the maximum number of `#`s. Defaults to 6.
+    ///
+    /// Deeper headers are parsed as text, unless [`clamp_headers`](Config::clamp_headers) is
+    /// enabled. Renderers render headers deeper than 6 like a depth 6 header.
+    pub const fn max_header_depth(mut self, value: usize) -> Self {
+        self.max_header_depth = value;
+        self
+    }
+
+    /// Set whether headers deeper than the max header depth are clamped to it, instead of being
+    /// parsed as text.
+    ///
+    /// Disabled by default.
+    pub const fn clamp_headers(mut self, value: bool) -> Self {
+        self.clamp_headers = value;
+        self
+    }
+
+    /// Set the longest run of `*`s or `_`s that can open or close emphasis. Defaults to 32.
+    ///
+    /// Longer runs are parsed as text, so that untrusted input can't make the parser try to
+    /// match every delimiter of a huge run.
+    pub const fn max_delimiter_run(mut self, value: usize) -> Self {
+        self.max_delimiter_run = value;
+        self
+    }
+
+    /// Set how many delimiter runs are looked at when looking for the end of emphasis.
+    /// Defaults to 128.
+    ///
+    /// If the end isn't found in that many runs, the opening delimiters are parsed as text.
+    /// This bounds the work done for each delimiter, so that untrusted input with lots of
+    /// unclosed delimiters takes linear time to parse.
+    pub const fn max_emphasis_lookahead(mut self, value: usize) -> Self {
+        self.max_emphasis_lookahead = value;
+        self
+    }
+
+    /// Set how many bytes after a `[` are looked at when looking for the end of a link, an
+    /// image or a footnote label. Defaults to 1024.
+    ///
+    /// Longer ones are parsed as text. This bounds the work done for each bracket, so that
+    /// untrusted input with lots of unclosed brackets takes linear time to parse.
+    pub const fn max_link_lookahead(mut self, value: usize) -> Self {
+        self.max_link_lookahead = value;
+        self
+    }
+
+    /// Set how deep collapsible sections can be nested. Defaults to 16.
+    ///
+    /// A section with more sections nested in it is parsed as text. This bounds how many
+    /// sections look for their end at each line, so that untrusted input with lots of unclosed
+    /// sections takes linear time to parse.
+    pub const fn max_details_depth(mut self, value: usize) -> Self {
+        self.max_details_depth = value;
+        self
+    }
+
+    /// Set whether the indentation of an indented code fence is stripped from its lines.
+    ///
+    /// Fences in list items are indented like the item's text, and each line of the code loses
+    /// as much indentation as the opening fence has, like in CommonMark. Enabled by default.
+    pub const fn strip_fence_indent(mut self, value: bool) -> Self {
+        self.strip_fence_indent = value;
+        self
+    }
+
+    /// Set whether headers are parsed. If disabled, header lines are parsed as text.
+    ///
+    /// This and the other toggles are useful for places that should only have some markdown,
+    /// like comment fields. Enabled by default.
+    pub const fn headers(mut self, value: bool) -> Self {
+        self.headers = value;
+        self
+    }
+
+    /// Set whether code fences and indented code are parsed. If disabled, they are parsed as
+    /// text. Inline code is still parsed.
+    ///
+    /// Enabled by default.
+    pub const fn code_fences(mut self, value: bool) -> Self {
+        self.code_fences = value;
+        self
+    }
+
+    /// Set whether images are parsed. If disabled, images (including their `!`) are parsed as
+    /// text, not as links.
+    ///
+    /// Enabled by default.
+    pub const fn images(mut self, value: bool) -> Self {
+        self.images = value;
+        self
+    }
+}
 
 /// The core of this crate. This trait implements markdown parsing, and several utilities.
 ///
 /// Implementing this trait for your own types is very easy, the onyl required methods are `next_char`
 /// and `get_range_str`. You can also provide implementations for other methods if you can include a
 /// more optimized way for your types.
+///
+/// Every offset taken or returned by the methods (like `at`) is a byte offset into the string
+/// returned by `get_range_str(..)`, not a character index. `next_char` used to take a
+/// character index, so implementations of it need to be changed to look up by byte offset.
 pub trait Parser {
     /// Parses self for tokens.
     fn parse_md(&self) -> Vec<Token<'_, ()>> {
@@ -35,193 +293,1501 @@ pub trait Parser {
         tokens
     }
     /// Parses self for tokens, and outputs to a buffer.
+    ///
+    /// The buffer is cleared before parsing, but its capacity is kept, so it can be reused
+    /// across parses.
     fn parse_md_with_buf<'a>(&'a self, buf: &mut Vec<Token<'a, ()>>) {
+        buf.clear();
+        let config = Config::default();
         let mut at = 0;
-        while let Some((token, nat)) = self.parse_token(at, |_, _| None) {
+        while let Some((token, nat)) = self.parse_token_with_config(at, &[], &config) {
             at = nat;
             buf.push(token);
         }
     }
-    /// Parses self for tokens, with a custom token producer.
+    /// Parses self for tokens, allocating them in an arena.
+    ///
+    /// Servers parsing lots of documents can reset the arena between documents, instead of
+    /// allocating a new buffer for each of them. The returned [`Vec`](bumpalo::collections::Vec)
+    /// drops the tokens (some of which own memory outside of the arena, like the bodies of
+    /// sections) when it is dropped, so it should be dropped before resetting the arena.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::Token, Parser};
+    /// let mut arena = bumpalo::Bump::new();
+    /// for md in ["# Title", "Some text."] {
+    ///     let tokens = md.parse_md_in(&arena);
+    ///     assert_eq!(tokens[..], md.parse_md()[..]);
+    ///     drop(tokens);
+    ///     arena.reset();
+    /// }
+    /// ```
+    #[cfg(feature = "bumpalo")]
+    fn parse_md_in<'a, 'arena>(
+        &'a self,
+        arena: &'arena bumpalo::Bump,
+    ) -> bumpalo::collections::Vec<'arena, Token<'a, ()>> {
+        let config = Config::default();
+        let mut tokens = bumpalo::collections::Vec::new_in(arena);
+        let mut at = 0;
+        while let Some((token, nat)) = self.parse_token_with_config(at, &[], &config) {
+            at = nat;
+            tokens.push(token);
+        }
+        tokens
+    }
+    /// Parses self for tokens, using the given configuration.
+    fn parse_md_with_config(&self, config: Config) -> Vec<Token<'_, ()>> {
+        let mut tokens = Vec::new();
+        let mut at = 0;
+        while let Some((token, nat)) = self.parse_token_with_config(at, &[], &config) {
+            at = nat;
+            tokens.push(token);
+        }
+        if config.paragraphs {
+            tokens = mark_paragraphs(&tokens);
+        }
+        tokens
+    }
+    /// Parses self for tokens, and also returns warnings about markup that was parsed as
+    /// literal text.
+    ///
+    /// Unclosed code fences, code spans and emphasis, and malformed links are reported. They are
+    /// still parsed the same way as [`parse_md`](Parser::parse_md) does.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::Parser;
+    /// let (_, diagnostics) = "Some *unclosed text".parse_md_with_diagnostics();
+    /// assert_eq!(diagnostics[0].span, 5..6);
+    /// assert_eq!(diagnostics[0].message, "unclosed `*` treated as literal");
+    /// ```
+    fn parse_md_with_diagnostics(&self) -> (Vec<Token<'_, ()>>, Vec<Diagnostic>) {
+        let config = Config::default();
+        let (mut tokens, mut diagnostics) = (Vec::new(), Vec::new());
+        let mut at = 0;
+        while let Some((token, nat)) = self.parse_token_with_config(at, &[], &config) {
+            if let Token::Text(text) = &token {
+                if text == &Text::naked(text.value) {
+                    self.diagnose_text(nat - text.value.len()..nat, &mut diagnostics);
+                }
+            }
+            at = nat;
+            tokens.push(token);
+        }
+        (tokens, diagnostics)
+    }
+    /// Parses self for tokens, pairing each token with the source it was parsed from.
+    ///
+    /// The source includes the delimiters that are dropped while parsing (like the `**` around
+    /// bold text), and any whitespace the token skipped over, so joining the sources of all
+    /// tokens gives back the whole input. This is useful for source maps, or for editing a document
+    /// without losing its formatting.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::Parser;
+    /// let tokens = "Some **bold**".parse_md_lossless();
+    /// assert_eq!(tokens[1].1, "**bold**");
+    /// assert_eq!(tokens.iter().map(|(_, raw)| *raw).collect::<String>(), "Some **bold**");
+    /// ```
+    fn parse_md_lossless(&self) -> Vec<(Token<'_, ()>, &str)> {
+        let config = Config::default();
+        let mut tokens = Vec::new();
+        let mut at = 0;
+        while let Some((token, nat)) = self.parse_token_with_config(at, &[], &config) {
+            tokens.push((token, self.get_range_str(at..nat)));
+            at = nat;
+        }
+        tokens
+    }
+    /// Reports the delimiters in naked text that should have started some markup.
+    fn diagnose_text(&self, range: Range<usize>, diagnostics: &mut Vec<Diagnostic>) {
+        let mut pos = range.start;
+        while let Some(c) = self.get_range_str(pos..range.end).chars().next() {
+            let rest = self.get_range_str(pos..range.end);
+            let end = pos + rest.len() - rest.trim_start_matches(c).len();
+            let message = match c {
+                '`' if end - pos >= 3 => Some("unclosed code fence treated as literal"),
+                '`' => Some("unclosed code span treated as literal"),
+                '*' | '_' => self
+                    .delimiter_run(pos, c, Config::default().max_delimiter_run)
+                    .filter(|(start, end)| self.delimiter_flanking(*start, *end, c).0)
+                    .map(|_| match c {
+                        '*' => "unclosed `*` treated as literal",
+                        _ => "unclosed `_` treated as literal",
+                    }),
+                '[' => rest
+                    .find(']')
+                    .filter(|len| rest[len + 1..].starts_with('('))
+                    .map(|_| "malformed link treated as literal"),
+                _ => None,
+            };
+            if let Some(message) = message {
+                diagnostics.push(Diagnostic {
+                    span: pos..end,
+                    message,
+                });
+            }
+            pos = end;
+        }
+    }
+    /// Parses self for inline tokens only.
+    ///
+    /// Unlike [`parse_md`](Parser::parse_md), block structure (headers, list items, code fences
+    /// and footnote definitions) is not recognized, so it is suitable for single lines of text,
+    /// like table cells or labels. Line breaks are treated like any other whitespace, so no
+    /// [`Token::LineBreak`] is produced.
+    fn parse_inline(&self) -> Vec<Token<'_, ()>> {
+        let mut tokens = Vec::new();
+        let config = Config::default();
+        let mut at = 0;
+        while let Some((token, nat)) = self.parse_inline_token(at, &config) {
+            at = nat;
+            tokens.push(token);
+        }
+        tokens
+    }
+    /// Returns the text of the first header, without any formatting, to be used as a title.
+    ///
+    /// Returns `None` if there are no headers. See also [`toc::title`](crate::toc::title).
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::Parser;
+    /// assert_eq!("# Hello **World**".title().as_deref(), Some("Hello World"));
+    /// ```
+    fn title(&self) -> Option<String> {
+        crate::toc::title(self.parse_md())
+    }
+    /// Parses self for tokens, with custom token producers.
+    ///
+    /// The custom functions are tried in order wherever a token can start (ie. at the start of a
+    /// line, after another token, or at the start of a word in text), after line breaks but before
+    /// any of the other built-in tokens, so they can't span lines but can override headers.
+    /// The first one that returns a token wins, so several extensions can be combined.
+    /// See `examples/custom.rs` for an example.
     fn parse_md_custom<'a, Custom>(
         &'a self,
-        custom: CustomFn<'a, Custom, Self>,
-    ) -> Vec<Token<'_, Custom>> {
+        customs: &[CustomFn<'a, Custom, Self>],
+    ) -> Vec<Token<'a, Custom>> {
         let mut tokens = Vec::new();
-        self.parse_md_with_buf_custom(&mut tokens, custom);
+        self.parse_md_with_buf_custom(&mut tokens, customs);
         tokens
     }
-    /// Parses self for tokens, and outputs to a buffer, with a custom token producer.
+    /// Parses self for tokens, and outputs to a buffer, with custom token producers.
+    ///
+    /// Like [`parse_md_with_buf`](Parser::parse_md_with_buf), the buffer is cleared before
+    /// parsing, but its capacity is kept.
     fn parse_md_with_buf_custom<'a, Custom>(
         &'a self,
         buf: &mut Vec<Token<'a, Custom>>,
-        custom: CustomFn<'a, Custom, Self>,
+        customs: &[CustomFn<'a, Custom, Self>],
     ) {
+        buf.clear();
+        let config = Config::default();
         let mut at = 0;
-        while let Some((token, nat)) = self.parse_token(at, custom) {
+        while let Some((token, nat)) = self.parse_token_with_config(at, customs, &config) {
             at = nat;
             buf.push(token);
         }
     }
+    /// Parses self for tokens, replacing known `:name:` emoji shortcodes with their emoji.
+    ///
+    /// This is a shorthand for [`parse_md_custom`](Parser::parse_md_custom) with
+    /// [`emoji::parse_emoji`](crate::emoji::parse_emoji). Unknown shortcodes are left as text.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::Text, Parser};
+    /// let tokens = "Released :tada:".parse_md_emoji();
+    /// assert_eq!(tokens[1], Text::naked("\u{1f389}").into_token());
+    /// ```
+    #[cfg(feature = "emoji")]
+    fn parse_md_emoji(&self) -> Vec<Token<'_, ()>> {
+        self.parse_md_custom(&[crate::emoji::parse_emoji])
+    }
+    /// Scans the token at `at`, returning only its kind and where it ends.
+    ///
+    /// This is for tools that only need token boundaries, like outline extractors. Custom
+    /// tokens are not recognized, and the default configuration is used. Nothing is allocated,
+    /// since the bodies of blocks aren't parsed and the code and URLs of tokens aren't built.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::TokenKind, Parser};
+    /// assert_eq!("# Title".scan_token(0), Some((TokenKind::Header, 2)));
+    /// ```
+    fn scan_token(&self, at: usize) -> Option<(TokenKind, usize)> {
+        let config = Config {
+            strip_fence_indent: false,
+            scan: true,
+            ..Config::default()
+        };
+        self.parse_token_with_config::<()>(at, &[], &config)
+            .map(|(token, nat)| (token.kind(), nat))
+    }
+    /// Parses the token at `at`, returning it and where the next token starts.
+    ///
+    /// This is [`parse_token`](Parser::parse_token) without custom tokens, using the default
+    /// configuration. It can be used to re-parse only a changed region of a document, like in
+    /// an editor.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::Token, Parser};
+    /// let (token, next) = "# Title".token_at(0).unwrap();
+    /// assert_eq!((token, next), (Token::Header(1), 2));
+    /// ```
+    fn token_at(&self, at: usize) -> Option<AtToken<'_, ()>> {
+        self.parse_token(at, &[])
+    }
+    /// Parses the token at `at` with the default configuration, trying the custom token
+    /// parsers first. See [`parse_token_with_config`](Parser::parse_token_with_config).
     fn parse_token<'a, Custom>(
         &'a self,
         at: usize,
-        custom: CustomFn<'a, Custom, Self>,
-    ) -> Option<AtToken<'_, Custom>> {
+        customs: &[CustomFn<'a, Custom, Self>],
+    ) -> Option<AtToken<'a, Custom>> {
+        self.parse_token_with_config(at, customs, &Config::default())
+    }
+    /// Parses the token at `at` using `config`, trying the custom token parsers first.
+    fn parse_token_with_config<'a, Custom>(
+        &'a self,
+        at: usize,
+        customs: &[CustomFn<'a, Custom, Self>],
+        config: &Config,
+    ) -> Option<AtToken<'a, Custom>> {
         self.eof(at)
             .not()
             .then(|| {
-                self.consume_whitespace(at)
-                    .map(|(_, at)| {
+                self.parse_front_matter(at).or_else(|| {
+                    self.consume_whitespace(at).and_then(|(indent, at)| {
+                        // Whitespace after a token starts the text after it, so only the
+                        // tokens after indentation can be blocks or custom tokens.
+                        let text_at = self.token_start(at - indent.len(), at);
                         self.parse_line_break(at)
-                            .or_else(|| custom(self, at))
-                            .or_else(|| self.parse_header(at))
-                            .or_else(|| self.parse_list_item(at))
-                            .or_else(|| self.parse_texty(at))
+                            .or_else(|| {
+                                (text_at == at)
+                                    .then(|| {
+                                        customs.iter().find_map(|custom| custom(self, at)).or_else(
+                                            || self.parse_block(at, indent, customs, config),
+                                        )
+                                    })
+                                    .flatten()
+                            })
+                            .or_else(|| {
+                                self.parse_texty(text_at, config)
+                                    .map(|token| self.stop_at_custom(token, customs))
+                            })
                     })
+                })
+            })
+            .flatten()
+    }
+    /// Parses a block token at `at`, after the indentation `indent` of the line.
+    ///
+    /// Block parsers are only tried if they can match the line: most blocks have to start at the
+    /// start of a line, with a certain character. This saves trying each of them for every
+    /// token, since most tokens are inline.
+    fn parse_block<'a, Custom>(
+        &'a self,
+        at: usize,
+        indent: &str,
+        customs: &[CustomFn<'a, Custom, Self>],
+        config: &Config,
+    ) -> Option<AtToken<'a, Custom>> {
+        let first = self.next_char(at).ok()?;
+        let parse_marker = || match first {
+            '#' => self.parse_header(at, config),
+            '-' | '+' | '*' | '0'..='9' => self.parse_list_item(at),
+            _ => None,
+        };
+        // Headers and list items don't need to be at the start of a line.
+        if self.is_line_start(at).not() {
+            return parse_marker();
+        }
+
+        self.parse_indented_code(at, indent, config)
+            .or_else(parse_marker)
+            // Items numbered with letters have to start a line, since they look like words.
+            .or_else(|| {
+                first
+                    .is_ascii_alphabetic()
+                    .then(|| self.parse_list_item(at))
+                    .flatten()
+            })
+            .or_else(|| self.parse_list_continuation(at, indent, config))
+            .or_else(|| match first {
+                '[' => self.parse_footnote_def(at, config),
+                ':' => self.parse_details(at, customs, config),
+                '>' => self.parse_alert(at, customs, config),
+                _ => None,
+            })
+            .or_else(|| self.parse_definition_list(at, config))
+            .or_else(|| {
+                (first == '<' && config.html)
+                    .then(|| self.parse_html_block(at))
                     .flatten()
             })
+    }
+    /// Cuts naked text short before the first word that a custom parser recognizes, so that
+    /// custom tokens can also start in the middle of a line.
+    fn stop_at_custom<'a, Custom>(
+        &'a self,
+        (token, nat): AtToken<'a, Custom>,
+        customs: &[CustomFn<'a, Custom, Self>],
+    ) -> AtToken<'a, Custom> {
+        let text = match &token {
+            Token::Text(t) if customs.is_empty().not() && t == &Text::naked(t.value) => t.value,
+            _ => return (token, nat),
+        };
+        let start = nat - text.len();
+        text.match_indices(char::is_whitespace)
+            .map(|(len, ws)| len + ws.len())
+            .find(|len| {
+                *len < text.len()
+                    && customs
+                        .iter()
+                        .any(|custom| custom(self, start + len).is_some())
+            })
+            .map_or((token, nat), |len| {
+                (Text::naked(&text[..len]).into_token(), start + len)
+            })
+    }
+    fn parse_inline_token<Custom>(
+        &self,
+        at: usize,
+        config: &Config,
+    ) -> Option<AtToken<'_, Custom>> {
+        let start = at;
+        let (space, at) = self
+            .consume_while(at, char::is_whitespace)
+            .unwrap_or_else(|(_, maybe_info)| maybe_info)
+            .unwrap_or(("", at));
+        // Line breaks are whitespace like any other here, but text can't start with one.
+        let start = if space.contains('\n') { at } else { start };
+        self.eof(at)
+            .not()
+            .then(|| {
+                if self.get_range_str(at..).starts_with("```") {
+                    Some((
+                        Text::naked(self.get_range_str(at..at + 3)).into_token(),
+                        at + 3,
+                    ))
+                } else {
+                    self.parse_texty(self.token_start(start, at), config)
+                }
+            })
+            .flatten()
+    }
+    /// Parses an inline token or a line break at `at`, for the markup nested in text. See
+    /// [`Text::parse_nested`].
+    fn parse_nested_token<Custom>(
+        &self,
+        at: usize,
+        config: &Config,
+    ) -> Option<AtToken<'_, Custom>> {
+        self.eof(at)
+            .not()
+            .then(|| {
+                self.consume_whitespace(at).and_then(|(indent, at)| {
+                    self.parse_line_break(at).or_else(|| {
+                        self.parse_texty(self.token_start(at - indent.len(), at), config)
+                    })
+                })
+            })
             .flatten()
     }
     #[inline(always)]
-    fn parse_texty<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
-        self.parse_code(at)
-            .or_else(|| self.parse_inline_url(at))
-            .or_else(|| self.parse_text(at).map(|(t, at)| (t.into_token(), at)))
+    fn parse_texty<Custom>(&self, at: usize, config: &Config) -> Option<AtToken<'_, Custom>> {
+        let token = self
+            .parse_code(at, config)
+            .or_else(|| self.parse_math(at))
+            .or_else(|| self.parse_footnote_ref(at, config))
+            .or_else(|| self.parse_link(at, config))
+            .or_else(|| self.parse_comment(at))
+            .or_else(|| self.parse_inline_url(at, config))
+            .or_else(|| self.parse_www_autolink(at, config))
+            .or_else(|| {
+                self.parse_text(at, config)
+                    .map(|(t, at)| (t.into_token(), at))
+            });
+        if config.images {
+            return token;
+        }
+        // Nested markup and link names are parsed again when rendered, so they have to remember
+        // that images are disabled.
+        let keep_images = |t: &mut Text| t.images_as_text = t.value.contains("![");
+        token.map(|(mut token, nat)| {
+            match &mut token {
+                Token::Text(t) if t.bold || t.italic || t.highlight => keep_images(t),
+                Token::Url { name: Some(t), .. } => keep_images(t),
+                _ => {}
+            }
+            (token, nat)
+        })
+    }
+    /// Parses inline (`$...$`) or display (`$$...$$`) math.
+    ///
+    /// Inline math can't span multiple lines, can't start or end with whitespace, and can't be
+    /// followed by a digit; so text like `costs $5 and $10` is not treated as math.
+    fn parse_math<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
+        // Only the first few `$`s are looked at, since longer runs aren't math anyway.
+        let dollars = self.get_range_str(at..).bytes().take(3);
+        let dollars = dollars.take_while(|c| *c == b'$').count();
+        (dollars > 0)
+            .then_some((dollars, at + dollars))
+            .and_then(|(dollars, nat)| match dollars {
+                2 => {
+                    let rest = until_blank_line(self.get_range_str(nat..));
+                    rest.find("$$").filter(|len| *len > 0).map(|len| {
+                        (
+                            Token::Math {
+                                content: &rest[..len],
+                                display: true,
+                            },
+                            nat + len + 2,
+                        )
+                    })
+                }
+                1 => self
+                    .consume_until(nat, |c, _, _| matches!(c, '$' | '\n'))
+                    .ok()
+                    .flatten()
+                    .filter(|(content, nat)| {
+                        matches!(self.next_char(*nat), Ok('$'))
+                            && content.starts_with(char::is_whitespace).not()
+                            && content.ends_with(char::is_whitespace).not()
+                            && matches!(self.next_char(nat + 1), Ok(c) if c.is_ascii_digit()).not()
+                    })
+                    .map(|(content, nat)| {
+                        (
+                            Token::Math {
+                                content,
+                                display: false,
+                            },
+                            nat + 1,
+                        )
+                    }),
+                _ => None,
+            })
     }
-    fn parse_code<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
+    fn parse_code<Custom>(&self, at: usize, config: &Config) -> Option<AtToken<'_, Custom>> {
         self.consume_while(at, is_backtick)
             .ok()
             .flatten()
-            .map(|(ticks, nat)| {
+            .and_then(|(ticks, nat)| {
                 let len = ticks.len();
                 match len {
-                    3 => self.parse_code_fence(nat),
-                    1 => self.parse_inline_code(nat),
-                    _ => None,
+                    3 => config
+                        .code_fences
+                        .then(|| self.parse_code_fence(nat, config))
+                        .flatten(),
+                    len => self.parse_inline_code(nat, len),
                 }
             })
-            .flatten()
     }
-    fn parse_inline_code<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
-        self.consume_while(at, |c| is_backtick(c).not())
-            .ok()
-            .flatten()
-            .map(|(value, at)| (Text::code(value).into_token(), at + 1))
+    /// Parses inline code that started with a run of `ticks` backticks.
+    ///
+    /// The code ends at the next run of exactly `ticks` backticks, so code can contain shorter or
+    /// longer runs. If the code both starts and ends with a space, one space is stripped from
+    /// each side, so code can also start or end with a backtick (ie. ``` `` `a` `` ```).
+    fn parse_inline_code<Custom>(&self, at: usize, ticks: usize) -> Option<AtToken<'_, Custom>> {
+        let rest = until_blank_line(self.get_range_str(at..));
+        let mut search = 0;
+        while let Some(start) = rest[search..].find('`').map(|pos| search + pos) {
+            let len = rest[start..]
+                .find(|c: char| is_backtick(c).not())
+                .unwrap_or(rest.len() - start);
+            if len == ticks {
+                let value = &rest[..start];
+                let value = value
+                    .strip_prefix(' ')
+                    .and_then(|v| v.strip_suffix(' '))
+                    .filter(|_| value.trim_matches(' ').is_empty().not())
+                    .unwrap_or(value);
+                return value
+                    .is_empty()
+                    .not()
+                    .then(|| (Text::code(value).into_token(), at + start + len));
+            }
+            search = start + len;
+        }
+        None
     }
+    /// Parses a list item marker, like `-`, `1.`, `a.` or `iv.`, and the whitespace after it.
+    ///
+    /// Like in Pandoc, single uppercase letters need two spaces after them, so that lines
+    /// starting with initials (like `A. Lincoln`) aren't list items. Roman numerals longer than
+    /// a letter (like `iv.`) only continue a list numbered with roman numerals, so that lines
+    /// starting with words like `mix.` aren't list items.
     fn parse_list_item<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
-        self.consume_char_if(at, |c| matches!(c, '-' | '+' | '*'))
-            .map(|nat| (None, nat))
+        self.next_char(at)
+            .ok()
+            .filter(|c| matches!(c, '-' | '+' | '*'))
+            .map(|marker| (None, marker, at + 1, 1))
             .or_else(|| {
                 self.consume_while(at, |c| c.is_ascii_digit())
                     .ok()
                     .flatten()
-                    .map(|(place, nat)| {
-                        self.consume_char_if(nat, |c| c == '.')
-                            .map(|nat| place.parse::<usize>().ok().map(|p| (Some(p), nat)))
-                            .flatten()
+                    .and_then(|(place, nat)| {
+                        self.consume_char_if(nat, |c| c == '.').and_then(|nat| {
+                            place.parse::<usize>().ok().map(|p| (Some(p), '.', nat, 1))
+                        })
                     })
-                    .flatten()
             })
-            .map(|(place, nat)| {
-                self.consume_whitespace(nat)
-                    .map(|(s, nat)| s.is_empty().not().then(|| (Token::ListItem(place), nat)))
-                    .flatten()
+            .or_else(|| {
+                let (letters, nat) = self
+                    .consume_while(at, |c| c.is_ascii_alphabetic())
+                    .ok()
+                    .flatten()?;
+                let nat = self.consume_char_if(nat, |c| c == '.')?;
+                let last_line = || {
+                    self.get_range_str(..at)
+                        .lines()
+                        .rev()
+                        .find(|line| line.trim().is_empty().not())
+                        .map(str::trim_start)
+                };
+                // `i.` is the letter after `h.`, not the roman numeral.
+                let follows_h = || {
+                    let h = if letters == "i" { "h." } else { "H." };
+                    last_line().is_some_and(|line| line.get(..2) == Some(h))
+                };
+                let follows_roman = |roman_marker| {
+                    last_line().is_some_and(|line| {
+                        matches!(
+                            line.parse_list_item::<()>(0),
+                            Some((Token::ListItem { marker, .. }, _)) if marker == roman_marker
+                        )
+                    })
+                };
+                let (place, marker) = match letters {
+                    "i" | "I" if follows_h() => (9, if letters == "i" { 'a' } else { 'A' }),
+                    letters => letter_place(letters)?,
+                };
+                if letters.len() > 1 && !follows_roman(marker) {
+                    return None;
+                }
+                let min_space = if letters.len() == 1 && marker.is_ascii_uppercase() {
+                    2
+                } else {
+                    1
+                };
+                Some((Some(place), marker, nat, min_space))
             })
-            .flatten()
+            .and_then(|(place, marker, nat, min_space)| {
+                self.consume_whitespace(nat).and_then(|(s, nat)| {
+                    (s.len() >= min_space).then(|| (Token::ListItem { place, marker }, nat))
+                })
+            })
+    }
+    /// Parses the indentation of a line that continues the preceding list item.
+    ///
+    /// A line continues a list item if it's indented by at least two columns (see
+    /// [`Config::tab_width`]), and the closest non-blank line before it that isn't indented is a
+    /// list item.
+    fn parse_list_continuation<Custom>(
+        &self,
+        at: usize,
+        indent: &str,
+        config: &Config,
+    ) -> Option<AtToken<'_, Custom>> {
+        let is_indented = |line: &str| indent_width(line, config.tab_width) >= 2;
+        let continues_item = is_indented(indent)
+            && self.is_line_start(at)
+            && self
+                .get_range_str(..at - indent.len())
+                .lines()
+                .rev()
+                .filter(|line| line.trim().is_empty().not())
+                .find(|line| is_indented(line).not())
+                .is_some_and(|line| line.parse_list_item::<()>(0).is_some());
+        continues_item.then_some((Token::ListContinuation, at))
+    }
+    /// Parses an indented code block, made of lines indented by at least four columns (see
+    /// [`Config::tab_width`]).
+    ///
+    /// The block needs to be preceded by a blank line (or be at the start of the input), and
+    /// can't continue a list item. One level of indentation is stripped from each line.
+    fn parse_indented_code<Custom>(
+        &self,
+        at: usize,
+        indent: &str,
+        config: &Config,
+    ) -> Option<AtToken<'_, Custom>> {
+        if config.code_fences.not() {
+            return None;
+        }
+        let is_code_line = |line: &str| indent_width(line, config.tab_width) >= 4;
+        let start = at - indent.len();
+        let follows_blank_line = || {
+            self.get_range_str(..start)
+                .strip_suffix('\n')
+                .is_none_or(|before| {
+                    let before = before.trim_end_matches([' ', '\t']);
+                    before.is_empty() || before.ends_with('\n')
+                })
+        };
+        if !(is_code_line(indent)
+            && self.is_line_start(at)
+            && follows_blank_line()
+            && self
+                .parse_list_continuation::<()>(at, indent, config)
+                .is_none())
+        {
+            return None;
+        }
+
+        // Blank lines are a part of the block only if there are more code lines after them.
+        let (mut end, mut line_start) = (start, start);
+        for line in self.get_range_str(start..).split('\n') {
+            if is_code_line(line) {
+                end = line_start + line.len();
+            } else if line.trim().is_empty().not() {
+                break;
+            }
+            line_start += line.len() + 1;
+        }
+        let lines = self.get_range_str(start..end);
+        let code = if config.scan {
+            Cow::Borrowed(lines)
+        } else if lines.contains('\n') {
+            Cow::Owned(
+                lines
+                    .split('\n')
+                    .map(|line| strip_indent(line, 4, config.tab_width))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        } else {
+            strip_indent(lines, 4, config.tab_width)
+        };
+        Some((Token::CodeFence { code, attrs: "" }, end))
     }
-    fn parse_code_fence<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
+    /// Parses front matter, ie. a `---` (YAML) or `+++` (TOML) line at the start of the input,
+    /// some lines and a closing line with the same fence.
+    ///
+    /// The newline after the closing fence is consumed too.
+    fn parse_front_matter<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
+        if at != 0 {
+            return None;
+        }
+        let input = self.get_range_str(..);
+        let (first, rest) = input.split_once('\n')?;
+        let (fence, format) = match first.trim_end() {
+            "---" => ("---", FrontMatterFormat::Yaml),
+            "+++" => ("+++", FrontMatterFormat::Toml),
+            _ => return None,
+        };
+        let mut line_start = 0;
+        rest.split_inclusive('\n').find_map(|line| {
+            let start = line_start;
+            line_start += line.len();
+            (line.trim_end() == fence).then(|| {
+                let raw = rest[..start].strip_suffix('\n').unwrap_or(&rest[..start]);
+                (
+                    Token::FrontMatter { format, raw },
+                    first.len() + 1 + line_start,
+                )
+            })
+        })
+    }
+    /// Parses a code fence, whose opening backticks end at `at`.
+    ///
+    /// If the fence is indented, as much indentation is stripped from each line of the code,
+    /// see [`Config::strip_fence_indent`].
+    fn parse_code_fence<Custom>(&self, at: usize, config: &Config) -> Option<AtToken<'_, Custom>> {
+        let fence_start = at - 3;
+        let before = self.get_range_str(..fence_start);
+        let indent = &before[before.rfind('\n').map_or(0, |i| i + 1)..];
+        let indent = if config.strip_fence_indent && indent.trim().is_empty() {
+            indent_width(indent, config.tab_width)
+        } else {
+            0
+        };
         self.consume_until_str(at, "```")
             .ok()
             .flatten()
             .map(|(v, at)| {
                 let part_count = v.split('\n').count();
 
-                let (code, attrs) = (part_count >= 1)
-                    .then(|| {
-                        let mut split = v.split('\n');
-                        let attrs_raw = split.next().unwrap();
-                        let code = v.trim_start_matches(attrs_raw).trim_start_matches('\n');
-                        (code, attrs_raw)
-                    })
-                    .unwrap_or_else(|| (v.trim_start_matches('\n'), ""));
+                let (code, attrs) = if part_count >= 1 {
+                    let mut split = v.split('\n');
+                    let attrs_raw = split.next().unwrap();
+                    // Only the line break after the attributes is removed, so that blank
+                    // lines at the start of the code are kept.
+                    let code = &v[attrs_raw.len()..];
+                    let code = code.strip_prefix('\n').unwrap_or(code);
+                    (code, attrs_raw)
+                } else {
+                    (v.trim_start_matches('\n'), "")
+                };
 
+                let code = match indent {
+                    0 => Cow::Borrowed(code),
+                    indent => Cow::Owned(
+                        code.split('\n')
+                            .map(|line| strip_indent(line, indent, config.tab_width))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ),
+                };
                 (Token::CodeFence { code, attrs }, at + 3)
             })
     }
-    fn parse_header<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
+    fn parse_header<Custom>(&self, at: usize, config: &Config) -> Option<AtToken<'_, Custom>> {
+        if config.headers.not() {
+            return None;
+        }
         self.consume_while(at, |c| c == '#')
             .ok()
             .flatten()
-            .map(|(_, hnat)| {
-                self.consume_whitespace(hnat)
-                    .map(|(w, nat)| {
-                        w.is_empty()
-                            .not()
-                            .then(|| {
-                                let h = hnat - at;
-                                (h > 0 && h < 7).then(|| (Token::Header(h), nat))
-                            })
-                            .flatten()
-                    })
-                    .flatten()
+            .and_then(|(_, hnat)| {
+                self.consume_whitespace(hnat).and_then(|(w, nat)| {
+                    w.is_empty()
+                        .not()
+                        .then(|| {
+                            let h = match hnat - at {
+                                h if h > config.max_header_depth && config.clamp_headers => {
+                                    config.max_header_depth
+                                }
+                                h => h,
+                            };
+                            (h > 0 && h <= config.max_header_depth).then(|| (Token::Header(h), nat))
+                        })
+                        .flatten()
+                })
             })
+    }
+    /// Parses a footnote label (ie. `[^label]`).
+    fn parse_footnote_label(&self, at: usize, config: &Config) -> Option<AtStr<'_>> {
+        let limit = at.saturating_add(config.max_link_lookahead);
+        self.consume_char_if(at, |c| c == '[')
+            .and_then(|nat| self.consume_char_if(nat, |c| c == '^'))
+            .and_then(|nat| {
+                self.consume_until(nat, |c, _, pos| {
+                    pos >= limit || c == ']' || c.is_whitespace()
+                })
+                .ok()
+                .flatten()
+            })
+            .and_then(|(id, nat)| self.consume_char_if(nat, |c| c == ']').map(|nat| (id, nat)))
+    }
+    fn parse_footnote_ref<Custom>(
+        &self,
+        at: usize,
+        config: &Config,
+    ) -> Option<AtToken<'_, Custom>> {
+        self.parse_footnote_label(at, config)
+            .map(|(id, nat)| (Token::FootnoteRef(id), nat))
+    }
+    fn parse_footnote_def<Custom>(
+        &self,
+        at: usize,
+        config: &Config,
+    ) -> Option<AtToken<'_, Custom>> {
+        self.is_line_start(at)
+            .then(|| self.parse_footnote_label(at, config))
             .flatten()
+            .and_then(|(id, nat)| self.consume_char_if(nat, |c| c == ':').map(|nat| (id, nat)))
+            .and_then(|(id, nat)| self.consume_whitespace(nat).map(|(_, nat)| (id, nat)))
+            .map(|(id, nat)| {
+                let (body, nat) = self
+                    .consume_while(nat, |c| c != '\n')
+                    .unwrap_or_else(|(_, maybe_info)| maybe_info)
+                    .unwrap_or(("", nat));
+                (Token::FootnoteDef { id, body }, nat)
+            })
+    }
+    /// Parses a collapsible section, which starts with a `:::details Summary` line and ends with
+    /// a `:::` line. Sections can be nested.
+    fn parse_details<'a, Custom>(
+        &'a self,
+        at: usize,
+        customs: &[CustomFn<'a, Custom, Self>],
+        config: &Config,
+    ) -> Option<AtToken<'a, Custom>> {
+        const OPEN: &str = ":::details";
+        let rest = self.get_range_str(at..);
+        let after_open = rest.strip_prefix(OPEN)?;
+        if !self.is_line_start(at) || after_open.starts_with(|c: char| c.is_whitespace().not()) {
+            return None;
+        }
+        let summary_line = after_open.split('\n').next().unwrap_or_default();
+        let body_start = at + OPEN.len() + summary_line.len() + 1;
+
+        let end_of_input = at + rest.len();
+        let mut depth = 1;
+        let mut in_fence = false;
+        let mut line_start = body_start;
+        let (body_end, end) = loop {
+            if line_start > end_of_input {
+                return None;
+            }
+            let line = self
+                .get_range_str(line_start..)
+                .split('\n')
+                .next()
+                .unwrap_or_default();
+            // Lines in code fences can't open or close sections. Like code fences, a fence ends
+            // at the next ``` even if it's in the middle of a line.
+            let fences = if config.code_fences {
+                line.matches("```").count()
+            } else {
+                0
+            };
+            let was_in_fence = in_fence;
+            in_fence ^= fences % 2 == 1;
+            match line.trim() {
+                _ if was_in_fence => {}
+                trimmed if trimmed.starts_with(OPEN) => {
+                    depth += 1;
+                    if depth > config.max_details_depth {
+                        return None;
+                    }
+                }
+                ":::" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break (line_start, line_start + line.len());
+                    }
+                }
+                _ => {}
+            }
+            line_start += line.len() + 1;
+        };
+
+        let summary = summary_line.trim();
+        let summary_at = at + OPEN.len() + (summary_line.len() - summary_line.trim_start().len());
+        let summary = self.parse_whole_text(summary_at, summary, config);
+
+        let mut body = Vec::new();
+        if config.scan.not() {
+            self.parse_tokens_until(body_start, body_end, customs, config, &mut body);
+        }
+        while body.last().is_some_and(Token::is_line_break) {
+            body.pop();
+        }
+        Some((Token::Details { summary, body }, end))
+    }
+    /// Parses an alert, ie. a `> [!NOTE]` line followed by lines starting with `>`.
+    ///
+    /// Blockquotes aren't supported otherwise, so lines starting with `>` that don't start an
+    /// alert of a known kind are left as text. Each line of the body is parsed on its own, so
+    /// multi-line markup (like emphasis or code fences) can't span lines.
+    fn parse_alert<'a, Custom>(
+        &'a self,
+        at: usize,
+        customs: &[CustomFn<'a, Custom, Self>],
+        config: &Config,
+    ) -> Option<AtToken<'a, Custom>> {
+        let first_line = self.get_range_str(at..).split('\n').next()?;
+        let kind = first_line
+            .strip_prefix('>')?
+            .trim()
+            .strip_prefix("[!")?
+            .strip_suffix(']')
+            .and_then(AlertKind::from_name)?;
+
+        let mut body = Vec::new();
+        let mut end = at + first_line.len();
+        while self.get_range_str(end..).starts_with("\n>") {
+            let line_start = end + 1;
+            let line = self
+                .get_range_str(line_start..)
+                .split('\n')
+                .next()
+                .unwrap_or_default();
+            let line_end = line_start + line.len();
+            if body.is_empty().not() {
+                body.push(Token::LineBreak);
+            }
+            let nat = line_start + if line.starts_with("> ") { 2 } else { 1 };
+            if config.scan.not() {
+                self.parse_tokens_until(nat, line_end, customs, config, &mut body);
+            }
+            end = line_end;
+        }
+        while body.last().is_some_and(Token::is_line_break) {
+            body.pop();
+        }
+        Some((Token::Alert { kind, body }, end))
+    }
+    /// Parses the tokens from `at` to `end` into `tokens`, for the body of a block. Tokens
+    /// can't continue after `end`, so the first character of one that does is text, like an
+    /// unclosed `*`.
+    fn parse_tokens_until<'a, Custom>(
+        &'a self,
+        mut at: usize,
+        end: usize,
+        customs: &[CustomFn<'a, Custom, Self>],
+        config: &Config,
+        tokens: &mut Vec<Token<'a, Custom>>,
+    ) {
+        while at < end {
+            match self.parse_token_with_config(at, customs, config) {
+                Some((token, next)) if next <= end => {
+                    tokens.push(token);
+                    at = next;
+                }
+                Some(_) => {
+                    let Some((_, start)) = self.consume_whitespace(at) else {
+                        break;
+                    };
+                    let Ok((_, next)) = self.consume_char(start) else {
+                        break;
+                    };
+                    tokens.push(Text::naked(self.get_range_str(start..next)).into_token());
+                    at = next;
+                }
+                None => break,
+            }
+        }
+    }
+    fn parse_definition_list<Custom>(
+        &self,
+        at: usize,
+        config: &Config,
+    ) -> Option<AtToken<'_, Custom>> {
+        let mut definitions = Vec::new();
+        let mut end = None;
+        let mut line = at;
+        while let Some((term, mut nat)) = self.parse_definition_term(line) {
+            let mut descriptions = Vec::new();
+            while let Some((description, dnat)) = self.parse_definition_description(nat) {
+                if config.scan.not() {
+                    descriptions.push(description);
+                }
+                end = Some(dnat);
+                nat = dnat + 1;
+            }
+            if config.scan.not() {
+                definitions.push(Definition { term, descriptions });
+            }
+            line = nat;
+        }
+        end.map(|end| (Token::DefinitionList(definitions), end))
     }
-    fn parse_inline_url<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
-        self.consume_char_if(at, |c| c == '<')
+    /// Parses a term line, which must be followed by a description line.
+    fn parse_definition_term(&self, at: usize) -> Option<AtStr<'_>> {
+        if self.eof(at) {
+            return None;
+        }
+        let rest = self.get_range_str(at..);
+        rest.find('\n')
+            .map(|len| (&rest[..len], at + len))
+            .filter(|(term, nat)| {
+                self.is_line_start(at)
+                    && term.starts_with(':').not()
+                    && term.trim().is_empty().not()
+                    && self.parse_definition_description(nat + 1).is_some()
+            })
+            .map(|(term, nat)| (term.trim(), nat + 1))
+    }
+    fn parse_definition_description(&self, at: usize) -> Option<AtStr<'_>> {
+        self.consume_char_if(at, |c| c == ':')
+            .and_then(|nat| self.consume_char_if(nat, |c| c == ' ' || c == '\t'))
             .map(|nat| {
-                self.consume_while(nat, |c| c != '>')
+                self.consume_while(nat, |c| c != '\n')
+                    .unwrap_or_else(|(_, maybe_info)| maybe_info)
+                    .map_or(("", nat), |(description, nat)| (description.trim(), nat))
+            })
+    }
+    fn parse_html_block<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
+        self.is_line_start(at)
+            .then(|| self.consume_char_if(at, |c| c == '<'))
+            .flatten()
+            .and_then(|nat| {
+                let nat = self.consume_char_if(nat, |c| c == '/').unwrap_or(nat);
+                self.consume_while(nat, |c| c.is_ascii_alphanumeric())
                     .ok()
                     .flatten()
-                    .map(|(url, nat)| {
-                        (
-                            Token::Url {
-                                name: None,
-                                is_image: false,
-                                url,
-                            },
-                            nat + 1,
-                        )
-                    })
             })
+            .filter(|(tag, nat)| {
+                HTML_BLOCK_TAGS.contains(&tag.to_ascii_lowercase().as_str())
+                    && matches!(self.next_char(*nat), Ok(c) if c == '>' || c == '/' || c.is_whitespace())
+            })
+            .map(|(tag, nat)| {
+                let close = format!("</{}>", tag);
+                // Tag names aren't case sensitive, so `<DIV>` is closed by `</div>`.
+                let is_close = |at: usize| {
+                    self.get_range_str(at..)
+                        .get(..close.len())
+                        .is_some_and(|rest| rest.eq_ignore_ascii_case(&close))
+                };
+                let end = match self.consume_until(nat, |_, _, at| {
+                    is_close(at) || self.get_range_str(at..).starts_with("\n\n")
+                }) {
+                    Ok(Some((_, end))) | Err((_, Some((_, end)))) => end,
+                    Ok(None) | Err((_, None)) => nat,
+                };
+                let end = if is_close(end) {
+                    end + close.len()
+                } else {
+                    end
+                };
+                (Token::Html(self.get_range_str(at..end)), end)
+            })
+    }
+    /// Parses a link (ie. `[name](url "title")`) or an image (ie. `![alt](url "title")`).
+    fn parse_link<Custom>(&self, at: usize, config: &Config) -> Option<AtToken<'_, Custom>> {
+        let (is_image, at) = self
+            .consume_char_if(at, |c| c == '!')
+            .map_or((false, at), |nat| (true, nat));
+        // With images disabled, a link right after a `!` would turn an image into a link.
+        if config.images.not() && self.get_range_str(..at).ends_with('!') {
+            return None;
+        }
+        let name_at = self.consume_char_if(at, |c| c == '[')?;
+        let limit = name_at.saturating_add(config.max_link_lookahead);
+        // The name ends at the matching `]`, so it can contain images (ie. `[![alt](a)](b)`).
+        let rest = truncate(self.get_range_str(name_at..), config.max_link_lookahead);
+        let mut depth = 0_usize;
+        let name_len = rest.find(|c| match c {
+            '[' => {
+                depth += 1;
+                false
+            }
+            ']' if depth > 0 => {
+                depth -= 1;
+                false
+            }
+            c => c == ']' || c == '\n',
+        })?;
+        let name = &rest[..name_len];
+        let nat = self.consume_char_if(name_at + name_len, |c| c == ']')?;
+        let nat = self.consume_char_if(nat, |c| c == '(')?;
+        let (url, nat) = self
+            .consume_until(nat, |c, _, pos| {
+                pos >= limit || c == ')' || c.is_whitespace()
+            })
+            .ok()
+            .flatten()?;
+        let (_, nat) = self.consume_whitespace(nat)?;
+        let (width, height, nat) = is_image
+            .then(|| self.parse_image_size(nat))
             .flatten()
+            .map_or((None, None, nat), |(width, height, nat)| {
+                let (_, nat) = self.consume_whitespace(nat).unwrap_or(("", nat));
+                (width, height, nat)
+            });
+        let (title, nat) = self
+            .parse_link_title(nat, limit)
+            .map_or((None, nat), |(title, nat)| (Some(title), nat));
+        let (_, nat) = self.consume_whitespace(nat)?;
+        let nat = self.consume_char_if(nat, |c| c == ')')?;
+
+        let name = name
+            .is_empty()
+            .not()
+            .then(|| self.parse_whole_text(name_at, name, config));
+        Some((
+            Token::Url {
+                name,
+                url: Cow::Borrowed(url),
+                title,
+                is_image,
+                width,
+                height,
+            },
+            nat,
+        ))
+    }
+    /// Parses the size of an image, like `=200x100`. Either the width or the height can be left
+    /// out, like `=200x` or `=x100`.
+    fn parse_image_size(&self, at: usize) -> Option<(Option<usize>, Option<usize>, usize)> {
+        let rest = self.get_range_str(at..).strip_prefix('=')?;
+        let digits = |s: &str| {
+            s.find(|c: char| c.is_ascii_digit().not())
+                .unwrap_or(s.len())
+        };
+        let width_len = digits(rest);
+        let after_x = rest[width_len..].strip_prefix('x')?;
+        let height_len = digits(after_x);
+        let end = width_len + 1 + height_len;
+        let ends_size = rest[end..].starts_with(|c: char| c == ')' || c.is_whitespace());
+        (ends_size && end > 1).then(|| {
+            (
+                rest[..width_len].parse().ok(),
+                after_x[..height_len].parse().ok(),
+                at + 1 + end,
+            )
+        })
+    }
+    /// Parses `value`, which starts at `at`, as emphasized text if the emphasis covers all of it,
+    /// or as naked text otherwise.
+    fn parse_whole_text<'a>(&'a self, at: usize, value: &'a str, config: &Config) -> Text<'a> {
+        let end = at + value.len();
+        self.parse_emphasis(at, '*', config)
+            .or_else(|| self.parse_emphasis(at, '_', config))
+            .filter(|(_, nat)| *nat == end)
+            .map_or_else(|| Text::naked(value), |(text, _)| text)
+    }
+    /// Parses a link title quoted with `"` or `'`, which has to end before `limit`.
+    fn parse_link_title(&self, at: usize, limit: usize) -> Option<AtStr<'_>> {
+        self.consume_char(at)
+            .ok()
+            .filter(|(c, _)| matches!(c, '"' | '\''))
+            .and_then(|(quote, nat)| {
+                self.consume_until(nat, |c, _, pos| pos >= limit || c == quote || c == '\n')
+                    .ok()
+                    .flatten()
+                    .and_then(|(title, nat)| {
+                        self.consume_char_if(nat, |c| c == quote)
+                            .map(|nat| (title, nat))
+                    })
+            })
+    }
+    /// Parses an HTML comment, which can span several lines. An unterminated comment is text.
+    fn parse_comment<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
+        let rest = self.get_range_str(at..).strip_prefix("<!--")?;
+        let end = rest.find("-->")?;
+        Some((Token::Comment(&rest[..end]), at + 4 + end + 3))
+    }
+    /// Parses an autolink, like `<https://example.org>` or `<user@example.org>`.
+    ///
+    /// Email addresses and `www.` links are linked with a `mailto:` or `http://` scheme, and keep
+    /// the address as their name.
+    fn parse_inline_url<Custom>(&self, at: usize, config: &Config) -> Option<AtToken<'_, Custom>> {
+        self.consume_char_if(at, |c| c == '<').and_then(|nat| {
+            let rest = until_blank_line(self.get_range_str(nat..));
+            rest.find('>')
+                .filter(|len| *len > 0)
+                .map(|len| (&rest[..len], nat + len))
+                .map(|(url, nat)| {
+                    let token = if is_email(url) {
+                        autolink("mailto:", url, config)
+                    } else if url.starts_with("www.") {
+                        autolink("http://", url, config)
+                    } else {
+                        Token::url(url)
+                    };
+                    (token, nat + 1)
+                })
+        })
+    }
+    /// Parses a `www.` autolink, like `www.example.org/page`.
+    ///
+    /// The link must start at a word boundary, and ends at whitespace or `<`. Trailing
+    /// punctuation (like the `.` ending a sentence) and unbalanced closing parentheses are not a
+    /// part of the link. The link gets an `http://` scheme, and keeps the URL as written as its
+    /// name.
+    fn parse_www_autolink<Custom>(
+        &self,
+        at: usize,
+        config: &Config,
+    ) -> Option<AtToken<'_, Custom>> {
+        let rest = self.get_range_str(at..);
+        if !(rest.starts_with("www.") && self.is_word_boundary(at)) {
+            return None;
+        }
+        let mut url = &rest[..rest
+            .find(|c: char| c.is_whitespace() || c == '<')
+            .unwrap_or(rest.len())];
+        loop {
+            let trimmed = url.trim_end_matches(['?', '!', '.', ',', ':', '*', '_', '~']);
+            let trimmed = match trimmed.strip_suffix(')') {
+                Some(without) if trimmed.matches(')').count() > trimmed.matches('(').count() => {
+                    without
+                }
+                _ => trimmed,
+            };
+            if trimmed.len() == url.len() {
+                break;
+            }
+            url = trimmed;
+        }
+        let domain = &url[..url.find(['/', '?', '#']).unwrap_or(url.len())];
+        let is_domain = domain.split('.').skip(1).all(|label| {
+            !label.is_empty()
+                && label
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+        });
+        (is_domain && domain.len() > "www.".len())
+            .then(|| (autolink("http://", url, config), at + url.len()))
+    }
+    fn parse_text(&self, at: usize, config: &Config) -> Option<AtText<'_>> {
+        self.parse_emphasis(at, '*', config)
+            .or_else(|| self.parse_emphasis(at, '_', config))
+            .or_else(|| self.parse_script(at))
+            .or_else(|| self.parse_highlight(at))
+            .or_else(|| {
+                // Emphasis can only start at a position if a run that can close it is in reach.
+                // The last position that was looked from is remembered with what was found,
+                // so that each run is only looked at a few times:
+                // - the first closing run after a position is also the first one after any
+                //   later position up to it,
+                // - if there is none in reach, a later position only reaches as many more runs
+                //   as it is past the last position.
+                let closers = [Cell::new(None), Cell::new(None)];
+                let is_emphasis = |pos: usize, delim: char| {
+                    let cached = &closers[usize::from(delim == '_')];
+                    let closer = match cached.get() {
+                        Some((_, Ok(start))) if pos <= start => Ok(start),
+                        Some((_, Err(usize::MAX))) => Err(usize::MAX),
+                        Some((mut from, Err(until))) => {
+                            let mut passed = 0;
+                            while let Some((_, end, ..)) = self
+                                .next_delimiter_run(from, delim, config)
+                                .filter(|(_, end, ..)| *end <= pos)
+                            {
+                                (from, passed) = (end, passed + 1);
+                            }
+                            self.emphasis_closer(until, delim, passed, config)
+                        }
+                        _ => {
+                            self.emphasis_closer(pos, delim, config.max_emphasis_lookahead, config)
+                        }
+                    };
+                    cached.set(Some((pos, closer)));
+                    closer.is_ok() && self.parse_emphasis(pos, delim, config).is_some()
+                };
+                self.consume_until(at, |c, _, pos| match c {
+                    '\n' => true,
+                    // A `<` that starts nothing else is text.
+                    '<' => pos != at,
+                    '~' | '^' => pos != at && self.parse_script(pos).is_some(),
+                    '=' => pos != at && self.parse_highlight(pos).is_some(),
+                    '*' | '_' => pos != at && is_emphasis(pos, c),
+                    '`' => {
+                        pos != at
+                            && self.get_range_str(..pos).ends_with('`').not()
+                            && (self.get_range_str(pos..).starts_with("```")
+                                || self.parse_code::<()>(pos, config).is_some())
+                    }
+                    '$' => pos != at && self.parse_math::<()>(pos).is_some(),
+                    '[' => {
+                        pos != at
+                            && (self.parse_footnote_label(pos, config).is_some()
+                                || self.parse_link::<()>(pos, config).is_some())
+                    }
+                    '!' => pos != at && self.parse_link::<()>(pos, config).is_some(),
+                    'w' => pos != at && self.parse_www_autolink::<()>(pos, config).is_some(),
+                    _ => false,
+                })
+                .map_or_else(try_handle_err, |v| v.map(|(s, nat)| (Text::naked(s), nat)))
+            })
+    }
+    /// Parses a subscript (`~sub~`) or a superscript (`^sup^`).
+    ///
+    /// Scripts can't contain whitespace, and doubled delimiters (like `~~`) are not scripts.
+    fn parse_script(&self, at: usize) -> Option<AtText<'_>> {
+        let rest = self.get_range_str(at..);
+        let delim = rest.chars().next().filter(|c| matches!(c, '~' | '^'))?;
+        let content = &rest[1..];
+        let len = content.find(|c: char| c == delim || c.is_whitespace())?;
+        let is_script = len > 0
+            && content[len..].starts_with(delim)
+            && content[len + 1..].starts_with(delim).not()
+            && self.get_range_str(..at).ends_with(delim).not();
+        is_script.then(|| {
+            (
+                Text {
+                    value: &content[..len],
+                    subscript: delim == '~',
+                    superscript: delim == '^',
+                    ..Text::default()
+                },
+                at + len + 2,
+            )
+        })
+    }
+    /// Parses highlighted text (`==highlight==`).
+    ///
+    /// The text can't start or end with whitespace or span a blank line, and longer runs of
+    /// `=` (like `===`) are left alone.
+    fn parse_highlight(&self, at: usize) -> Option<AtText<'_>> {
+        let content = self.get_range_str(at..).strip_prefix("==")?;
+        if self.get_range_str(..at).ends_with('=') || content.starts_with('=') {
+            return None;
+        }
+        let len = content.find("==")?;
+        let value = &content[..len];
+        let is_highlight = value.starts_with(char::is_whitespace).not()
+            && value.ends_with(char::is_whitespace).not()
+            && content[len + 2..].starts_with('=').not()
+            && value
+                .split('\n')
+                .skip(1)
+                .any(|line| line.trim().is_empty())
+                .not();
+        is_highlight.then(|| {
+            (
+                Text {
+                    value,
+                    highlight: true,
+                    ..Text::default()
+                },
+                at + len + 4,
+            )
+        })
+    }
+    /// Returns where the token after the whitespace from `start` to `at` starts.
+    ///
+    /// Whitespace at the start of a line is indentation, so the token starts after it. Otherwise
+    /// the whitespace follows another token, and is kept as the start of the text after it, so
+    /// that renderers only put spaces between tokens where the source has them.
+    fn token_start(&self, start: usize, at: usize) -> usize {
+        if self.is_line_start(start) {
+            at
+        } else {
+            start
+        }
+    }
+    /// Parses emphasis delimited by `delim`.
+    ///
+    /// This follows the CommonMark rules for delimiter runs: the run at `at` must be able to
+    /// open emphasis, and the emphasis ends at the first run after it that can close it and
+    /// isn't closing emphasis nested in it. If the opening run is longer than the closing run,
+    /// the extra delimiters are left as text before the emphasis, and this returns `None` until
+    /// `at` is far enough into the run.
+    ///
+    /// The value of the text keeps the markup nested in it, see [`Text::parse_nested`]. Runs
+    /// longer than [`Config::max_delimiter_run`] are text, and so is the opening run if its end
+    /// isn't in the next [`Config::max_emphasis_lookahead`] runs.
+    fn parse_emphasis(&self, at: usize, delim: char, config: &Config) -> Option<AtText<'_>> {
+        let (start, end) = self.delimiter_run(at, delim, config.max_delimiter_run)?;
+        let (can_open, opener_closes) = self.delimiter_flanking(start, end, delim);
+        if can_open.not() {
+            return None;
+        }
+        let count = end - at;
+        let mut pos = end;
+        // Number of nested runs that can only open emphasis, which the next closing runs close.
+        let mut depth = 0_usize;
+        for _ in 0..config.max_emphasis_lookahead {
+            let (close_start, close_end, closer_opens, can_close) =
+                self.next_delimiter_run(pos, delim, config)?;
+            pos = close_end;
+            if can_close.not() {
+                depth += 1;
+                continue;
+            } else if depth > 0 {
+                depth -= 1;
+                continue;
+            }
+            let (opener_len, closer_len) = (end - start, close_end - close_start);
+            // A run that can both open and close only matches another run if their lengths
+            // don't add up to a multiple of 3, unless both are.
+            let multiple_of_3 =
+                (opener_len + closer_len) % 3 == 0 && (opener_len % 3 != 0 || closer_len % 3 != 0);
+            if ((opener_closes || closer_opens) && multiple_of_3).not() {
+                let used = count.min(closer_len).min(3);
+                return (used == count).then(|| {
+                    (
+                        Text {
+                            value: self.get_range_str(end..close_start),
+                            bold: used != 1,
+                            italic: used != 2,
+                            ..Text::default()
+                        },
+                        close_start + used,
+                    )
+                });
+            }
+        }
+        None
+    }
+    /// Finds the first run of `delim`s at or after `at` that can close emphasis, returning its
+    /// start.
+    ///
+    /// Only the next `runs` runs are looked at. If none of them can close emphasis, this returns
+    /// where they end, or `usize::MAX` if there are no more runs before a blank line, since
+    /// emphasis can't span one.
+    fn emphasis_closer(
+        &self,
+        mut at: usize,
+        delim: char,
+        runs: usize,
+        config: &Config,
+    ) -> Result<usize, usize> {
+        for _ in 0..runs {
+            match self.next_delimiter_run(at, delim, config) {
+                Some((start, _, _, true)) => return Ok(start),
+                Some((_, end, ..)) => at = end,
+                None => return Err(usize::MAX),
+            }
+        }
+        Err(at)
+    }
+    /// Finds the first run of `delim`s at or after `at` that can open or close emphasis,
+    /// returning its start, end and whether it can open and close emphasis.
+    ///
+    /// Returns `None` if there is no such run before a blank line. Runs longer than
+    /// [`Config::max_delimiter_run`] are skipped.
+    fn next_delimiter_run(
+        &self,
+        mut at: usize,
+        delim: char,
+        config: &Config,
+    ) -> Option<(usize, usize, bool, bool)> {
+        loop {
+            let found = at + self.get_range_str(at..).find([delim, '\n'])?;
+            let rest = self.get_range_str(found..);
+            if is_blank_line_after(rest) {
+                return None;
+            }
+            match self.delimiter_run(found, delim, config.max_delimiter_run) {
+                Some((start, end)) => match self.delimiter_flanking(start, end, delim) {
+                    (false, false) => at = end,
+                    (opens, closes) => return Some((start, end, opens, closes)),
+                },
+                // A newline, or a run that is too long.
+                None => at = found + (rest.len() - rest.trim_start_matches(delim).len()).max(1),
+            }
+        }
+    }
+    /// Returns the start and end of the run of `delim`s that contains the character at `at`, or
+    /// `None` if the run is longer than `max_len`.
+    ///
+    /// At most `max_len` characters are looked at on each side, so this is fast even for huge
+    /// runs.
+    fn delimiter_run(&self, at: usize, delim: char, max_len: usize) -> Option<(usize, usize)> {
+        let rest = self.get_range_str(at..);
+        if rest.starts_with(delim).not() {
+            return None;
+        }
+        let run_len = |chars: &mut dyn Iterator<Item = char>| {
+            chars
+                .take(max_len.saturating_add(1))
+                .take_while(|c| *c == delim)
+                .count()
+                * delim.len_utf8()
+        };
+        let before = run_len(&mut self.get_range_str(..at).chars().rev());
+        let after = run_len(&mut rest.chars());
+        (before + after <= max_len.saturating_mul(delim.len_utf8()))
+            .then(|| (at - before, at + after))
     }
-    fn parse_text(&self, at: usize) -> Option<AtText<'_>> {
-        self.consume_while(at, |c| c == '*')
-            .ok()
-            .flatten()
-            .map(|(stars, nat)| {
-                let count = stars.len();
-                (1..=count)
-                    .rev()
-                    .flat_map(|search| {
-                        let check_italic = count == 2 && search == 1;
-                        let offset = check_italic.not().then(|| count - search).unwrap_or(0);
-                        self.consume_until_str(nat - offset, &stars[0..search])
-                            .ok()
-                            .flatten()
-                            .map(|(s, nnat)| {
-                                (
-                                    Text {
-                                        value: check_italic
-                                            .then(|| self.get_range_str(nat - 1..nnat))
-                                            .unwrap_or(s),
-                                        bold: search != 1,
-                                        italic: search != 2,
-                                        code: false,
-                                    },
-                                    nnat + search,
-                                )
-                            })
-                    })
-                    .next()
-            })
-            .flatten()
-            .or_else(|| {
-                self.consume_while(at, |c| matches!(c, '\n' | '<' | '`' | '*').not())
-                    .map_or_else(try_handle_err, |v| v.map(|(s, nat)| (Text::naked(s), nat)))
-            })
+    /// Returns whether the run of `delim`s from `start` to `end` can open and close emphasis.
+    ///
+    /// A run can open emphasis if it is left-flanking, ie. it is not followed by whitespace,
+    /// and it is not followed by punctuation unless it is preceded by whitespace or
+    /// punctuation. Closing is the same, with the directions swapped. Underscores also can't
+    /// open or close emphasis inside of words.
+    fn delimiter_flanking(&self, start: usize, end: usize, delim: char) -> (bool, bool) {
+        let before = self.get_range_str(..start).chars().next_back();
+        let after = self.get_range_str(end..).chars().next();
+        let is_space = |c: Option<char>| c.is_none_or(char::is_whitespace);
+        let is_punct = |c: Option<char>| {
+            c.is_some_and(|c| c.is_alphanumeric().not() && c.is_whitespace().not())
+        };
+        let left = is_space(after).not()
+            && (is_punct(after).not() || is_space(before) || is_punct(before));
+        let right = is_space(before).not()
+            && (is_punct(before).not() || is_space(after) || is_punct(after));
+        if delim == '_' {
+            (
+                left && (right.not() || is_punct(before)),
+                right && (left.not() || is_punct(after)),
+            )
+        } else {
+            (left, right)
+        }
     }
     fn parse_line_break<Custom>(&self, at: usize) -> Option<AtToken<'_, Custom>> {
         self.consume_char_if(at, |c| c == '\n')
@@ -238,44 +1804,70 @@ pub trait Parser {
     fn consume_char_if<F: Fn(char) -> bool>(&self, at: usize, f: F) -> Option<usize> {
         self.consume_char(at)
             .ok()
-            .map(|(c, nat)| f(c).then(|| nat))
-            .flatten()
+            .and_then(|(c, nat)| f(c).then_some(nat))
     }
     #[inline(always)]
     fn consume_while<F: Fn(char) -> bool>(
         &self,
         at: usize,
         f: F,
-    ) -> Result<Option<AtStr>, (ParserError, Option<AtStr>)> {
+    ) -> Result<Option<AtStr<'_>>, (ParserError, Option<AtStr<'_>>)> {
         self.consume_until(at, |c, _, _| f(c).not())
     }
     fn consume_until<F: Fn(char, usize, usize) -> bool>(
         &self,
         mut at: usize,
         f: F,
-    ) -> Result<Option<AtStr>, (ParserError, Option<AtStr>)> {
+    ) -> Result<Option<AtStr<'_>>, (ParserError, Option<AtStr<'_>>)> {
         let old_at = at;
         loop {
             let (ch, nat) = self.consume_char(at).map_err(|err| {
                 (err, {
                     let content = self.get_range_str(old_at..at);
-                    content.is_empty().not().then(|| (content, at))
+                    content.is_empty().not().then_some((content, at))
                 })
             })?;
             if f(ch, nat, at) {
                 let content = self.get_range_str(old_at..at);
-                return Ok(content.is_empty().not().then(|| (content, at)));
+                return Ok(content.is_empty().not().then_some((content, at)));
             }
             at = nat;
         }
     }
+    /// Consumes until `s` is found, like [`consume_until`](Parser::consume_until).
+    ///
+    /// This searches the rest of the input for `s` at once, instead of checking every character.
     #[inline(always)]
     fn consume_until_str(
         &self,
         at: usize,
         s: &str,
-    ) -> Result<Option<AtStr>, (ParserError, Option<AtStr>)> {
-        self.consume_until(at, |_, _, at| self.get_range_str(at..).starts_with(s))
+    ) -> Result<Option<AtStr<'_>>, (ParserError, Option<AtStr<'_>>)> {
+        let rest = self.get_range_str(at..);
+        match rest.find(s).filter(|_| rest.is_empty().not()) {
+            Some(len) => Ok((len > 0).then(|| (&rest[..len], at + len))),
+            None => Err((
+                ParserError::EOF,
+                rest.is_empty().not().then_some((rest, at + rest.len())),
+            )),
+        }
+    }
+    /// Returns `true` if there is only whitespace between the start of the line and `at`.
+    #[inline(always)]
+    fn is_line_start(&self, at: usize) -> bool {
+        self.get_range_str(..at)
+            .trim_end_matches(|c: char| c != '\n' && c.is_whitespace())
+            .chars()
+            .next_back()
+            .is_none_or(|c| c == '\n')
+    }
+    /// Returns `true` if the character before `at` is not a part of a word.
+    #[inline(always)]
+    fn is_word_boundary(&self, at: usize) -> bool {
+        self.get_range_str(..at)
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_alphanumeric().not())
     }
     #[inline(always)]
     fn eof(&self, at: usize) -> bool {
@@ -285,16 +1877,28 @@ pub trait Parser {
     fn consume_char(&self, at: usize) -> Result<(char, usize), ParserError> {
         self.next_char(at).map(|c| (c, at + char_bytes(c)))
     }
+    /// Converts the byte offset `at` into a line and column, see [`Position::from_offset`].
+    ///
+    /// Custom parsers can use this to report errors at user friendly positions.
+    fn position(&self, at: usize) -> Position {
+        Position::from_offset(self.get_range_str(..), at)
+    }
     /// Gets a string slice using the provided range.
     fn get_range_str<S: SliceIndex<str>>(&self, range: S) -> &S::Output;
-    /// Gets the character on index `at`.
+    /// Gets the character that starts at byte offset `at`, or [`ParserError::EOF`] if there is
+    /// none.
+    ///
+    /// Offsets are bytes, like the ones used by [`get_range_str`](Parser::get_range_str), not
+    /// characters, so this should be a constant time lookup.
     fn next_char(&self, at: usize) -> Result<char, ParserError>;
 }
 
-impl<'a> Parser for &'a str {
+impl Parser for &str {
     #[inline(always)]
     fn next_char(&self, at: usize) -> Result<char, ParserError> {
-        self.chars().nth(at).ok_or(ParserError::EOF)
+        self.get(at..)
+            .and_then(|rest| rest.chars().next())
+            .ok_or(ParserError::EOF)
     }
 
     #[inline(always)]
@@ -306,7 +1910,9 @@ impl<'a> Parser for &'a str {
 impl Parser for String {
     #[inline(always)]
     fn next_char(&self, at: usize) -> Result<char, ParserError> {
-        self.chars().nth(at).ok_or(ParserError::EOF)
+        self.get(at..)
+            .and_then(|rest| rest.chars().next())
+            .ok_or(ParserError::EOF)
     }
 
     #[inline(always)]
@@ -315,6 +1921,198 @@ impl Parser for String {
     }
 }
 
+impl Parser for Cow<'_, str> {
+    #[inline(always)]
+    fn next_char(&self, at: usize) -> Result<char, ParserError> {
+        self.as_ref().next_char(at)
+    }
+
+    #[inline(always)]
+    fn get_range_str<S: SliceIndex<str>>(&self, range: S) -> &S::Output {
+        &self.as_ref()[range]
+    }
+}
+
+impl Parser for alloc::rc::Rc<str> {
+    #[inline(always)]
+    fn next_char(&self, at: usize) -> Result<char, ParserError> {
+        self.as_ref().next_char(at)
+    }
+
+    #[inline(always)]
+    fn get_range_str<S: SliceIndex<str>>(&self, range: S) -> &S::Output {
+        &self.as_ref()[range]
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Parser for alloc::sync::Arc<str> {
+    #[inline(always)]
+    fn next_char(&self, at: usize) -> Result<char, ParserError> {
+        self.as_ref().next_char(at)
+    }
+
+    #[inline(always)]
+    fn get_range_str<S: SliceIndex<str>>(&self, range: S) -> &S::Output {
+        &self.as_ref()[range]
+    }
+}
+
+/// A parser that reads markdown from an iterator of characters one block at a time, so that
+/// the whole input doesn't have to be in memory.
+///
+/// Blocks end at blank lines, except in code fences, sections, comments and front matter, and
+/// before lines that could continue a list. Markup can't span the other blank lines, so the
+/// tokens of all blocks are the tokens of the whole input. Like in sections, code fences are
+/// found by counting the ` ``` ` on each line, so a ` ``` ` that is part of other markup can
+/// end a block early.
+///
+/// Tokens borrow the block they are parsed from, so this doesn't implement [`Parser`], which
+/// hands out slices of the whole input.
+///
+/// # Example
+/// ```
+/// # use linemd::{parser::StreamParser, Parser};
+/// let md = "# Title\n\nSome *text*.";
+/// let mut parser = StreamParser::new(md.chars());
+/// let mut kinds = Vec::new();
+/// while let Some(tokens) = parser.next_block() {
+///     kinds.extend(tokens.iter().map(|token| token.kind()));
+/// }
+/// assert_eq!(kinds, md.parse_md().iter().map(|token| token.kind()).collect::<Vec<_>>());
+/// ```
+pub struct StreamParser<I> {
+    chars: I,
+    config: Config,
+    /// The blank line that ended the block before, followed by the block being parsed.
+    buffer: String,
+    /// The line after the block, which is read to decide if the block ends.
+    next_line: String,
+    is_first_block: bool,
+}
+
+impl<I: Iterator<Item = char>> StreamParser<I> {
+    /// Create a parser that reads from the given characters, using the default configuration.
+    pub fn new(chars: I) -> Self {
+        Self::with_config(chars, Config::default())
+    }
+
+    /// Create a parser that reads from the given characters, using the given configuration.
+    pub fn with_config(chars: I, config: Config) -> Self {
+        Self {
+            chars,
+            config,
+            buffer: String::new(),
+            next_line: String::new(),
+            is_first_block: true,
+        }
+    }
+
+    /// Reads and parses the next block of the input, or returns `None` if all of it was read.
+    pub fn next_block(&mut self) -> Option<Vec<Token<'_, ()>>> {
+        // The blank line before the block is kept, so that it isn't parsed as the start of the
+        // input (which can have front matter).
+        let before = self
+            .buffer
+            .trim_end_matches('\n')
+            .rfind('\n')
+            .map_or(0, |at| at + 1);
+        self.buffer.drain(..before);
+        let start = self.buffer.len();
+
+        if self.is_first_block {
+            self.read_line();
+            self.read_front_matter();
+            self.is_first_block = false;
+        }
+        let mut in_fence = false;
+        let mut in_comment = false;
+        let mut details_depth = 0_usize;
+        while self.next_line.is_empty().not() {
+            let line = core::mem::take(&mut self.next_line);
+            self.read_line();
+            if self.config.code_fences && line.matches("```").count() % 2 == 1 {
+                in_fence = !in_fence;
+            }
+            match line.trim() {
+                trimmed if trimmed.starts_with(":::details") => details_depth += 1,
+                ":::" => details_depth = details_depth.saturating_sub(1),
+                _ => {}
+            }
+            in_comment = comment_is_open(&line, in_comment);
+            self.buffer.push_str(&line);
+
+            let starts_block = self.next_line.starts_with(char::is_whitespace).not()
+                && self.next_line.split_once('.').is_none_or(|(letters, _)| {
+                    letters.is_empty() || letters.bytes().any(|c| c.is_ascii_alphabetic().not())
+                });
+            let is_open = in_fence || in_comment || details_depth > 0;
+            if line.trim().is_empty() && is_open.not() && starts_block {
+                break;
+            }
+        }
+
+        (self.buffer.len() > start).then(|| {
+            let mut tokens = Vec::new();
+            let mut at = start;
+            while let Some((token, nat)) =
+                self.buffer.parse_token_with_config(at, &[], &self.config)
+            {
+                at = nat;
+                tokens.push(token);
+            }
+            if self.config.paragraphs {
+                tokens = mark_paragraphs(&tokens);
+            }
+            tokens
+        })
+    }
+
+    /// Reads the next line into `next_line`, with its line break.
+    fn read_line(&mut self) {
+        for c in self.chars.by_ref() {
+            self.next_line.push(c);
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    /// Reads the front matter at the start of the input into the buffer, if there is any.
+    fn read_front_matter(&mut self) {
+        let fence = match self.next_line.trim_end() {
+            fence @ ("---" | "+++") => String::from(fence),
+            _ => return,
+        };
+        loop {
+            let line = core::mem::take(&mut self.next_line);
+            self.read_line();
+            self.buffer.push_str(&line);
+            if self.next_line.is_empty() || self.next_line.trim_end() == fence {
+                break;
+            }
+        }
+    }
+}
+
+/// Returns `true` if a comment is open after the line, given whether one was open before it.
+fn comment_is_open(mut line: &str, mut is_open: bool) -> bool {
+    loop {
+        let (marker, next) = if is_open {
+            ("-->", false)
+        } else {
+            ("<!--", true)
+        };
+        match line.find(marker) {
+            Some(at) => {
+                line = &line[at + marker.len()..];
+                is_open = next;
+            }
+            None => return is_open,
+        }
+    }
+}
+
 #[inline(always)]
 fn try_handle_err(err: (ParserError, Option<AtStr<'_>>)) -> Option<AtText<'_>> {
     let (err, maybe_info) = err;
@@ -330,6 +2128,478 @@ fn char_bytes(c: char) -> usize {
     temp.len()
 }
 
+/// Collapses runs of three or more line breaks in tokens into two line breaks.
+///
+/// Two line breaks (ie. a blank line) already separate paragraphs, so more of them don't change
+/// the structure of a document; they only add empty lines to rendered output. The bodies of
+/// details sections are normalized too.
+///
+/// # Example
+/// ```
+/// # use linemd::{parser::{normalize_breaks, Text, Token}, Parser};
+/// let tokens = normalize_breaks(&"a\n\n\n\nb".parse_md());
+/// assert_eq!(
+///     tokens,
+///     vec![
+///         Token::Text(Text::naked("a")),
+///         Token::LineBreak,
+///         Token::LineBreak,
+///         Token::Text(Text::naked("b")),
+///     ]
+/// );
+/// ```
+pub fn normalize_breaks<'a, Custom: Clone>(tokens: &[Token<'a, Custom>]) -> Vec<Token<'a, Custom>> {
+    let mut normalized: Vec<Token<'a, Custom>> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let is_extra_break = token.is_line_break()
+            && matches!(
+                normalized.as_slice(),
+                [.., Token::LineBreak, Token::LineBreak]
+            );
+        if is_extra_break {
+            continue;
+        }
+        normalized.push(match token {
+            Token::Details { summary, body } => Token::Details {
+                summary: summary.clone(),
+                body: normalize_breaks(body),
+            },
+            Token::Alert { kind, body } => Token::Alert {
+                kind: *kind,
+                body: normalize_breaks(body),
+            },
+            token => token.clone(),
+        });
+    }
+    normalized
+}
+
+/// Merges adjacent naked text tokens into one token, if their values are next to each other
+/// in `source`.
+///
+/// Text is sometimes split into several tokens, like before a word a custom parser might
+/// recognize, or when tokens are built by hand. Merging them means less tokens for renderers
+/// to go through. Only naked text is merged, since formatted text is delimited, and the bodies
+/// of sections and alerts are merged too. `source` has to be the string the tokens were parsed
+/// from; texts outside of it are left as they are.
+///
+/// # Example
+/// ```
+/// # use linemd::parser::{coalesce_text, Text, Token};
+/// let source = "Some uninspiring text.";
+/// let tokens: [Token<()>; 2] = [
+///     Text::naked(&source[..5]).into_token(),
+///     Text::naked(&source[5..]).into_token(),
+/// ];
+/// assert_eq!(coalesce_text(source, &tokens), [Text::naked(source).into_token()]);
+/// ```
+pub fn coalesce_text<'a, Custom: Clone>(
+    source: &'a str,
+    tokens: &[Token<'a, Custom>],
+) -> Vec<Token<'a, Custom>> {
+    // Where a value is in the source, if it is in it.
+    let range_in_source = |value: &str| {
+        let start = (value.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+        let end = start + value.len();
+        (end <= source.len()).then_some(start..end)
+    };
+
+    let mut coalesced: Vec<Token<'a, Custom>> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let token = match token {
+            Token::Details { summary, body } => Token::Details {
+                summary: summary.clone(),
+                body: coalesce_text(source, body),
+            },
+            Token::Alert { kind, body } => Token::Alert {
+                kind: *kind,
+                body: coalesce_text(source, body),
+            },
+            token => token.clone(),
+        };
+        if let (Some(Token::Text(last)), Token::Text(text)) = (coalesced.last_mut(), &token) {
+            let is_naked = |t: &Text| t == &Text::naked(t.value);
+            let merged = (is_naked(last) && is_naked(text))
+                .then(|| Some((range_in_source(last.value)?, range_in_source(text.value)?)))
+                .flatten()
+                .filter(|(last, text)| last.end == text.start);
+            if let Some((last_range, text_range)) = merged {
+                last.value = &source[last_range.start..text_range.end];
+                continue;
+            }
+        }
+        coalesced.push(token);
+    }
+    coalesced
+}
+
+/// Marks the paragraphs in tokens with [`Token::ParagraphStart`] and [`Token::ParagraphEnd`].
+///
+/// A paragraph starts at an inline token (text, URLs, inline math and footnote references) that
+/// isn't on a header or list item line. It ends before a block token, before a blank line or a
+/// line break followed by a block token, and at the end of the tokens.
+///
+/// The HTML renderer uses this to decide where paragraphs are, so it renders tokens that are
+/// already marked the same as unmarked ones. The other renderers skip the markers and separate
+/// paragraphs by their blank lines.
+///
+/// # Example
+/// ```
+/// # use linemd::{parser::{mark_paragraphs, Text, Token}, Parser};
+/// let tokens = mark_paragraphs(&"Some uninspiring text.".parse_md());
+/// assert_eq!(
+///     tokens,
+///     vec![
+///         Token::ParagraphStart,
+///         Token::Text(Text::naked("Some uninspiring text.")),
+///         Token::ParagraphEnd,
+///     ]
+/// );
+/// ```
+pub fn mark_paragraphs<'a, Custom: Clone>(tokens: &[Token<'a, Custom>]) -> Vec<Token<'a, Custom>> {
+    let mut marked = Vec::with_capacity(tokens.len() + 2);
+    let mut in_paragraph = false;
+
+    let mut at = 0;
+    while at < tokens.len() {
+        let token = &tokens[at];
+        let is_line_break = token.is_line_break();
+
+        // Block elements can't be inside of a paragraph.
+        if in_paragraph && token.is_block() {
+            marked.push(Token::ParagraphEnd);
+            in_paragraph = false;
+        }
+
+        if in_paragraph {
+            if at + 1 >= tokens.len() {
+                if is_line_break {
+                    marked.push(Token::ParagraphEnd);
+                    marked.push(token.clone());
+                } else {
+                    marked.push(token.clone());
+                    marked.push(Token::ParagraphEnd);
+                }
+                in_paragraph = false;
+            } else if is_line_break && {
+                let next = &tokens[at + 1];
+                next.is_line_break() || next.is_block()
+            } {
+                marked.push(Token::ParagraphEnd);
+                marked.push(token.clone());
+                in_paragraph = false;
+            } else {
+                marked.push(token.clone());
+            }
+            at += 1;
+        } else if token.is_inline() {
+            marked.push(Token::ParagraphStart);
+            in_paragraph = true;
+        } else {
+            // Headers take the rest of their line, and list items their continuations too.
+            let end = match token {
+                Token::Header(_) => line_end(tokens, at),
+                Token::ListItem { .. } => list_item_end(tokens, at),
+                _ => at + 1,
+            };
+            marked.extend_from_slice(&tokens[at..end]);
+            at = end;
+        }
+    }
+
+    marked
+}
+
+/// Returns the width in columns of the indentation at the start of `line`.
+///
+/// Tabs advance to the next multiple of `tab_width`.
+fn indent_width(line: &str, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    line.chars()
+        .map_while(|c| match c {
+            ' ' => Some(1),
+            '\t' => Some(tab_width),
+            _ => None,
+        })
+        .fold(0, |col, width| {
+            if width == 1 {
+                col + 1
+            } else {
+                (col / width + 1) * width
+            }
+        })
+}
+
+/// Strips up to `width` columns of indentation from a line, like four columns from a line of
+/// an indented code block.
+///
+/// A tab that is only partly stripped is replaced with the spaces that are left of it.
+fn strip_indent(line: &str, width: usize, tab_width: usize) -> Cow<'_, str> {
+    let mut col = 0;
+    for (i, c) in line.char_indices() {
+        if col >= width {
+            return Cow::Borrowed(&line[i..]);
+        }
+        match c {
+            ' ' => col += 1,
+            '\t' => {
+                col = indent_width(&line[..=i], tab_width);
+                if col > width {
+                    let mut stripped = " ".repeat(col - width);
+                    stripped.push_str(&line[i + 1..]);
+                    return Cow::Owned(stripped);
+                }
+            }
+            _ => return Cow::Borrowed(&line[i..]),
+        }
+    }
+    Cow::Borrowed("")
+}
+
+/// Creates the URL token of an autolink that is written without a scheme, like
+/// `<user@example.com>` or `www.example.com`, which keeps the link as written as its name.
+fn autolink<'a, Custom>(scheme: &str, url: &'a str, config: &Config) -> Token<'a, Custom> {
+    Token::Url {
+        name: Some(Text::naked(url)),
+        url: if config.scan {
+            Cow::Borrowed(url)
+        } else {
+            Cow::Owned(format!("{}{}", scheme, url))
+        },
+        title: None,
+        is_image: false,
+        width: None,
+        height: None,
+    }
+}
+
+/// Returns `true` if `url` is an email address, like the ones in `<user@example.com>` autolinks.
+fn is_email(url: &str) -> bool {
+    let is_label = |label: &str| {
+        (1..=63).contains(&label.len())
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    };
+    url.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty()
+            && local
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c))
+            && domain.split('.').all(is_label)
+    })
+}
+
+/// Returns the start of `text` before its first blank line, since inline markup (like code,
+/// display math or autolinks) can't span one.
+fn until_blank_line(text: &str) -> &str {
+    let mut len = 0;
+    for line in text.split_inclusive('\n') {
+        if len > 0 && line.trim().is_empty() {
+            return &text[..len];
+        }
+        len += line.len();
+    }
+    text
+}
+
+/// Returns the scheme of the URL, if it has one.
+pub(crate) fn url_scheme(url: &str) -> Option<&str> {
+    url.split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        })
+}
+
+/// Returns the index of the line break that ends the line at `at`, or the length of the tokens.
+pub(crate) fn line_end<Custom>(tokens: &[Token<'_, Custom>], at: usize) -> usize {
+    tokens[at..]
+        .iter()
+        .position(Token::is_line_break)
+        .map_or(tokens.len(), |pos| at + pos)
+}
+
+/// Returns the end of the list item at `at`, ie. the end of its last continuation line.
+pub(crate) fn list_item_end<Custom>(tokens: &[Token<'_, Custom>], at: usize) -> usize {
+    let mut end = line_end(tokens, at);
+    loop {
+        let next = tokens[end..]
+            .iter()
+            .position(|t| !t.is_line_break())
+            .map_or(tokens.len(), |pos| end + pos);
+        match tokens.get(next) {
+            Some(Token::ListContinuation) => end = line_end(tokens, next),
+            _ => return end,
+        }
+    }
+}
+
+/// Returns the number of an ordered list item written like its marker (see
+/// [`Token::ListItem`]), ie. `3` is `c` for `a` markers and `iii` for `i` markers.
+///
+/// # Example
+/// ```
+/// # use linemd::parser::list_item_label;
+/// assert_eq!(list_item_label(3, '.'), "3");
+/// assert_eq!(list_item_label(28, 'A'), "AB");
+/// assert_eq!(list_item_label(14, 'i'), "xiv");
+/// ```
+pub fn list_item_label(place: usize, marker: char) -> String {
+    let mut label = match marker {
+        'a' | 'A' if place > 0 => {
+            // Like HTML, `z` is followed by `aa`.
+            let mut letters = Vec::new();
+            let mut rest = place;
+            while rest > 0 {
+                rest -= 1;
+                letters.push(b'a' + (rest % 26) as u8);
+                rest /= 26;
+            }
+            letters.iter().rev().map(|&c| c as char).collect()
+        }
+        'i' | 'I' if (1..4000).contains(&place) => roman(place),
+        _ => return format!("{}", place),
+    };
+    if marker.is_ascii_uppercase() {
+        label.make_ascii_uppercase();
+    }
+    label
+}
+
+/// Writes a number between 1 and 3999 in lowercase roman numerals.
+fn roman(number: usize) -> String {
+    roman_numerals(number).collect()
+}
+
+/// Returns the numerals that write a number between 1 and 3999 in lowercase roman numerals.
+fn roman_numerals(mut number: usize) -> impl Iterator<Item = &'static str> {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    NUMERALS.into_iter().flat_map(move |(value, numeral)| {
+        let count = number / value;
+        number %= value;
+        core::iter::repeat_n(numeral, count)
+    })
+}
+
+/// Returns the number and the marker of a list item numbered with `letters`.
+///
+/// Letters that make up a roman numeral (except single letters other than `i`, like `c`) are
+/// roman numerals, and other single letters are numbered alphabetically.
+fn letter_place(letters: &str) -> Option<(usize, char)> {
+    let lowercase = letters.bytes().all(|c| c.is_ascii_lowercase());
+    let uppercase = letters.bytes().all(|c| c.is_ascii_uppercase());
+    if !(lowercase || uppercase) {
+        return None;
+    }
+    let (alpha, roman_marker) = if lowercase { ('a', 'i') } else { ('A', 'I') };
+    if letters.len() == 1 && !letters.eq_ignore_ascii_case("i") {
+        let place = letters.as_bytes()[0].to_ascii_lowercase() - b'a' + 1;
+        return Some((place as usize, alpha));
+    }
+    // Roman numerals up to 3999 are at most 15 letters long.
+    if letters.len() > 15 {
+        return None;
+    }
+    let value = letters
+        .bytes()
+        .enumerate()
+        .try_fold(0, |sum: isize, (at, c)| {
+            let value = |c: u8| match c.to_ascii_lowercase() {
+                b'i' => Some(1),
+                b'v' => Some(5),
+                b'x' => Some(10),
+                b'l' => Some(50),
+                b'c' => Some(100),
+                b'd' => Some(500),
+                b'm' => Some(1000),
+                _ => None,
+            };
+            let this = value(c)?;
+            let next = letters
+                .as_bytes()
+                .get(at + 1)
+                .map_or(Some(0), |&c| value(c))?;
+            Some(if this < next { sum - this } else { sum + this })
+        })?;
+    // Only numerals written the usual way count, so `iiii` or `ic` aren't list items.
+    let value = usize::try_from(value)
+        .ok()
+        .filter(|value| (1..4000).contains(value))?;
+    let mut rest = letters;
+    let is_usual = roman_numerals(value).all(|numeral| {
+        let matches = rest
+            .get(..numeral.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(numeral));
+        if matches {
+            rest = &rest[numeral.len()..];
+        }
+        matches
+    });
+    (is_usual && rest.is_empty()).then_some((value, roman_marker))
+}
+
+/// Returns `true` if renderers should write a space after the token at `at`, which is only
+/// the case between two inline tokens.
+///
+/// The whitespace between tokens is kept in the naked text around them, which renderers write
+/// without its trailing whitespace. So a space is put back after naked text that ends with
+/// whitespace, unless the next text starts with it. Comments are skipped, so they don't change
+/// the spacing.
+pub(crate) fn needs_space<Custom>(tokens: &[Token<'_, Custom>], at: usize) -> bool {
+    let token = &tokens[at];
+    let next = tokens[at + 1..]
+        .iter()
+        .find(|token| !matches!(token, Token::Comment(_)));
+    let Some(next) = next.filter(|next| token.is_inline() && next.is_inline()) else {
+        return false;
+    };
+    let is_naked = |t: &Text| !(t.bold || t.italic || t.code || t.highlight);
+    match (token, next) {
+        (Token::Text(_), Token::Text(next))
+            if is_naked(next) && next.value.starts_with(char::is_whitespace) =>
+        {
+            false
+        }
+        (Token::Text(t), _) => is_naked(t) && t.value.ends_with(char::is_whitespace),
+        _ => false,
+    }
+}
+
+/// Returns the start of `rest` that is at most `max_len` bytes long, without splitting a
+/// character.
+fn truncate(rest: &str, max_len: usize) -> &str {
+    let mut end = max_len.min(rest.len());
+    while rest.is_char_boundary(end).not() {
+        end -= 1;
+    }
+    &rest[..end]
+}
+
+/// Returns `true` if `rest` starts with a line break followed by a blank line.
+fn is_blank_line_after(rest: &str) -> bool {
+    rest.strip_prefix('\n')
+        .and_then(|rest| rest.split('\n').next())
+        .is_some_and(|line| line.trim().is_empty())
+}
+
 #[inline(always)]
 const fn is_backtick(c: char) -> bool {
     c == '`'
@@ -343,24 +2613,649 @@ pub enum Token<'a, Custom: 'a> {
     /// An URL.
     Url {
         /// Name of this URL (ie. the text in `[]`, if it exists).
+        ///
+        /// Unless the whole name is emphasized, it is kept as naked text, and renderers parse
+        /// it for inline markup (like code or images).
         name: Option<Text<'a>>,
         /// Actual URL. Note that this does not get checked to see if it's a valid URL or not.
-        url: &'a str,
+        ///
+        /// Autolinks of email addresses (like `<user@example.com>`) and `www.` autolinks get a
+        /// `mailto:` or `http://` scheme, so the URL is owned, and keep the address as written
+        /// as their name.
+        url: Cow<'a, str>,
+        /// Title of this URL (ie. the quoted text after the URL in `()`, if it exists).
+        title: Option<&'a str>,
+        /// `true` if this URL is an image (ie. `![alt](url)`).
         is_image: bool,
+        /// Width of this image in pixels, if given (ie. the `200` in `![alt](url =200x100)`).
+        width: Option<usize>,
+        /// Height of this image in pixels, if given (ie. the `100` in `![alt](url =200x100)`).
+        height: Option<usize>,
     },
     /// A header.
     Header(usize),
     /// A list item, which can be ordered or unordered.
     /// If `None`, then it is an unordered item.
-    ListItem(Option<usize>),
-    /// A code fence. (\`\`\`)
-    CodeFence { code: &'a str, attrs: &'a str },
+    ListItem {
+        /// The number of the item if it is ordered, or `None` if it is unordered.
+        place: Option<usize>,
+        /// The marker the item was written with: `-`, `+` or `*` for unordered items. For
+        /// ordered items, it's `.` (the delimiter after the number) if they are numbered with
+        /// numbers, `a` or `A` if they are numbered with letters, and `i` or `I` if they are
+        /// numbered with roman numerals. See [`list_item_label`].
+        marker: char,
+    },
+    /// The start of an indented line that continues the preceding list item.
+    ///
+    /// A list item with a blank line before one of its continuations is a loose item, and its
+    /// content is made of paragraphs.
+    ListContinuation,
+    /// Math written in TeX, either inline (`$...$`) or display (`$$...$$`).
+    Math {
+        /// The TeX source of the math.
+        content: &'a str,
+        /// `true` if this is display math.
+        display: bool,
+    },
+    /// A reference to a footnote (ie. `[^id]`).
+    FootnoteRef(&'a str),
+    /// A footnote definition (ie. `[^id]: body`).
+    FootnoteDef {
+        /// The id of the footnote.
+        id: &'a str,
+        /// The body of the footnote, unparsed.
+        body: &'a str,
+    },
+    /// A collapsible section (ie. a `:::details Summary` line, some markdown and a `:::` line).
+    Details {
+        /// The summary, which is always shown.
+        summary: Text<'a>,
+        /// The parsed tokens of the body, which is shown when the section is expanded.
+        body: Vec<Token<'a, Custom>>,
+    },
+    /// A GitHub style alert (ie. a `> [!NOTE]` line followed by lines starting with `>`).
+    Alert {
+        /// The kind of the alert.
+        kind: AlertKind,
+        /// The parsed tokens of the body, without the `>` markers.
+        body: Vec<Token<'a, Custom>>,
+    },
+    /// A definition list (ie. a `term` line followed by `: description` lines).
+    DefinitionList(Vec<Definition<'a>>),
+    /// A raw HTML block, see [`Config::html`].
+    Html(&'a str),
+    /// An HTML comment (ie. `<!-- comment -->`), with the content between `<!--` and `-->`.
+    ///
+    /// An unterminated `<!--` is text.
+    Comment(&'a str),
+    /// Front matter at the start of the input (ie. a `---` or `+++` line, some metadata and a
+    /// closing line with the same fence).
+    ///
+    /// Renderers leave it out of the output.
+    FrontMatter {
+        /// The format of the front matter, decided by its fence.
+        format: FrontMatterFormat,
+        /// The front matter between the fences, unparsed.
+        raw: &'a str,
+    },
+    /// A code fence (\`\`\`), or an indented code block.
+    ///
+    /// The code is only owned if it had to be changed, like when the indentation of an
+    /// indented code block is stripped.
+    CodeFence { code: Cow<'a, str>, attrs: &'a str },
     /// A line break.
     LineBreak,
+    /// The start of a paragraph, see [`mark_paragraphs`].
+    ParagraphStart,
+    /// The end of a paragraph, see [`mark_paragraphs`].
+    ParagraphEnd,
     /// A custom token.
     Custom(Custom),
 }
 
+impl<'a, Custom> Token<'a, Custom> {
+    /// Create an URL token without a name, like the one of `<https://example.org>`.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::Token, Parser};
+    /// assert_eq!("<https://example.org>".parse_md(), [Token::url("https://example.org")]);
+    /// ```
+    pub const fn url(url: &'a str) -> Self {
+        Self::Url {
+            name: None,
+            url: Cow::Borrowed(url),
+            title: None,
+            is_image: false,
+            width: None,
+            height: None,
+        }
+    }
+
+    /// Create a link token, like the one of `[name](url)`. An empty name is no name, like when
+    /// parsing.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::Token, Parser};
+    /// assert_eq!("[name](url)".parse_md(), [Token::link("name", "url")]);
+    /// ```
+    pub const fn link(name: &'a str, url: &'a str) -> Self {
+        Self::Url {
+            name: naked_or_none(name),
+            url: Cow::Borrowed(url),
+            title: None,
+            is_image: false,
+            width: None,
+            height: None,
+        }
+    }
+
+    /// Create an image token, like the one of `![alt](url)`. An empty alt text is no alt text,
+    /// like when parsing.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::Token, Parser};
+    /// assert_eq!("![alt](url)".parse_md(), [Token::image("alt", "url")]);
+    /// ```
+    pub const fn image(alt: &'a str, url: &'a str) -> Self {
+        Self::Url {
+            name: naked_or_none(alt),
+            url: Cow::Borrowed(url),
+            title: None,
+            is_image: true,
+            width: None,
+            height: None,
+        }
+    }
+
+    /// Create a header token of the given depth, like the one of `## Header`.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::{Text, Token}, Parser};
+    /// assert_eq!("## Header".parse_md(), [Token::header(2), Text::naked("Header").into_token()]);
+    /// ```
+    pub const fn header(depth: usize) -> Self {
+        Self::Header(depth)
+    }
+}
+
+/// Returns `value` as naked text, or `None` if it's empty.
+const fn naked_or_none(value: &str) -> Option<Text<'_>> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(Text::naked(value))
+    }
+}
+
+impl<Custom> Token<'_, Custom> {
+    /// Returns the kind of this token, without its contents.
+    pub const fn kind(&self) -> TokenKind {
+        match self {
+            Token::Text(_) => TokenKind::Text,
+            Token::Url { .. } => TokenKind::Url,
+            Token::Header(_) => TokenKind::Header,
+            Token::ListItem { .. } => TokenKind::ListItem,
+            Token::ListContinuation => TokenKind::ListContinuation,
+            Token::Math { .. } => TokenKind::Math,
+            Token::FootnoteRef(_) => TokenKind::FootnoteRef,
+            Token::FootnoteDef { .. } => TokenKind::FootnoteDef,
+            Token::Details { .. } => TokenKind::Details,
+            Token::Alert { .. } => TokenKind::Alert,
+            Token::DefinitionList(_) => TokenKind::DefinitionList,
+            Token::Html(_) => TokenKind::Html,
+            Token::Comment(_) => TokenKind::Comment,
+            Token::FrontMatter { .. } => TokenKind::FrontMatter,
+            Token::CodeFence { .. } => TokenKind::CodeFence,
+            Token::LineBreak => TokenKind::LineBreak,
+            Token::ParagraphStart => TokenKind::ParagraphStart,
+            Token::ParagraphEnd => TokenKind::ParagraphEnd,
+            Token::Custom(_) => TokenKind::Custom,
+        }
+    }
+
+    /// Returns `true` if the token is a block element, which ends a paragraph.
+    ///
+    /// Tokens that are neither blocks nor inline (like line breaks, comments and paragraph
+    /// markers) only affect the structure around them.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::Parser;
+    /// assert!("# Title".parse_md()[0].is_block());
+    /// ```
+    pub const fn is_block(&self) -> bool {
+        match self {
+            Token::Header(_)
+            | Token::ListItem { .. }
+            | Token::CodeFence { .. }
+            | Token::Html(_)
+            | Token::FootnoteDef { .. }
+            | Token::DefinitionList(_)
+            | Token::Details { .. }
+            | Token::Alert { .. } => true,
+            Token::Math { display, .. } => *display,
+            Token::Text(_)
+            | Token::Url { .. }
+            | Token::FootnoteRef(_)
+            | Token::Custom(_)
+            | Token::ListContinuation
+            | Token::Comment(_)
+            | Token::FrontMatter { .. }
+            | Token::LineBreak
+            | Token::ParagraphStart
+            | Token::ParagraphEnd => false,
+        }
+    }
+
+    /// Returns `true` if the token is an inline element, which can be in a paragraph.
+    ///
+    /// Custom tokens are treated as inline.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::Parser;
+    /// assert!("*text*".parse_md()[0].is_inline());
+    /// ```
+    pub const fn is_inline(&self) -> bool {
+        match self {
+            Token::Text(_) | Token::Url { .. } | Token::FootnoteRef(_) | Token::Custom(_) => true,
+            Token::Math { display, .. } => !*display,
+            Token::Header(_)
+            | Token::ListItem { .. }
+            | Token::CodeFence { .. }
+            | Token::Html(_)
+            | Token::FootnoteDef { .. }
+            | Token::DefinitionList(_)
+            | Token::Details { .. }
+            | Token::Alert { .. }
+            | Token::ListContinuation
+            | Token::Comment(_)
+            | Token::FrontMatter { .. }
+            | Token::LineBreak
+            | Token::ParagraphStart
+            | Token::ParagraphEnd => false,
+        }
+    }
+
+    /// Returns `true` if the token is a [`Token::LineBreak`].
+    pub const fn is_line_break(&self) -> bool {
+        matches!(self, Token::LineBreak)
+    }
+
+    /// Returns `true` if the tokens have the same content, ignoring how their text is
+    /// formatted.
+    ///
+    /// Text (including the names of links and the summaries of details) is compared by its
+    /// plain text, so emphasis, code, highlights, scripts and surrounding whitespace don't matter.
+    /// The bodies of details and alerts are compared token by token, skipping text that is only
+    /// whitespace. Other tokens are compared with [`PartialEq`]. This is useful for diffing
+    /// documents by their content.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::Parser;
+    /// let (bold, naked) = ("**a**".parse_md(), "a".parse_md());
+    /// assert_ne!(bold, naked);
+    /// assert!(bold[0].text_eq(&naked[0]));
+    /// ```
+    pub fn text_eq(&self, other: &Self) -> bool
+    where
+        Custom: PartialEq,
+    {
+        let text = |t: &Text| crate::render_as_plain::<()>([t.clone().into_token()]);
+        let body_eq = |a: &[Self], b: &[Self]| {
+            let is_space = |t: &&Self| matches!(t, Token::Text(t) if t.value.trim().is_empty());
+            let (mut a, mut b) = (
+                a.iter().filter(|t| !is_space(t)),
+                b.iter().filter(|t| !is_space(t)),
+            );
+            loop {
+                match (a.next(), b.next()) {
+                    (Some(a), Some(b)) if a.text_eq(b) => {}
+                    (None, None) => return true,
+                    _ => return false,
+                }
+            }
+        };
+        match (self, other) {
+            (Token::Text(a), Token::Text(b)) => text(a).trim() == text(b).trim(),
+            (
+                Token::Url {
+                    name: a_name,
+                    url: a_url,
+                    title: a_title,
+                    is_image: a_is_image,
+                    width: a_width,
+                    height: a_height,
+                },
+                Token::Url {
+                    name: b_name,
+                    url: b_url,
+                    title: b_title,
+                    is_image: b_is_image,
+                    width: b_width,
+                    height: b_height,
+                },
+            ) => {
+                (a_url, a_title, a_is_image, a_width, a_height)
+                    == (b_url, b_title, b_is_image, b_width, b_height)
+                    && a_name.as_ref().map(text) == b_name.as_ref().map(text)
+            }
+            (
+                Token::Details {
+                    summary: a_summary,
+                    body: a_body,
+                },
+                Token::Details {
+                    summary: b_summary,
+                    body: b_body,
+                },
+            ) => body_eq(a_body, b_body) && text(a_summary) == text(b_summary),
+            (
+                Token::Alert {
+                    kind: a_kind,
+                    body: a_body,
+                },
+                Token::Alert {
+                    kind: b_kind,
+                    body: b_body,
+                },
+            ) => a_kind == b_kind && body_eq(a_body, b_body),
+            (a, b) => a == b,
+        }
+    }
+
+    /// Estimates how many columns this token takes up when rendered as plain text, in a
+    /// monospace font. Tokens spanning multiple lines use their widest line.
+    ///
+    /// See [`display_width`](crate::wrap::display_width) for how the width is estimated.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::Parser;
+    /// let tokens = "*\u{4f60}\u{597d}* [link](https://example.org)".parse_md();
+    /// assert_eq!(tokens[0].display_width(), 4);
+    /// assert_eq!(tokens[2].display_width(), 4);
+    /// ```
+    pub fn display_width(&self) -> usize {
+        crate::render_as_plain(core::slice::from_ref(self))
+            .lines()
+            .map(crate::wrap::display_width)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Writes the token as markdown, in the form the parser reads it back from.
+///
+/// Only this token is written, so whitespace around it is left out, and a list item is only
+/// its marker. Line breaks are written as newlines.
+///
+/// # Example
+/// ```
+/// # use linemd::Parser;
+/// let tokens = "## A **bold** [link](https://example.org)".parse_md();
+/// let markdown: Vec<_> = tokens.iter().map(|token| token.to_string()).collect();
+/// assert_eq!(markdown, ["## ", "A", "**bold**", "", "[link](https://example.org)"]);
+/// ```
+impl Display for Token<'_, ()> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Text(t) => write_text_markdown(f, t),
+            Token::Url {
+                name,
+                url,
+                title,
+                is_image,
+                width,
+                height,
+            } => {
+                let is_autolink = name.is_none() && !*is_image && title.is_none();
+                if is_autolink {
+                    return write!(f, "<{}>", url);
+                }
+                if *is_image {
+                    f.write_char('!')?;
+                }
+                f.write_char('[')?;
+                if let Some(name) = name {
+                    write_text_markdown(f, name)?;
+                }
+                write!(f, "]({}", url)?;
+                if width.is_some() || height.is_some() {
+                    f.write_str(" =")?;
+                    if let Some(width) = width {
+                        write!(f, "{}", width)?;
+                    }
+                    f.write_char('x')?;
+                    if let Some(height) = height {
+                        write!(f, "{}", height)?;
+                    }
+                }
+                if let Some(title) = title {
+                    write!(f, r#" "{}""#, title)?;
+                }
+                f.write_char(')')
+            }
+            Token::Header(depth) => write!(f, "{} ", "#".repeat(*depth)),
+            Token::ListItem {
+                place: Some(place),
+                marker,
+            } => match marker {
+                'a' | 'A' | 'i' | 'I' => write!(f, "{}. ", list_item_label(*place, *marker)),
+                _ => write!(f, "{}{} ", place, marker),
+            },
+            Token::ListItem {
+                place: None,
+                marker,
+            } => write!(f, "{} ", marker),
+            Token::ListContinuation => f.write_str("  "),
+            Token::Math { content, display } => {
+                let fence = if *display { "$$" } else { "$" };
+                write!(f, "{0}{1}{0}", fence, content)
+            }
+            Token::FootnoteRef(id) => write!(f, "[^{}]", id),
+            Token::FootnoteDef { id, body } => write!(f, "[^{}]: {}", id, body),
+            Token::Details { summary, body } => {
+                f.write_str(":::details ")?;
+                write_text_markdown(f, summary)?;
+                f.write_char('\n')?;
+                write_tokens_markdown(f, body)?;
+                f.write_str("\n:::")
+            }
+            Token::Alert { kind, body } => {
+                write!(f, "> [!{}]", kind.name().to_uppercase())?;
+                let mut body_markdown = String::new();
+                write_tokens_markdown(&mut body_markdown, body)?;
+                body_markdown
+                    .lines()
+                    .try_for_each(|line| write!(f, "\n> {}", line))
+            }
+            Token::DefinitionList(definitions) => {
+                for (index, Definition { term, descriptions }) in definitions.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str("\n\n")?;
+                    }
+                    f.write_str(term)?;
+                    for description in descriptions {
+                        write!(f, "\n: {}", description)?;
+                    }
+                }
+                Ok(())
+            }
+            Token::Html(html) => f.write_str(html),
+            Token::Comment(comment) => write!(f, "<!--{}-->", comment),
+            Token::FrontMatter { format, raw } => {
+                let fence = match format {
+                    FrontMatterFormat::Yaml => "---",
+                    FrontMatterFormat::Toml => "+++",
+                };
+                write!(f, "{0}\n{1}\n{0}", fence, raw)
+            }
+            Token::CodeFence { code, attrs } => {
+                let newline = if code.ends_with('\n') { "" } else { "\n" };
+                write!(f, "```{}\n{}{}```", attrs, code, newline)
+            }
+            Token::LineBreak => f.write_char('\n'),
+            Token::ParagraphStart | Token::ParagraphEnd | Token::Custom(()) => Ok(()),
+        }
+    }
+}
+
+/// Writes text as markdown, wrapped in the markers of its formatting. See [`Token`]'s
+/// [`Display`] implementation.
+fn write_text_markdown(f: &mut dyn Write, t: &Text) -> fmt::Result {
+    let value = t.value.trim_end();
+    if t.code {
+        let fence = if value.contains('`') { "``" } else { "`" };
+        let padding = if value.contains('`') { " " } else { "" };
+        return write!(f, "{0}{1}{2}{1}{0}", fence, padding, value);
+    }
+    let markers = [
+        (t.highlight, "=="),
+        (t.bold, "**"),
+        (t.italic, "*"),
+        (t.subscript, "~"),
+        (t.superscript, "^"),
+    ];
+    let markers = markers.iter().filter(|(is_set, _)| *is_set);
+    markers
+        .clone()
+        .try_for_each(|(_, marker)| f.write_str(marker))?;
+    f.write_str(value)?;
+    markers
+        .rev()
+        .try_for_each(|(_, marker)| f.write_str(marker))
+}
+
+/// Writes tokens as markdown, putting back the spaces between them.
+fn write_tokens_markdown(f: &mut dyn Write, tokens: &[Token<'_, ()>]) -> fmt::Result {
+    for at in 0..tokens.len() {
+        write!(f, "{}", tokens[at])?;
+        if needs_space(tokens, at) {
+            f.write_char(' ')?;
+        }
+    }
+    Ok(())
+}
+
+/// The kind of a [`Token`], without its contents. See [`Parser::scan_token`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TokenKind {
+    /// See [`Token::Text`].
+    Text,
+    /// See [`Token::Url`].
+    Url,
+    /// See [`Token::Header`].
+    Header,
+    /// See [`Token::ListItem`].
+    ListItem,
+    /// See [`Token::ListContinuation`].
+    ListContinuation,
+    /// See [`Token::Math`].
+    Math,
+    /// See [`Token::FootnoteRef`].
+    FootnoteRef,
+    /// See [`Token::FootnoteDef`].
+    FootnoteDef,
+    /// See [`Token::Details`].
+    Details,
+    /// See [`Token::Alert`].
+    Alert,
+    /// See [`Token::DefinitionList`].
+    DefinitionList,
+    /// See [`Token::Html`].
+    Html,
+    /// See [`Token::Comment`].
+    Comment,
+    /// See [`Token::FrontMatter`].
+    FrontMatter,
+    /// See [`Token::CodeFence`].
+    CodeFence,
+    /// See [`Token::LineBreak`].
+    LineBreak,
+    /// See [`Token::ParagraphStart`].
+    ParagraphStart,
+    /// See [`Token::ParagraphEnd`].
+    ParagraphEnd,
+    /// See [`Token::Custom`].
+    Custom,
+}
+
+/// The kind of an alert, see [`Token::Alert`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AlertKind {
+    /// Useful information (ie. `[!NOTE]`).
+    Note,
+    /// Helpful advice (ie. `[!TIP]`).
+    Tip,
+    /// Key information (ie. `[!IMPORTANT]`).
+    Important,
+    /// Urgent information that needs attention (ie. `[!WARNING]`).
+    Warning,
+    /// Advice about risks (ie. `[!CAUTION]`).
+    Caution,
+}
+
+impl AlertKind {
+    /// Returns the kind with the given name, ignoring case (ie. `note` or `NOTE`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            AlertKind::Note,
+            AlertKind::Tip,
+            AlertKind::Important,
+            AlertKind::Warning,
+            AlertKind::Caution,
+        ]
+        .into_iter()
+        .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the name of the kind in lowercase (ie. `note`).
+    pub const fn name(self) -> &'static str {
+        match self {
+            AlertKind::Note => "note",
+            AlertKind::Tip => "tip",
+            AlertKind::Important => "important",
+            AlertKind::Warning => "warning",
+            AlertKind::Caution => "caution",
+        }
+    }
+
+    /// Returns the title that is shown for the kind (ie. `Note`).
+    pub const fn title(self) -> &'static str {
+        match self {
+            AlertKind::Note => "Note",
+            AlertKind::Tip => "Tip",
+            AlertKind::Important => "Important",
+            AlertKind::Warning => "Warning",
+            AlertKind::Caution => "Caution",
+        }
+    }
+}
+
+/// The format of front matter, see [`Token::FrontMatter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FrontMatterFormat {
+    /// YAML, fenced by `---` lines.
+    Yaml,
+    /// TOML, fenced by `+++` lines.
+    Toml,
+}
+
+/// A term of a definition list, with its descriptions.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Definition<'a> {
+    /// The term, unparsed.
+    pub term: &'a str,
+    /// The descriptions of the term, unparsed.
+    pub descriptions: Vec<&'a str>,
+}
+
 /// Some text.
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Text<'a> {
@@ -372,6 +3267,15 @@ pub struct Text<'a> {
     pub italic: bool,
     /// `true` if this text is code.
     pub code: bool,
+    /// `true` if this text is a subscript (ie. `~sub~`).
+    pub subscript: bool,
+    /// `true` if this text is a superscript (ie. `^sup^`).
+    pub superscript: bool,
+    /// `true` if this text is highlighted (ie. `==highlight==`).
+    pub highlight: bool,
+    /// `true` if images in the markup nested in this text, or in this link name, are kept as
+    /// text, since images were disabled when it was parsed. See [`Config::images`].
+    pub images_as_text: bool,
 }
 
 impl<'a> Text<'a> {
@@ -382,16 +3286,24 @@ impl<'a> Text<'a> {
             code: true,
             italic: false,
             bold: false,
+            subscript: false,
+            superscript: false,
+            highlight: false,
+            images_as_text: false,
         }
     }
 
-    /// Create a "naked" text, ie. not italic, bold or code.
+    /// Create a "naked" text, ie. not italic, bold, code or highlighted.
     pub const fn naked(value: &'a str) -> Self {
         Self {
             value,
             code: false,
             italic: false,
             bold: false,
+            subscript: false,
+            superscript: false,
+            highlight: false,
+            images_as_text: false,
         }
     }
 
@@ -399,4 +3311,39 @@ impl<'a> Text<'a> {
     pub const fn into_token<Custom>(self) -> Token<'a, Custom> {
         Token::Text(self)
     }
+
+    /// Parses the markup nested in this text, like the italic text in `**bold _and italic_**`.
+    ///
+    /// Only emphasis and highlights can have nested markup, so for other text this returns
+    /// `None`. The nested markup is inline, so block syntax like `# ` or `- ` is kept as text.
+    /// Renderers render the nested tokens inside of the formatting of this text.
+    ///
+    /// # Example
+    /// ```
+    /// # use linemd::{parser::{Text, Token}, Parser};
+    /// let tokens = "**bold _and italic_**".parse_md();
+    /// let Token::Text(bold) = &tokens[0] else { unreachable!() };
+    /// let nested = bold.parse_nested::<()>().unwrap();
+    /// assert_eq!(nested[0], Text::naked("bold ").into_token());
+    /// assert!(matches!(&nested[1], Token::Text(t) if t.italic && t.value == "and italic"));
+    /// ```
+    pub fn parse_nested<Custom>(&self) -> Option<Vec<Token<'_, Custom>>> {
+        let has_nested = (self.bold || self.italic || self.highlight) && !self.code;
+        has_nested.then(|| {
+            let config = Config::default().images(self.images_as_text.not());
+            let mut tokens = Vec::new();
+            let mut at = 0;
+            while let Some((token, nat)) = self.value.parse_nested_token(at, &config) {
+                at = nat;
+                tokens.push(token);
+            }
+            tokens
+        })
+    }
+
+    /// Estimates how many columns this text takes up when rendered as plain text, in a
+    /// monospace font. See [`Token::display_width`].
+    pub fn display_width(&self) -> usize {
+        self.clone().into_token::<()>().display_width()
+    }
 }
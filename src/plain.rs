@@ -0,0 +1,160 @@
+use crate::parser::{needs_space, Definition, Text, Token};
+
+use super::*;
+use alloc::{borrow::Cow, format};
+
+/// Renders parsed tokens as plain text, without any markup.
+///
+/// Only the text of headers, list items and emphasis is kept. Links are rendered as their
+/// names (or URLs, if they don't have one), and code fences and math as their content. Raw
+/// HTML and footnote references are left out, and footnote definitions are rendered as their
+/// text. This is useful for search indexing, previews or meta descriptions.
+///
+/// # Example
+/// ```
+/// # use linemd::{render_as_plain, Parser};
+/// let text = render_as_plain("# Title\nSome *uninspiring* [text](https://example.org).".parse_md());
+/// assert_eq!(text, "Title\nSome uninspiring text.");
+/// ```
+pub fn render_as_plain<'a, Custom: 'a>(tokens: impl AsRef<[Token<'a, Custom>]> + 'a) -> String {
+    let mut buf = String::new();
+    render_to_buffer(tokens, &mut buf);
+    buf
+}
+
+/// Renders parsed tokens as plain text, to a buffer. See [`render_as_plain`].
+pub fn render_to_buffer<'a, Custom: 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    buf: &mut String,
+) {
+    let tokens = tokens.as_ref();
+    for (at, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Text(t) if t.code => buf.push_str(t.value),
+            Token::Text(t) => match t.parse_nested::<()>() {
+                Some(nested) => render_to_buffer(nested, buf),
+                None => buf.push_str(t.value.trim_end()),
+            },
+            Token::Url { name, url, .. } => match name {
+                Some(t) if t == &Text::naked(t.value) => {
+                    // Links can't contain links, so links in the name are written as their text.
+                    let tokens: Vec<Token<()>> = t
+                        .value
+                        .parse_inline()
+                        .into_iter()
+                        .map(|token| match token {
+                            Token::Url {
+                                name: Some(name),
+                                is_image: false,
+                                ..
+                            } => Token::Text(name),
+                            Token::Url {
+                                url: Cow::Borrowed(url),
+                                is_image: false,
+                                ..
+                            } => Text::naked(url).into_token(),
+                            token => token,
+                        })
+                        .collect();
+                    render_to_buffer(tokens, buf)
+                }
+                Some(t) => buf.push_str(t.value.trim_end()),
+                None => buf.push_str(url),
+            },
+            Token::Math { content, .. } => buf.push_str(content),
+            Token::CodeFence { code, .. } => buf.push_str(code),
+            Token::FootnoteDef { body, .. } => render_to_buffer(body.parse_md(), buf),
+            Token::Details { summary, body } => {
+                buf.push_str(summary.value.trim_end());
+                buf.push('\n');
+                render_to_buffer(body, buf);
+            }
+            Token::Alert { kind, body } => {
+                buf.push_str(kind.title());
+                buf.push('\n');
+                render_to_buffer(body, buf);
+            }
+            Token::DefinitionList(definitions) => {
+                for (index, Definition { term, descriptions }) in definitions.iter().enumerate() {
+                    if index > 0 {
+                        buf.push('\n');
+                    }
+                    render_to_buffer(term.parse_inline(), buf);
+                    for description in descriptions {
+                        buf.push('\n');
+                        render_to_buffer(description.parse_inline(), buf);
+                    }
+                }
+            }
+            Token::LineBreak => buf.push('\n'),
+            Token::Header(_)
+            | Token::ListItem { .. }
+            | Token::ListContinuation
+            | Token::FootnoteRef(_)
+            | Token::Html(_)
+            | Token::Comment(_)
+            | Token::FrontMatter { .. }
+            | Token::ParagraphStart
+            | Token::ParagraphEnd
+            | Token::Custom(_) => {}
+        }
+        if needs_space(tokens, at) {
+            buf.push(' ');
+        }
+    }
+}
+
+/// Renders parsed tokens as a short plain text preview, of at most about `max_chars`
+/// characters.
+///
+/// The text is rendered like [`render_as_plain`] does, but code fences, display math, raw HTML
+/// and footnote definitions are left out, and whitespace (including line breaks) is collapsed
+/// into single spaces. If the text is longer than `max_chars`, it is cut at the last word that
+/// fits and an ellipsis (`…`) is put after it, which counts towards `max_chars`. This is useful
+/// for card previews and search snippets.
+///
+/// # Example
+/// ```
+/// # use linemd::{render_preview, Parser};
+/// let tokens = "# Title\nSome *uninspiring* text.\n```\ncode\n```".parse_md();
+/// assert_eq!(render_preview(&tokens, 100), "Title Some uninspiring text.");
+/// assert_eq!(render_preview(&tokens, 20), "Title Some…");
+/// ```
+pub fn render_preview<'a, Custom: 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    max_chars: usize,
+) -> String {
+    let tokens = tokens.as_ref();
+    let mut text = String::new();
+    for (at, token) in tokens.iter().enumerate() {
+        let is_shown = !matches!(
+            token,
+            Token::CodeFence { .. }
+                | Token::Math { display: true, .. }
+                | Token::Html(_)
+                | Token::FootnoteDef { .. }
+        );
+        if is_shown {
+            render_to_buffer(core::slice::from_ref(token), &mut text);
+            if needs_space(tokens, at) {
+                text.push(' ');
+            }
+        }
+    }
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    // Leave room for the ellipsis.
+    let end = text
+        .char_indices()
+        .nth(max_chars.saturating_sub(1))
+        .map_or(text.len(), |(end, _)| end);
+    let mut preview = &text[..end];
+    // A word that doesn't fit is left out, unless it's the only one.
+    if !text[end..].starts_with(' ') {
+        preview = preview.rfind(' ').map_or(preview, |end| &preview[..end]);
+    }
+    format!("{}…", preview)
+}
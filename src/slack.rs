@@ -0,0 +1,171 @@
+use core::fmt::Write;
+
+use crate::{
+    parser::{list_item_label, needs_space, Definition, Text, Token},
+    toc::line_text,
+};
+
+use super::*;
+
+/// Renders parsed tokens as Slack's `mrkdwn`, the markdown dialect used in chat messages.
+///
+/// Bold text is rendered as `*bold*`, italic text as `_italic_` and code as `` `code` ``.
+/// Links are rendered as `<url|name>` (or `<url>`, if they don't have a name), and headers,
+/// which chat messages don't have, as bold lines. `&`, `<` and `>` are escaped, as Slack
+/// expects. Raw HTML and comments are left out. This is useful for sending markdown content
+/// from chat bots.
+///
+/// # Example
+/// ```
+/// # use linemd::{render_as_slack, Parser};
+/// let text = render_as_slack("# Title\nSome *uninspiring* [text](https://example.org).".parse_md());
+/// assert_eq!(text, "*Title*\nSome _uninspiring_ <https://example.org|text>.");
+/// ```
+pub fn render_as_slack<'a, Custom: 'a>(tokens: impl AsRef<[Token<'a, Custom>]> + 'a) -> String {
+    let mut buf = String::new();
+    render_to_buffer(tokens, &mut buf);
+    buf
+}
+
+/// Renders parsed tokens as Slack's `mrkdwn`, to a buffer. See [`render_as_slack`].
+pub fn render_to_buffer<'a, Custom: 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    buf: &mut String,
+) {
+    let tokens = tokens.as_ref();
+    let mut at = 0;
+    while at < tokens.len() {
+        match &tokens[at] {
+            Token::Text(t) => write_text(buf, t),
+            Token::Url { name, url, .. } => {
+                buf.push('<');
+                push_url(buf, url);
+                if name.is_some() {
+                    buf.push('|');
+                    let name = crate::render_as_plain(core::slice::from_ref(&tokens[at]));
+                    push_escaped(buf, &name);
+                }
+                buf.push('>');
+            }
+            Token::Header(_) => {
+                let (text, end) = line_text(tokens, at + 1);
+                buf.push('*');
+                push_escaped(buf, &text);
+                buf.push('*');
+                at = end;
+                continue;
+            }
+            Token::ListItem { place, marker } => match place {
+                Some(place) => write!(buf, "{}. ", list_item_label(*place, *marker)).unwrap(),
+                None => buf.push_str("• "),
+            },
+            Token::Math { content, display } => {
+                let fence = if *display { "```" } else { "`" };
+                buf.push_str(fence);
+                push_escaped(buf, content);
+                buf.push_str(fence);
+            }
+            Token::CodeFence { code, .. } => {
+                buf.push_str("```\n");
+                push_escaped(buf, code);
+                buf.push_str("```");
+            }
+            Token::FootnoteRef(id) => {
+                buf.push('[');
+                push_escaped(buf, id);
+                buf.push(']');
+            }
+            Token::FootnoteDef { id, body } => {
+                buf.push('[');
+                push_escaped(buf, id);
+                buf.push_str("] ");
+                render_to_buffer(body.parse_md(), buf);
+            }
+            Token::Details { summary, body } => {
+                write_text(
+                    buf,
+                    &Text {
+                        bold: true,
+                        ..*summary
+                    },
+                );
+                buf.push('\n');
+                render_to_buffer(body, buf);
+            }
+            Token::Alert { kind, body } => {
+                writeln!(buf, "*{}*", kind.title()).unwrap();
+                render_to_buffer(body, buf);
+            }
+            Token::DefinitionList(definitions) => {
+                for (index, Definition { term, descriptions }) in definitions.iter().enumerate() {
+                    if index > 0 {
+                        buf.push('\n');
+                    }
+                    let (term, _) = line_text(&term.parse_inline(), 0);
+                    buf.push('*');
+                    push_escaped(buf, &term);
+                    buf.push('*');
+                    for description in descriptions {
+                        buf.push_str("\n    ");
+                        render_to_buffer(description.parse_inline(), buf);
+                    }
+                }
+            }
+            Token::LineBreak => buf.push('\n'),
+            Token::ListContinuation
+            | Token::Html(_)
+            | Token::Comment(_)
+            | Token::FrontMatter { .. }
+            | Token::ParagraphStart
+            | Token::ParagraphEnd
+            | Token::Custom(_) => {}
+        }
+        if needs_space(tokens, at) {
+            buf.push(' ');
+        }
+        at += 1;
+    }
+}
+
+fn write_text(buf: &mut String, t: &Text) {
+    // Slack can't format code, so code is only wrapped in backticks.
+    if t.code {
+        buf.push('`');
+        push_escaped(buf, t.value);
+        buf.push('`');
+        return;
+    }
+
+    let bold = if t.bold { "*" } else { "" };
+    let italic = if t.italic { "_" } else { "" };
+    buf.push_str(bold);
+    buf.push_str(italic);
+    match t.parse_nested::<()>() {
+        Some(nested) => render_to_buffer(nested, buf),
+        None => push_escaped(buf, t.value.trim_end()),
+    }
+    buf.push_str(italic);
+    buf.push_str(bold);
+}
+
+/// Pushes `url` to `buf`, escaping it so that it can't end the `<url|name>` link early.
+fn push_url(buf: &mut String, url: &str) {
+    for part in url.split('|').enumerate() {
+        if part.0 > 0 {
+            buf.push_str("%7C");
+        }
+        push_escaped(buf, part.1);
+    }
+}
+
+/// Pushes `input` to `buf`, escaping the characters Slack uses for its markup.
+fn push_escaped(buf: &mut String, input: &str) {
+    for c in input.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            c => buf.push(c),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::parser::{Definition, Token};
+
+use super::*;
+
+/// Counts the words in parsed tokens.
+///
+/// Words are counted in text, link names, headers, list items, definition lists, details and
+/// footnote definitions. Code (inline code, code fences and math) is only counted if
+/// `include_code` is `true`. Raw HTML is never counted.
+///
+/// # Example
+/// ```
+/// # use linemd::{stats::word_count, Parser};
+/// let tokens = "# Some title\nSome *uninspiring* text.\n```\nlet code = 1;\n```".parse_md();
+/// assert_eq!(word_count(&tokens, false), 5);
+/// assert_eq!(word_count(&tokens, true), 9);
+/// ```
+pub fn word_count<'a, Custom: 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    include_code: bool,
+) -> usize {
+    tokens
+        .as_ref()
+        .iter()
+        .map(|token| token_word_count(token, include_code))
+        .sum()
+}
+
+/// Estimates the reading time of parsed tokens in minutes, at `wpm` words per minute.
+///
+/// The estimate is rounded up, so any non-empty document takes at least a minute to read.
+/// See [`word_count`] for what is counted.
+///
+/// # Example
+/// ```
+/// # use linemd::{stats::reading_time, Parser};
+/// assert_eq!(reading_time("Some uninspiring text.".parse_md(), 200, false), 1);
+/// ```
+pub fn reading_time<'a, Custom: 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    wpm: usize,
+    include_code: bool,
+) -> usize {
+    word_count(tokens, include_code).div_ceil(wpm.max(1))
+}
+
+fn token_word_count<Custom>(token: &Token<'_, Custom>, include_code: bool) -> usize {
+    let count = |s: &str| s.split_whitespace().count();
+    match token {
+        Token::Text(text) if include_code || !text.code => match text.parse_nested::<()>() {
+            Some(nested) => word_count(nested, include_code),
+            None => count(text.value),
+        },
+        Token::Url { name, url, .. } => name.as_ref().map_or_else(
+            || count(url),
+            |name| token_word_count(&Token::<()>::Text(name.clone()), include_code),
+        ),
+        Token::CodeFence { code, .. } if include_code => count(code),
+        Token::Math { content, .. } if include_code => count(content),
+        Token::FootnoteDef { body, .. } => word_count(body.parse_md(), include_code),
+        Token::Details { summary, body } => {
+            token_word_count(&Token::<()>::Text(summary.clone()), include_code)
+                + body
+                    .iter()
+                    .map(|token| token_word_count(token, include_code))
+                    .sum::<usize>()
+        }
+        Token::Alert { body, .. } => body
+            .iter()
+            .map(|token| token_word_count(token, include_code))
+            .sum(),
+        Token::DefinitionList(definitions) => definitions
+            .iter()
+            .map(|Definition { term, descriptions }| {
+                count(term) + descriptions.iter().map(|d| count(d)).sum::<usize>()
+            })
+            .sum(),
+        _ => 0,
+    }
+}
@@ -1,8 +1,11 @@
-use crate::parser::{Text, Token};
+use crate::parser::{list_item_label, Definition, Text, Token};
 
 use super::*;
 use core::fmt::{self, Display, Formatter, Write};
 
+/// Font size the line height is relative to, in pixels.
+const FONT_SIZE: u64 = 16;
+
 /// Value that specifies the dimensions of an SVG document.
 #[derive(Debug)]
 pub enum ViewportDimensions<'a> {
@@ -22,14 +25,69 @@ impl<'a> Default for ViewportDimensions<'a> {
     }
 }
 
+/// Horizontal alignment of text, see [`Config::header_align`].
+///
+/// Maps to the SVG `text-anchor` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TextAnchor {
+    /// Aligned to the left edge.
+    Start,
+    /// Centered.
+    Middle,
+    /// Aligned to the right edge.
+    End,
+}
+
 /// SVG rendering configuration for [`render_as_svg`].
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct Config<'a> {
     dimensions: ViewportDimensions<'a>,
     font_family: Option<&'a str>,
     font_size: Option<&'a str>,
     font_style: Option<&'a str>,
     font_weight: Option<&'a str>,
+    bullet: &'a str,
+    ordered_separator: &'a str,
+    line_height: u32,
+    paragraph_spacing: u32,
+    text_color: Option<&'a str>,
+    header_color: Option<&'a str>,
+    link_color: &'a str,
+    code_color: Option<&'a str>,
+    highlight_color: &'a str,
+    header_align: TextAnchor,
+    title: Option<&'a str>,
+    description: Option<&'a str>,
+    background: Option<&'a str>,
+    padding: u32,
+    minify: bool,
+}
+
+impl<'a> Default for Config<'a> {
+    fn default() -> Self {
+        Self {
+            dimensions: ViewportDimensions::default(),
+            font_family: None,
+            font_size: None,
+            font_style: None,
+            font_weight: None,
+            bullet: "\u{2022}",
+            ordered_separator: ". ",
+            line_height: 120,
+            paragraph_spacing: 100,
+            text_color: None,
+            header_color: None,
+            link_color: "blue",
+            code_color: None,
+            highlight_color: "darkorange",
+            header_align: TextAnchor::Start,
+            title: None,
+            description: None,
+            background: None,
+            padding: 0,
+            minify: false,
+        }
+    }
 }
 
 impl<'a> Config<'a> {
@@ -63,6 +121,144 @@ impl<'a> Config<'a> {
         self
     }
 
+    /// Set the bullet used for unordered list items. Defaults to `•`.
+    pub const fn bullet(mut self, value: &'a str) -> Self {
+        self.bullet = value;
+        self
+    }
+
+    /// Set the separator put after the number of ordered list items. Defaults to `. `.
+    pub const fn ordered_separator(mut self, value: &'a str) -> Self {
+        self.ordered_separator = value;
+        self
+    }
+
+    /// Set the height of a line, as a percentage of the 16px font size. Defaults to `120`.
+    pub const fn line_height(mut self, value: u32) -> Self {
+        self.line_height = value;
+        self
+    }
+
+    /// Set the height of a blank line between paragraphs, as a percentage of the line height.
+    /// Defaults to `100`.
+    pub const fn paragraph_spacing(mut self, value: u32) -> Self {
+        self.paragraph_spacing = value;
+        self
+    }
+
+    /// Set the fill color of text. By default, the color is left to the SVG viewer, which is
+    /// usually black.
+    pub const fn text_color(mut self, value: &'a str) -> Self {
+        self.text_color = Some(value);
+        self
+    }
+
+    /// Set the fill color of headers. Defaults to the text color.
+    pub const fn header_color(mut self, value: &'a str) -> Self {
+        self.header_color = Some(value);
+        self
+    }
+
+    /// Set the fill color of links. Defaults to `blue`.
+    pub const fn link_color(mut self, value: &'a str) -> Self {
+        self.link_color = value;
+        self
+    }
+
+    /// Set the fill color of code and code fences. Defaults to the text color.
+    pub const fn code_color(mut self, value: &'a str) -> Self {
+        self.code_color = Some(value);
+        self
+    }
+
+    /// Set the fill color of highlighted text. Defaults to `darkorange`.
+    pub const fn highlight_color(mut self, value: &'a str) -> Self {
+        self.highlight_color = value;
+        self
+    }
+
+    /// Set the alignment of headers. Defaults to [`TextAnchor::Start`].
+    ///
+    /// Centered headers are put at the middle of the width, and right aligned headers at the
+    /// end of it. If the width is not in pixels, it is done with percentages.
+    pub const fn header_align(mut self, value: TextAnchor) -> Self {
+        self.header_align = value;
+        self
+    }
+
+    /// Set the title of the resulting SVG document, written as a `<title>` element for
+    /// screen readers. Defaults to the text of the first header, if there is one.
+    pub const fn title(mut self, value: &'a str) -> Self {
+        self.title = Some(value);
+        self
+    }
+
+    /// Set the description of the resulting SVG document, written as a `<desc>` element for
+    /// screen readers.
+    pub const fn description(mut self, value: &'a str) -> Self {
+        self.description = Some(value);
+        self
+    }
+
+    /// Set the background color of the resulting SVG document, filling the whole viewport. By
+    /// default, the background is transparent.
+    pub const fn background(mut self, value: &'a str) -> Self {
+        self.background = Some(value);
+        self
+    }
+
+    /// Set the space around the content, in pixels. Defaults to `0`.
+    ///
+    /// The content is moved right and down by the padding, and if the height is left to
+    /// linemd, it is made taller by twice the padding.
+    pub const fn padding(mut self, value: u32) -> Self {
+        self.padding = value;
+        self
+    }
+
+    /// Set whether the output is minified.
+    ///
+    /// Minified output leaves out attributes that are set to their default values, so it is
+    /// smaller but looks the same. Disabled by default.
+    pub const fn minify(mut self, value: bool) -> Self {
+        self.minify = value;
+        self
+    }
+
+    /// Writes a `<tspan>`, leaving out its default attributes if minifying.
+    fn write_tspan<const N: usize>(&self, f: &mut dyn Write, span: &TSpan<'_, N>) {
+        if self.minify {
+            write!(f, "{:#}", span).unwrap();
+        } else {
+            write!(f, "{}", span).unwrap();
+        }
+    }
+
+    /// Converts a height in hundredths of a line to pixels.
+    const fn line_px(&self, hundredths: u32) -> u32 {
+        (hundredths as u64 * FONT_SIZE * self.line_height as u64 / 10_000) as u32
+    }
+
+    /// Writes the `x` and `text-anchor` attributes of a header's `<text>`, if it isn't
+    /// aligned to the start.
+    fn write_header_anchor_to(&self, f: &mut dyn Write) {
+        let (anchor, percent) = match self.header_align {
+            TextAnchor::Start => return,
+            TextAnchor::Middle => ("middle", 50),
+            TextAnchor::End => ("end", 100),
+        };
+        match self.dimensions {
+            ViewportDimensions::Integer(width, _) | ViewportDimensions::OnlyWidth(width) => {
+                let width = width.saturating_sub(self.padding * 2);
+                write!(f, r#" x="{}""#, width as u64 * percent / 100).unwrap()
+            }
+            ViewportDimensions::Raw(..) | ViewportDimensions::OnlyWidthRaw(_) => {
+                write!(f, r#" x="{}%""#, percent).unwrap()
+            }
+        }
+        write!(f, r#" text-anchor="{}""#, anchor).unwrap();
+    }
+
     fn write_start_tag_to(&self, f: &mut dyn Write, unspecified_height: u32) {
         write!(f, "<svg").unwrap();
         match self.dimensions {
@@ -91,10 +287,42 @@ impl<'a> Config<'a> {
         if let Some(value) = self.font_weight {
             write!(f, r#" font-weight="{}""#, value).unwrap();
         }
-        write!(f, r#" xmlns="http://www.w3.org/2000/svg" version="1.1">"#).unwrap();
+        f.write_str(r#" xmlns="http://www.w3.org/2000/svg""#)
+            .unwrap();
+        if !self.minify {
+            f.write_str(r#" version="1.1""#).unwrap();
+        }
+        f.write_char('>').unwrap();
+    }
+
+    /// Writes the `<title>` and `<desc>` elements, using `fallback_title` if no title is set.
+    fn write_title_to(&self, f: &mut dyn Write, fallback_title: Option<&str>) {
+        if let Some(title) = self.title.or(fallback_title) {
+            f.write_str("<title>").unwrap();
+            write_escaped(f, title).unwrap();
+            f.write_str("</title>").unwrap();
+        }
+        if let Some(description) = self.description {
+            f.write_str("<desc>").unwrap();
+            write_escaped(f, description).unwrap();
+            f.write_str("</desc>").unwrap();
+        }
+    }
+
+    /// Writes the background and the start of the padding group, if they are set.
+    fn write_background_to(&self, f: &mut dyn Write) {
+        if let Some(color) = self.background {
+            write!(f, r#"<rect width="100%" height="100%" fill="{}"/>"#, color).unwrap();
+        }
+        if self.padding > 0 {
+            write!(f, r#"<g transform="translate({0} {0})">"#, self.padding).unwrap();
+        }
     }
 
     fn write_end_tag_to(&self, f: &mut dyn Write) {
+        if self.padding > 0 {
+            f.write_str("</g>").unwrap();
+        }
         write!(f, "</svg>").unwrap();
     }
 }
@@ -112,6 +340,24 @@ pub fn render_as_svg<'a>(tokens: impl AsRef<[Token<'a, ()>]> + 'a, config: Confi
     doc
 }
 
+/// Renders tokens from an iterator as SVG.
+///
+/// The tokens are collected before rendering, since list items need to look ahead.
+/// Use [`Iterator::cloned`] to render borrowed tokens.
+///
+/// # Example
+/// ```
+/// # use linemd::{svg, SvgConfig, Parser};
+/// let tokens = "Some uninspiring text.".parse_md();
+/// let svg = svg::render_iter(tokens.iter().cloned(), SvgConfig::default());
+/// ```
+pub fn render_iter<'a>(
+    tokens: impl IntoIterator<Item = Token<'a, ()>>,
+    config: Config<'_>,
+) -> String {
+    render_as_svg(tokens.into_iter().collect::<Vec<_>>(), config)
+}
+
 /// Renders parsed tokens as SVG, to a buffer.
 ///
 /// # Example
@@ -125,8 +371,47 @@ pub fn render_to_buffer<'a>(
     config: Config<'_>,
     doc: &mut String,
 ) {
+    render_to_buffer_custom(tokens, config, |_, _| {}, doc)
+}
+
+/// Renders parsed tokens with custom tokens as SVG.
+///
+/// Custom tokens are rendered by `render_custom`, which gets passed the custom token and
+/// the buffer to write to. They are written inside the `<text>` element of their line, so
+/// they should be rendered as `<tspan>` elements.
+///
+/// # Example
+/// ```
+/// # use linemd::{svg, SvgConfig, parser::Token};
+/// # use core::fmt::Write;
+/// let tokens = [Token::Custom("star")];
+/// let svg = svg::render_as_svg_custom(&tokens, SvgConfig::default(), |name, buf| {
+///     write!(buf, "<tspan>{}</tspan>", name).unwrap()
+/// });
+/// assert!(svg.contains("<tspan>star</tspan>"));
+/// ```
+pub fn render_as_svg_custom<'a, Custom: Clone + 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    config: Config<'_>,
+    render_custom: impl Fn(&Custom, &mut dyn Write),
+) -> String {
+    let mut doc = String::new();
+    render_to_buffer_custom(tokens, config, render_custom, &mut doc);
+    doc
+}
+
+/// Renders parsed tokens with custom tokens as SVG, to a buffer. See [`render_as_svg_custom`].
+pub fn render_to_buffer_custom<'a, Custom: Clone + 'a>(
+    tokens: impl AsRef<[Token<'a, Custom>]> + 'a,
+    config: Config<'_>,
+    render_custom: impl Fn(&Custom, &mut dyn Write),
+    doc: &mut String,
+) {
+    let render_custom: &dyn Fn(&Custom, &mut dyn Write) = &render_custom;
     let mut text = String::new();
-    let mut text_before: u32 = 1;
+    // Height of the content before the current text, in hundredths of a line.
+    let mut text_before: u32 = 100;
+    let line_dy = Position::Relative(config.line_px(100) as usize);
     let mut tspan_before: u32 = 0;
 
     let mut at = 0;
@@ -137,9 +422,16 @@ pub fn render_to_buffer<'a>(
         let token = &tokens[at];
         match token {
             Token::LineBreak => {
-                try_apply_text(doc, &mut text, &mut text_before, &mut tspan_before);
+                try_apply_text(
+                    doc,
+                    &mut text,
+                    &mut text_before,
+                    &mut tspan_before,
+                    was_header.is_some(),
+                    &config,
+                );
                 if let Some(depth) = was_header {
-                    text_before += 7_u32.saturating_sub(depth as u32) / 4;
+                    text_before += 7_u32.saturating_sub(depth as u32) / 4 * 100;
                 }
             }
             Token::CodeFence { code, attrs: _ } => {
@@ -147,55 +439,108 @@ pub fn render_to_buffer<'a>(
                     let span = TSpan::<0>::new()
                         .content(line)
                         .font_family("monospace")
+                        .color(config.text_color)
+                        .color(config.code_color)
                         .x(Position::Absolute(0))
-                        .y(Position::Relative(19));
-                    write!(text, "{}", span).unwrap();
+                        .y(line_dy);
+                    config.write_tspan(&mut text, &span);
+                }
+            }
+            // Sections are collapsed, so only their summary is shown.
+            Token::Details { summary, .. } => {
+                let prefix = [Value::Str("\u{25b8} ")];
+                try_apply_text_token(
+                    &mut text,
+                    &Token::Text(summary.clone()),
+                    TSpan::<1>::new().prefix(prefix),
+                    &mut tspan_before,
+                    &config,
+                    render_custom,
+                );
+            }
+            // Like the bodies of sections, the bodies of alerts aren't shown.
+            Token::Alert { kind, .. } => {
+                try_apply_text_token(
+                    &mut text,
+                    &Token::Text(Text {
+                        bold: true,
+                        ..Text::naked(kind.title())
+                    }),
+                    TSpan::<0>::new(),
+                    &mut tspan_before,
+                    &config,
+                    render_custom,
+                );
+            }
+            Token::DefinitionList(definitions) => {
+                for Definition { term, descriptions } in definitions {
+                    let span = TSpan::<0>::new()
+                        .content(term)
+                        .font_weight("bold")
+                        .color(config.text_color)
+                        .x(Position::Absolute(0))
+                        .y(line_dy);
+                    config.write_tspan(&mut text, &span);
+                    for description in descriptions {
+                        let span = TSpan::<0>::new()
+                            .content(description)
+                            .color(config.text_color)
+                            .x(Position::Absolute(20))
+                            .y(line_dy);
+                        config.write_tspan(&mut text, &span);
+                    }
                 }
             }
             Token::Header(depth) => {
-                let size = match *depth {
+                // Headers deeper than 6 are rendered like a depth 6 header, like in HTML.
+                let depth = (*depth).clamp(1, 6);
+                let size = match depth {
                     1 => "xx-large",
                     2 => "x-large",
                     3 => "large",
                     4 => "small",
                     5 => "x-small",
-                    6 => "xx-small",
-                    x if x > 6 => "xx-small",
-                    x if x < 1 => "xx-large",
-                    _ => unreachable!(),
+                    _ => "xx-small",
                 };
-                text_before += 7_u32.saturating_sub(*depth as u32) / 4;
+                text_before += 7_u32.saturating_sub(depth as u32) / 4 * 100;
                 at += 1;
                 at = write_until_line_break(
                     &mut text,
-                    TSpan::<0>::new().font_size(size),
+                    TSpan::<0>::new().font_size(size).color(config.header_color),
                     &mut tspan_before,
                     at,
                     tokens,
+                    &config,
+                    render_custom,
                 );
-                was_header = Some(*depth);
+                was_header = Some(depth);
                 continue;
             }
-            Token::ListItem(place) => {
+            Token::ListItem { place, marker } => {
                 at += 1;
                 if at >= tokens.len() {
                     continue;
                 }
                 if let Some(place) = place {
-                    let prefix = [Value::Number(*place), Value::Str(". ")];
+                    let label = list_item_label(*place, *marker);
+                    let prefix = [Value::Str(&label), Value::Str(config.ordered_separator)];
                     try_apply_text_token(
                         &mut text,
                         &tokens[at],
                         TSpan::<2>::new().prefix(prefix),
                         &mut tspan_before,
+                        &config,
+                        render_custom,
                     );
                 } else {
-                    let prefix = [Value::Str("• ")];
+                    let prefix = [Value::Str(config.bullet), Value::Str(" ")];
                     try_apply_text_token(
                         &mut text,
                         &tokens[at],
-                        TSpan::<1>::new().prefix(prefix),
+                        TSpan::<2>::new().prefix(prefix),
                         &mut tspan_before,
+                        &config,
+                        render_custom,
                     );
                 }
                 at = write_until_line_break(
@@ -204,45 +549,99 @@ pub fn render_to_buffer<'a>(
                     &mut tspan_before,
                     at,
                     tokens,
+                    &config,
+                    render_custom,
                 );
                 continue;
             }
-            token => try_apply_text_token(&mut text, token, TSpan::<0>::new(), &mut tspan_before),
+            token => try_apply_text_token(
+                &mut text,
+                token,
+                TSpan::<0>::new(),
+                &mut tspan_before,
+                &config,
+                render_custom,
+            ),
         }
         at += 1;
         was_header = None;
     }
 
-    try_apply_text(doc, &mut text, &mut text_before, &mut tspan_before);
+    try_apply_text(
+        doc,
+        &mut text,
+        &mut text_before,
+        &mut tspan_before,
+        was_header.is_some(),
+        &config,
+    );
 
-    let content_height = calculate_content_height(text_before + 1);
+    let content_height = config.line_px(text_before + 100) + config.padding * 2;
     let mut tmp = String::new();
     config.write_start_tag_to(&mut tmp, content_height);
+    let fallback_title = match config.title {
+        Some(_) => None,
+        None => crate::toc::title(tokens),
+    };
+    config.write_title_to(&mut tmp, fallback_title.as_deref());
+    config.write_background_to(&mut tmp);
     doc.insert_str(0, &tmp);
     config.write_end_tag_to(doc);
 }
 
-fn write_until_line_break<'a, const N: usize>(
+fn write_until_line_break<'a, Custom: Clone, const N: usize>(
     text: &mut String,
     span: TSpan<'a, N>,
     tspan_before: &mut u32,
     mut at: usize,
-    tokens: &[Token<()>],
+    tokens: &[Token<Custom>],
+    config: &Config,
+    render_custom: &dyn Fn(&Custom, &mut dyn Write),
 ) -> usize {
     while at < tokens.len() {
         let token = &tokens[at];
-        if matches!(token, Token::LineBreak) {
+        if token.is_line_break() {
             break;
         }
-        try_apply_text_token(text, token, span.clone(), tspan_before);
+        try_apply_text_token(
+            text,
+            token,
+            span.clone(),
+            tspan_before,
+            config,
+            render_custom,
+        );
         at += 1;
     }
     at
 }
 
+/// Escapes the characters that are special in SVG text content.
+fn write_escaped(f: &mut dyn Write, input: &str) -> fmt::Result {
+    for c in input.chars() {
+        match c {
+            '&' => f.write_str("&amp;"),
+            '<' => f.write_str("&lt;"),
+            '>' => f.write_str("&gt;"),
+            c => f.write_char(c),
+        }?
+    }
+    Ok(())
+}
+
+/// Escapes the characters that are special in a double quoted SVG attribute value.
+fn write_escaped_attr(f: &mut dyn Write, input: &str) -> fmt::Result {
+    for (i, part) in input.split('"').enumerate() {
+        if i > 0 {
+            f.write_str("&quot;")?;
+        }
+        write_escaped(f, part)?;
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 enum Value<'a> {
-    Number(usize),
     Str(&'a str),
 }
 
@@ -266,6 +665,7 @@ struct TSpan<'a, const N: usize> {
     font_size: Option<&'a str>,
     font_weight: Option<&'a str>,
     font_style: Option<&'a str>,
+    baseline_shift: Option<&'a str>,
     color: Option<&'a str>,
     x: Position,
     y: Position,
@@ -280,6 +680,7 @@ impl<'a> Default for TSpan<'a, 0> {
             font_size: None,
             font_weight: None,
             font_style: None,
+            baseline_shift: None,
             color: None,
             x: Position::default(),
             y: Position::default(),
@@ -300,6 +701,7 @@ impl<'a, const N: usize> TSpan<'a, N> {
             font_size: self.font_size,
             font_weight: self.font_weight,
             font_style: self.font_style,
+            baseline_shift: self.baseline_shift,
             color: self.color,
             x: self.x,
             y: self.y,
@@ -331,8 +733,16 @@ impl<'a, const N: usize> TSpan<'a, N> {
         self
     }
 
-    const fn color(mut self, value: &'a str) -> Self {
-        self.color = Some(value);
+    const fn baseline_shift(mut self, value: &'a str) -> Self {
+        self.baseline_shift = Some(value);
+        self
+    }
+
+    /// Sets the color, if there is one.
+    const fn color(mut self, value: Option<&'a str>) -> Self {
+        if value.is_some() {
+            self.color = value;
+        }
         self
     }
 
@@ -347,14 +757,17 @@ impl<'a, const N: usize> TSpan<'a, N> {
     }
 }
 
+/// The alternate format (`{:#}`) leaves out positions that don't move the span.
 impl<'a, const N: usize> Display for TSpan<'a, N> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "<tspan")?;
         match self.x {
+            Position::Relative(0) if f.alternate() => {}
             Position::Absolute(val) => write!(f, r#" x="{}""#, val)?,
             Position::Relative(val) => write!(f, r#" dx="{}""#, val)?,
         }
         match self.y {
+            Position::Relative(0) if f.alternate() => {}
             Position::Absolute(val) => write!(f, r#" y="{}""#, val)?,
             Position::Relative(val) => write!(f, r#" dy="{}""#, val)?,
         }
@@ -370,55 +783,73 @@ impl<'a, const N: usize> Display for TSpan<'a, N> {
         if let Some(value) = self.font_weight {
             write!(f, r#" font-weight="{}""#, value)?;
         }
+        if let Some(value) = self.baseline_shift {
+            write!(f, r#" baseline-shift="{}""#, value)?;
+        }
         if let Some(color) = self.color {
             write!(f, r#" fill="{}""#, color)?;
         }
         f.write_char('>')?;
         for v in &self.prefix {
             match v {
-                Value::Number(n) => write!(f, "{}", n)?,
-                Value::Str(s) => f.write_str(s)?,
+                Value::Str(s) => write_escaped(f, s)?,
             }
         }
-        write!(f, "{}</tspan>", self.content)
+        write_escaped(f, self.content)?;
+        f.write_str("</tspan>")
     }
 }
 
-const fn calculate_content_height(text_before: u32) -> u32 {
-    (text_before * 12 * 16) / 10
-}
-
 fn try_apply_text(
     doc: &mut String,
     text: &mut String,
     text_before: &mut u32,
     tspan_before: &mut u32,
+    is_header: bool,
+    config: &Config,
 ) {
     if text.is_empty() {
-        *text_before += 1;
+        *text_before += config.paragraph_spacing;
     } else {
-        let y = calculate_content_height(*text_before);
-        write!(doc, r#"<text x="0" y="{}">{}</text>"#, y, text).unwrap();
+        let y = config.line_px(*text_before);
+        doc.push_str("<text");
+        if is_header && config.header_align != TextAnchor::Start {
+            config.write_header_anchor_to(doc);
+        } else if !config.minify {
+            doc.push_str(r#" x="0""#);
+        }
+        write!(doc, r#" y="{}">{}</text>"#, y, text).unwrap();
         text.clear();
-        *text_before += 1;
+        *text_before += 100;
         *tspan_before = 0;
     }
 }
 
-fn try_apply_text_token<'a, const N: usize>(
+fn try_apply_text_token<'a, Custom: Clone, const N: usize>(
     text: &mut String,
-    token: &Token<()>,
+    token: &Token<Custom>,
     mut span: TSpan<'a, N>,
     tspan_before: &mut u32,
+    config: &Config<'a>,
+    render_custom: &dyn Fn(&Custom, &mut dyn Write),
 ) {
     span = span.x(Position::Relative(if *tspan_before > 0 { 5 } else { 0 }));
+    if span.color.is_none() {
+        span = span.color(config.text_color);
+    }
     match token {
-        Token::Text(Text {
-            value,
-            bold,
-            italic,
-            code,
-        }) => {
+        Token::Text(
+            t @ Text {
+                value,
+                bold,
+                italic,
+                code,
+                subscript,
+                superscript,
+                highlight,
+                ..
+            },
+        ) => {
             if *bold {
                 span = span.font_weight("bold");
             }
@@ -426,24 +857,86 @@ fn try_apply_text_token<'a, const N: usize>(
                 span = span.font_style("italic");
             }
             if *code {
-                span = span.font_family("monospace");
+                span = span.font_family("monospace").color(config.code_color);
+            }
+            if *highlight {
+                span = span.color(Some(config.highlight_color));
+            }
+            if *subscript {
+                span = span.baseline_shift("sub").font_size("smaller");
+            } else if *superscript {
+                span = span.baseline_shift("super").font_size("smaller");
+            }
+            match t.parse_nested::<Custom>() {
+                // The nested tokens are rendered with the formatting of this text, and only the
+                // first one gets the prefix.
+                Some(nested) => {
+                    let mut nested = nested.iter();
+                    if let Some(first) = nested.next() {
+                        try_apply_text_token(
+                            text,
+                            first,
+                            span.clone(),
+                            tspan_before,
+                            config,
+                            render_custom,
+                        );
+                    }
+                    for token in nested {
+                        try_apply_text_token(
+                            text,
+                            token,
+                            span.clone().prefix([]),
+                            tspan_before,
+                            config,
+                            render_custom,
+                        );
+                    }
+                }
+                // Spans are already spaced apart, so the whitespace between tokens is left out.
+                None if value.trim().is_empty() && N == 0 => {}
+                None => {
+                    config.write_tspan(text, &span.content(value.trim()));
+                    *tspan_before += 1;
+                }
             }
-            write!(text, "{}", span.content(value.trim())).unwrap();
-            *tspan_before += 1;
         }
         Token::Url {
-            name,
-            is_image: _,
-            url,
+            name, url, title, ..
         } => {
-            write!(text, r#"<a xlink:href="{}" target="_blank">"#, url).unwrap();
+            text.push_str(r#"<a xlink:href=""#);
+            write_escaped_attr(text, url).unwrap();
+            text.push_str(r#"" target="_blank">"#);
+            // The title (or the name) is shown as a tooltip by viewers.
+            let tooltip = title.or_else(|| name.as_ref().map(|name| name.value.trim()));
+            if let Some(tooltip) = tooltip {
+                text.push_str("<title>");
+                write_escaped(text, tooltip).unwrap();
+                text.push_str("</title>");
+            }
             let name = name.as_ref().map_or_else(
-                || Token::Text(Text::naked(url)),
+                || Token::<Custom>::Text(Text::naked(url.as_ref())),
                 |token| Token::Text(token.clone()),
             );
-            try_apply_text_token(text, &name, span.color("blue"), tspan_before);
+            try_apply_text_token(
+                text,
+                &name,
+                span.color(Some(config.link_color)),
+                tspan_before,
+                config,
+                render_custom,
+            );
             text.push_str("</a>");
         }
+        Token::Math { content, .. } => {
+            let span = span.font_family("monospace").content(content.trim());
+            config.write_tspan(text, &span);
+            *tspan_before += 1;
+        }
+        Token::Custom(custom) => {
+            render_custom(custom, text);
+            *tspan_before += 1;
+        }
         _ => {}
     }
 }
@@ -1,7 +1,7 @@
-use crate::parser::{Text, Token};
+use crate::parser::{Definition, Text, Token};
 
 use super::*;
-use alloc::{format, vec};
+use alloc::{borrow::Cow, format, vec};
 
 #[test]
 fn just_text() {
@@ -11,280 +11,3427 @@ fn just_text() {
     )
 }
 
+#[test]
+fn non_ascii() {
+    assert_eq!(
+        "h\u{e9}llo *w\u{f6}rld*".parse_md(),
+        vec![
+            Text::naked("h\u{e9}llo ").into_token(),
+            Token::Text(Text {
+                value: "w\u{f6}rld",
+                italic: true,
+                ..Default::default()
+            }),
+        ]
+    );
+}
+
+#[test]
+fn reuse_buffer() {
+    let mut buf = Vec::new();
+    "# first\n- item".parse_md_with_buf(&mut buf);
+    assert_eq!(buf.len(), 5);
+
+    let capacity = buf.capacity();
+    "second".parse_md_with_buf(&mut buf);
+    assert_eq!(buf, vec![Text::naked("second").into_token()]);
+    assert_eq!(buf.capacity(), capacity);
+}
+
+#[test]
+fn reuse_buffer_custom() {
+    use parser::{AtToken, CustomFn};
+
+    fn hash<'a>(md: &'a &str, at: usize) -> Option<AtToken<'a, ()>> {
+        md.get_range_str(at..)
+            .starts_with("#!")
+            .then(|| (Token::Custom(()), at + 2))
+    }
+    let customs = [hash as CustomFn<_, _>];
+
+    let mut buf = Vec::new();
+    "#! first\n#!".parse_md_with_buf_custom(&mut buf, &customs);
+    assert_eq!(
+        buf,
+        vec![
+            Token::Custom(()),
+            Text::naked(" first").into_token(),
+            Token::LineBreak,
+            Token::Custom(()),
+        ]
+    );
+
+    let capacity = buf.capacity();
+    "# second".parse_md_with_buf_custom(&mut buf, &customs);
+    assert_eq!(
+        buf,
+        vec![Token::Header(1), Text::naked("second").into_token()]
+    );
+    assert_eq!(buf.capacity(), capacity);
+}
+
+#[test]
+fn parse_inline() {
+    assert_eq!(
+        "# not a header".parse_inline(),
+        vec![Text::naked("# not a header").into_token()]
+    );
+    assert_eq!(
+        "- *a*\n```b```".parse_inline(),
+        vec![
+            Text::naked("- ").into_token(),
+            Token::Text(Text {
+                value: "a",
+                italic: true,
+                ..Default::default()
+            }),
+            Text::naked("```").into_token(),
+            Text::naked("b").into_token(),
+            Text::naked("```").into_token(),
+        ]
+    );
+}
+
 #[test]
 fn naked_url() {
     assert_eq!(
         "<asdasd>".parse_md(),
         vec![Token::Url {
             is_image: false,
+            width: None,
+            height: None,
+            title: None,
             name: None,
-            url: "asdasd",
+            url: "asdasd".into(),
         }],
     )
 }
 
+#[test]
+fn link_title() {
+    assert_eq!(
+        "[x](u \"t\")".parse_md(),
+        vec![Token::Url {
+            name: Some(Text::naked("x")),
+            url: "u".into(),
+            title: Some("t"),
+            is_image: false,
+            width: None,
+            height: None,
+        }],
+    );
+    assert_eq!(
+        "![x](u 't')".parse_md(),
+        vec![Token::Url {
+            name: Some(Text::naked("x")),
+            url: "u".into(),
+            title: Some("t"),
+            is_image: true,
+            width: None,
+            height: None,
+        }],
+    );
+    assert_eq!(
+        "[x](u)".parse_md(),
+        vec![Token::Url {
+            name: Some(Text::naked("x")),
+            url: "u".into(),
+            title: None,
+            is_image: false,
+            width: None,
+            height: None,
+        }],
+    );
+}
+
+#[test]
+fn html_link_title() {
+    assert_eq!(
+        &render_as_html("[x](u 'a \"b')".parse_md()),
+        "<p><a href=\"u\" title=\"a &quot;b\">x</a></p>"
+    );
+    assert_eq!(
+        &render_as_html("![x](u 't')".parse_md()),
+        "<p><img src=\"u\" alt=\"x\" title=\"t\"></p>"
+    );
+}
+
+#[test]
+fn link_name_markup() {
+    assert_eq!(
+        "[see `code`](u)".parse_md(),
+        vec![Token::link("see `code`", "u")]
+    );
+    assert_eq!(
+        "[![a](i)](u) [a [b](c)".parse_md(),
+        vec![
+            Token::link("![a](i)", "u"),
+            Text::naked(" [a ").into_token(),
+            Token::link("b", "c"),
+        ]
+    );
+
+    assert_eq!(
+        &render_as_html("[see `code`](u)".parse_md()),
+        "<p><a href=\"u\">see <code>code</code></a></p>"
+    );
+    assert_eq!(
+        &render_as_html("[![a](i)](u) [*b* <https://c.org>](d)".parse_md()),
+        "<p><a href=\"u\"><img src=\"i\" alt=\"a\"></a> <a href=\"d\"><i>b</i> https://c.org</a></p>"
+    );
+    assert_eq!(render_as_plain("[see `code`](u)".parse_md()), "see code");
+}
+
+#[test]
+fn image_size() {
+    let size = |md: &str| match &md.parse_md()[..] {
+        [Token::Url { width, height, .. }] => (*width, *height),
+        tokens => panic!("not an image: {:?}", tokens),
+    };
+    assert_eq!(size("![x](a.png =200x100)"), (Some(200), Some(100)));
+    assert_eq!(size("![x](a.png =200x)"), (Some(200), None));
+    assert_eq!(size("![x](a.png =x100)"), (None, Some(100)));
+    assert_eq!(size("![x](a.png =200x100 't')"), (Some(200), Some(100)));
+    assert_eq!(size("![x](a.png)"), (None, None));
+    // Sizes are only for images, and need a width or a height.
+    for md in ["[x](a.html =200x100)", "![x](a.png =x)"] {
+        assert_eq!(md.parse_md(), vec![Text::naked(md).into_token()]);
+    }
+
+    assert_eq!(
+        &render_as_html("![x](a.png =200x100 't')".parse_md()),
+        "<p><img src=\"a.png\" alt=\"x\" width=\"200\" height=\"100\" title=\"t\"></p>"
+    );
+    assert_eq!(
+        &render_as_html("![x](a.png =200x)".parse_md()),
+        "<p><img src=\"a.png\" alt=\"x\" width=\"200\"></p>"
+    );
+    assert_eq!(
+        &render_as_html("![x](a.png)".parse_md()),
+        "<p><img src=\"a.png\" alt=\"x\"></p>"
+    );
+}
+
+#[test]
+fn email_autolink() {
+    assert_eq!(
+        "<a@b.com>".parse_md(),
+        vec![Token::Url {
+            name: Some(Text::naked("a@b.com")),
+            url: "mailto:a@b.com".into(),
+            title: None,
+            is_image: false,
+            width: None,
+            height: None,
+        }]
+    );
+    assert_eq!(
+        &render_as_html("<a@b.com>".parse_md()),
+        "<p><a href=\"mailto:a@b.com\">a@b.com</a></p>"
+    );
+    assert_eq!(
+        &render_as_html("<https://x>".parse_md()),
+        "<p><a href=\"https://x\">https://x</a></p>"
+    );
+    assert_eq!(
+        &render_as_html("<https://a@b.com>".parse_md()),
+        "<p><a href=\"https://a@b.com\">https://a@b.com</a></p>"
+    );
+    assert_eq!(
+        &render_as_html("[mail](a@b.com)".parse_md()),
+        "<p><a href=\"a@b.com\">mail</a></p>"
+    );
+}
+
+#[test]
+fn html_render_iter() {
+    let tokens = "# Title\nSome *text*\n\nMore text".parse_md();
+    assert_eq!(
+        html::render_iter(
+            tokens
+                .iter()
+                .filter(|token| !matches!(token, Token::Header(_)))
+                .cloned(),
+            html::Config::default(),
+        ),
+        "<p>Title\nSome <i>text</i></p>\n\n<p>More text</p>"
+    );
+}
+
+#[test]
+fn html_paragraph_tag() {
+    let md = "# Title\nfirst *line*\nsecond line\n\nthird line".parse_md();
+    let render =
+        |tag| html::render_as_html_with_config(&md, html::Config::default().paragraph_tag(tag));
+    assert_eq!(
+        render(Some("p")),
+        "<h1>Title</h1>\n<p>first <i>line</i>\nsecond line</p>\n\n<p>third line</p>"
+    );
+    assert_eq!(render(Some("p")), render_as_html(&md));
+    assert_eq!(
+        render(Some(r#"div class="para""#)),
+        "<h1>Title</h1>\n<div class=\"para\">first <i>line</i>\nsecond line</div>\n\n<div class=\"para\">third line</div>"
+    );
+    assert_eq!(
+        render(None),
+        "<h1>Title</h1>\nfirst <i>line</i>\nsecond line\n\nthird line"
+    );
+}
+
+#[test]
+fn html_code_block_wrapper() {
+    let render = |md: &str, wrapper| {
+        html::render_as_html_with_config(
+            md.parse_md(),
+            html::Config::default().code_block_wrapper(wrapper),
+        )
+    };
+    let md = "```rust,ignore\nlet x = 1;\n```";
+    assert_eq!(
+        render(md, true),
+        "<div class=\"code-block\" data-lang=\"rust\"><pre><code class=\"language-rust\">let x = 1;\n</code></pre></div>"
+    );
+    assert_eq!(
+        render(md, false),
+        "<pre><code class=\"language-rust\">let x = 1;\n</code></pre>"
+    );
+    assert_eq!(
+        render("```\ntext\n```", true),
+        "<div class=\"code-block\"><pre><code>text\n</code></pre></div>"
+    );
+}
+
+#[test]
+fn html_soft_break() {
+    use html::SoftBreak;
+
+    let tokens = "first *line*\nsecond line\n\nthird line".parse_md();
+    let render = |soft_break| {
+        html::render_as_html_with_config(&tokens, html::Config::default().soft_break(soft_break))
+    };
+    assert_eq!(
+        render(SoftBreak::Newline),
+        "<p>first <i>line</i>\nsecond line</p>\n\n<p>third line</p>"
+    );
+    assert_eq!(
+        render(SoftBreak::Space),
+        "<p>first <i>line</i> second line</p>\n\n<p>third line</p>"
+    );
+    assert_eq!(
+        render(SoftBreak::Br),
+        "<p>first <i>line</i><br>\nsecond line</p>\n\n<p>third line</p>"
+    );
+
+    let render = |md: &str, soft_break| {
+        html::render_as_html_with_config(
+            md.parse_md(),
+            html::Config::default().soft_break(soft_break),
+        )
+    };
+    assert_eq!(render("a\nb", SoftBreak::Br), "<p>a<br>\nb</p>");
+    assert_eq!(render("a\nb", SoftBreak::Space), "<p>a b</p>");
+    assert_eq!(render("a\nb", SoftBreak::Newline), "<p>a\nb</p>");
+    // Line breaks between blocks are never hard breaks.
+    assert_eq!(
+        render("# a\nb\n```\nc\n```", SoftBreak::Br),
+        "<h1>a</h1>\n<p>b</p>\n<pre><code>c\n</code></pre>"
+    );
+}
+
+#[test]
+fn www_autolink() {
+    assert_eq!(
+        "visit www.x.com now".parse_md(),
+        vec![
+            Text::naked("visit ").into_token(),
+            Token::Url {
+                name: Some(Text::naked("www.x.com")),
+                url: "http://www.x.com".into(),
+                title: None,
+                is_image: false,
+                width: None,
+                height: None,
+            },
+            Text::naked(" now").into_token(),
+        ]
+    );
+    assert_eq!(
+        "<www.x.com>".parse_md(),
+        [Token::Url {
+            name: Some(Text::naked("www.x.com")),
+            url: "http://www.x.com".into(),
+            title: None,
+            is_image: false,
+            width: None,
+            height: None,
+        }]
+    );
+    assert_eq!(
+        &render_as_html("visit www.x.com now".parse_md()),
+        "<p>visit <a href=\"http://www.x.com\">www.x.com</a> now</p>"
+    );
+    assert_eq!(
+        &render_as_html("(see www.x.com/a_(b)). Not awww.x.com or www.".parse_md()),
+        "<p>(see <a href=\"http://www.x.com/a_(b)\">www.x.com/a_(b)</a>). Not awww.x.com or www.</p>"
+    );
+    assert_eq!(
+        &render_as_html("[named](www.x.com)".parse_md()),
+        "<p><a href=\"www.x.com\">named</a></p>"
+    );
+}
+
+#[test]
+fn comments() {
+    let md = "a <!-- TODO: *more* --> b\n\n<!-- unterminated\n\n# a header";
+    assert_eq!(
+        md.parse_md(),
+        vec![
+            Text::naked("a ").into_token(),
+            Token::Comment(" TODO: *more* "),
+            Text::naked(" b").into_token(),
+            Token::LineBreak,
+            Token::LineBreak,
+            Text::naked("<!-- unterminated").into_token(),
+            Token::LineBreak,
+            Token::LineBreak,
+            Token::Header(1),
+            Text::naked("a header").into_token(),
+        ]
+    );
+    assert_eq!(
+        &render_as_html(md.parse_md()),
+        "<p>a b</p>\n\n<p>&lt;!-- unterminated</p>\n\n<h1>a header</h1>"
+    );
+    assert_eq!(
+        &html::render_as_html_with_config(
+            md.parse_md(),
+            html::Config::default().keep_comments(true)
+        ),
+        "<p>a <!-- TODO: *more* --> b</p>\n\n<p>&lt;!-- unterminated</p>\n\n<h1>a header</h1>"
+    );
+    assert_eq!(
+        &render_as_plain(md.parse_md()),
+        "a b\n\n<!-- unterminated\n\na header"
+    );
+}
+
+#[test]
+fn html_url_sanitization() {
+    assert_eq!(
+        &render_as_html("[x](javascript:alert(1))".parse_md()),
+        "<p><a href=\"#\">x</a>)</p>"
+    );
+    assert_eq!(
+        &render_as_html("<JavaScript:alert(1)>".parse_md()),
+        "<p><a href=\"#\">JavaScript:alert(1)</a></p>"
+    );
+    assert_eq!(
+        &render_as_html("[x](https://example.org/?a=\"b\"&c)".parse_md()),
+        "<p><a href=\"https://example.org/?a=&quot;b&quot;&amp;c\">x</a></p>"
+    );
+    assert_eq!(
+        &render_as_html("![x](data:image/png;base64,AA) [y](data:text/html,a)".parse_md()),
+        "<p><img src=\"data:image/png;base64,AA\" alt=\"x\"> <a href=\"#\">y</a></p>"
+    );
+    assert_eq!(
+        &render_as_html("[x](/relative:path)".parse_md()),
+        "<p><a href=\"/relative:path\">x</a></p>"
+    );
+
+    let config = HtmlConfig::default().allowed_schemes(&["ftp"]);
+    assert_eq!(
+        &render_as_html_with_config("<ftp://example.org>".parse_md(), config),
+        "<p><a href=\"ftp://example.org\">ftp://example.org</a></p>"
+    );
+}
+
+#[test]
+fn html_default_scheme() {
+    let config = HtmlConfig::default().default_scheme(Some("https"));
+    let render = |md: &str| render_as_html_with_config(md.parse_md(), config);
+    assert_eq!(
+        render("[x](example.com)"),
+        "<p><a href=\"https://example.com\">x</a></p>"
+    );
+    assert_eq!(render("[x](#title)"), "<p><a href=\"#title\">x</a></p>");
+    assert_eq!(
+        render("[x](./a.html) [y](/a) [z](?q)"),
+        "<p><a href=\"./a.html\">x</a> <a href=\"/a\">y</a> <a href=\"?q\">z</a></p>"
+    );
+    assert_eq!(
+        render("[x](http://example.com) <www.example.com> <a@example.com>"),
+        "<p><a href=\"http://example.com\">x</a> <a href=\"https://www.example.com\">www.example.com</a> <a href=\"mailto:a@example.com\">a@example.com</a></p>"
+    );
+    assert_eq!(
+        render("see www.example.com"),
+        "<p>see <a href=\"https://www.example.com\">www.example.com</a></p>"
+    );
+
+    // The scheme can be built at runtime.
+    let scheme = String::from("https");
+    let config = HtmlConfig::default().default_scheme(Some(&scheme));
+    assert_eq!(
+        render_as_html_with_config("[x](example.com)".parse_md(), config),
+        "<p><a href=\"https://example.com\">x</a></p>"
+    );
+    assert_eq!(
+        render("![x](a.png)"),
+        "<p><img src=\"a.png\" alt=\"x\"></p>"
+    );
+    assert_eq!(
+        &render_as_html("[x](example.com)".parse_md()),
+        "<p><a href=\"example.com\">x</a></p>"
+    );
+}
+
+#[test]
+fn html_base_url() {
+    let href = |base, url: &str| {
+        let config = HtmlConfig::default().base_url(Some(base));
+        let html = render_as_html_with_config(format!("[x]({})", url).parse_md(), config);
+        let start = html.find("href=\"").unwrap() + 6;
+        String::from(&html[start..start + html[start..].find('"').unwrap()])
+    };
+    let base = "https://example.org/blog/post?page=2#top";
+    assert_eq!(href(base, "a.html"), "https://example.org/blog/a.html");
+    assert_eq!(href(base, "./a/b"), "https://example.org/blog/a/b");
+    assert_eq!(href(base, "../../a"), "https://example.org/a");
+    assert_eq!(href(base, "/a"), "https://example.org/a");
+    assert_eq!(href(base, "//cdn.org/a"), "https://cdn.org/a");
+    assert_eq!(href(base, "?q"), "https://example.org/blog/post?q");
+    assert_eq!(
+        href(base, "#title"),
+        "https://example.org/blog/post?page=2#title"
+    );
+    assert_eq!(href("https://example.org", "a"), "https://example.org/a");
+    assert_eq!(href(base, "mailto:a@example.org"), "mailto:a@example.org");
+    assert_eq!(href(base, "javascript:alert(1)"), "#");
+}
+
+#[test]
+fn html_rss_description() {
+    let md = "See [this](../a) ![img](b.png) ]]>\n<div>raw</div>\n<!-- comment -->";
+    let tokens = md.parse_md_with_config(ParserConfig::default().html(true));
+    assert_eq!(
+        html::render_as_rss_description(tokens, "https://example.org/blog/post/"),
+        "<p>See <a href=\"https://example.org/blog/a\">this</a> <img src=\"https://example.org/blog/post/b.png\" alt=\"img\"> ]]&gt;</p>\n\n"
+    );
+
+    // Autolinks without a scheme get theirs, and aren't resolved against the base URL.
+    assert_eq!(
+        html::render_as_rss_description(
+            "<www.example.com> <me@example.com> www.example.org".parse_md(),
+            "https://example.org/blog/post"
+        ),
+        "<p><a href=\"http://www.example.com\">www.example.com</a> <a href=\"mailto:me@example.com\">me@example.com</a> <a href=\"http://www.example.org\">www.example.org</a></p>"
+    );
+
+    // Each item has its own base URL, which is usually built at runtime.
+    for slug in ["first", "second"] {
+        let base_url = format!("https://example.org/{}/", slug);
+        let html = html::render_as_rss_description("[a](a)".parse_md(), &base_url);
+        assert_eq!(html, format!("<p><a href=\"{}a\">a</a></p>", base_url));
+    }
+}
+
+#[test]
+fn html_entities() {
+    assert_eq!(
+        html::render_as_html("AT&amp;T &#64; &#x40; & &; a > b `&amp;`".parse_md()),
+        "<p>AT&amp;T &#64; &#x40; &amp; &amp;; a &gt; b <code>&amp;amp;</code></p>"
+    );
+}
+
+#[test]
+fn html_autolink_escaped() {
+    let html = render_as_html("<<img src=x onerror=alert(1)//>".parse_md());
+    assert!(!html.contains("<img"));
+    assert!(html.contains(">&lt;img src=x onerror=alert(1)//</a>"));
+}
+
 #[test]
 fn code() {
     assert_eq!(
-        "`coding`".parse_md(),
-        vec![Text::code("coding").into_token()]
+        "`coding`".parse_md(),
+        vec![Text::code("coding").into_token()]
+    );
+    assert_eq!(
+        "`coding``".parse_md(),
+        vec![Text::naked("`coding``").into_token()]
+    );
+    assert_eq!("````".parse_md(), vec![Text::naked("````").into_token()]);
+    assert_eq!("`` a`b ``".parse_md(), vec![Text::code("a`b").into_token()]);
+    assert_eq!(
+        "`` `code` ``".parse_md(),
+        vec![Text::code("`code`").into_token()]
+    );
+    assert_eq!(
+        "a ``b`".parse_md(),
+        vec![Text::naked("a ``b`").into_token()]
+    );
+}
+
+#[test]
+fn code_fence() {
+    assert_eq!(
+        "```\ntest```".parse_md(),
+        vec![Token::CodeFence {
+            attrs: "",
+            code: "test".into(),
+        }]
+    );
+    assert_eq!(
+        "```rust\ntest```".parse_md(),
+        vec![Token::CodeFence {
+            attrs: "rust",
+            code: "test".into(),
+        }]
+    );
+    assert_eq!(
+        "```rust,norun\ntest```".parse_md(),
+        vec![Token::CodeFence {
+            attrs: "rust,norun",
+            code: "test".into(),
+        }]
+    );
+}
+
+#[test]
+fn code_fence_leading_blank_lines() {
+    assert_eq!(
+        "```rust\n\n\n  test\n```".parse_md(),
+        vec![Token::CodeFence {
+            attrs: "rust",
+            code: "\n\n  test\n".into(),
+        }]
+    );
+    assert_eq!(
+        &render_as_html("```\n\ntest\n```".parse_md()),
+        "<pre><code>\ntest\n</code></pre>"
+    );
+}
+
+#[test]
+fn html_code_tab_width() {
+    let md = "```\n\tfn a() {\n\t\tb\tc\n}\n```";
+    let config = HtmlConfig::default().code_tab_width(Some(4));
+    assert_eq!(
+        render_as_html_with_config(md.parse_md(), config),
+        "<pre><code>    fn a() {\n        b   c\n}\n</code></pre>"
+    );
+    assert_eq!(
+        &render_as_html(md.parse_md()),
+        "<pre><code>\tfn a() {\n\t\tb\tc\n}\n</code></pre>"
+    );
+}
+
+#[test]
+fn subscript_superscript() {
+    let sub = |value| {
+        Token::Text(Text {
+            value,
+            subscript: true,
+            ..Default::default()
+        })
+    };
+    assert_eq!(
+        "H~2~O".parse_md(),
+        vec![
+            Text::naked("H").into_token(),
+            sub("2"),
+            Text::naked("O").into_token(),
+        ]
+    );
+    assert_eq!(
+        "x^2^".parse_md(),
+        vec![
+            Text::naked("x").into_token(),
+            Token::Text(Text {
+                value: "2",
+                superscript: true,
+                ..Default::default()
+            }),
+        ]
+    );
+    assert_eq!(
+        "~~struck~~".parse_md(),
+        vec![Text::naked("~~struck~~").into_token()]
+    );
+    assert_eq!(
+        "a ~b c~".parse_md(),
+        vec![Text::naked("a ~b c~").into_token()]
+    );
+
+    assert_eq!(&render_as_html("H~2~O".parse_md()), "<p>H<sub>2</sub>O</p>");
+    assert_eq!(
+        &render_as_html("x^2^ = y^2^, *z*^3^ and ~a~ *b*".parse_md()),
+        "<p>x<sup>2</sup> = y<sup>2</sup>, <i>z</i><sup>3</sup> and <sub>a</sub> <i>b</i></p>"
+    );
+}
+
+#[test]
+fn highlight() {
+    assert_eq!(
+        "==hi== there".parse_md(),
+        vec![
+            Token::Text(Text {
+                value: "hi",
+                highlight: true,
+                ..Default::default()
+            }),
+            Text::naked(" there").into_token(),
+        ]
+    );
+    assert_eq!(
+        &render_as_html("some ==marked text== here".parse_md()),
+        "<p>some <mark>marked text</mark> here</p>"
+    );
+    for md in ["a = b", "a == b == c", "===x===", "== no ==", "==unclosed"] {
+        assert_eq!(md.parse_md(), vec![Text::naked(md).into_token()], "{}", md);
+    }
+    #[cfg(feature = "svg")]
+    assert!(render_as_svg("==hi==".parse_md(), SvgConfig::default())
+        .contains(r#"fill="darkorange">hi</tspan>"#));
+}
+
+#[test]
+fn bold_or_italic_text() {
+    fn text_test(parsed: Vec<Token<()>>, bold: bool, italic: bool) {
+        assert_eq!(
+            parsed,
+            vec![Token::Text(Text {
+                value: "ada",
+                bold,
+                italic,
+                ..Default::default()
+            })],
+        );
+    }
+
+    text_test("ada".parse_md(), false, false);
+    text_test("*ada*".parse_md(), false, true);
+    text_test("**ada**".parse_md(), true, false);
+    text_test("***ada***".parse_md(), true, true);
+
+    assert_eq!(
+        "***ada**".parse_md(),
+        vec![
+            Text::naked("*").into_token(),
+            Token::Text(Text {
+                value: "ada",
+                bold: true,
+                ..Default::default()
+            }),
+        ],
+    );
+    assert_eq!(
+        "**ada*".parse_md(),
+        vec![
+            Text::naked("*").into_token(),
+            Token::Text(Text {
+                value: "ada",
+                italic: true,
+                ..Default::default()
+            }),
+        ],
+    );
+    assert_eq!(
+        "**ada***".parse_md(),
+        vec![
+            Token::Text(Text {
+                value: "ada",
+                bold: true,
+                ..Default::default()
+            }),
+            Text::naked("*").into_token(),
+        ],
+    );
+    assert_eq!(
+        "*ada**".parse_md(),
+        vec![
+            Token::Text(Text {
+                value: "ada",
+                italic: true,
+                ..Default::default()
+            }),
+            Text::naked("*").into_token(),
+        ],
+    );
+
+    assert_eq!(
+        "**g** *g*".parse_md(),
+        vec![
+            Token::Text(Text {
+                value: "g",
+                bold: true,
+                ..Default::default()
+            }),
+            Text::naked(" ").into_token(),
+            Token::Text(Text {
+                value: "g",
+                italic: true,
+                ..Default::default()
+            })
+        ],
+    );
+}
+
+#[test]
+fn consecutive_emphasis() {
+    let bold = |value| {
+        Token::Text(Text {
+            value,
+            bold: true,
+            ..Default::default()
+        })
+    };
+    assert_eq!(
+        "**a** normal **b** and **c**".parse_md(),
+        vec![
+            bold("a"),
+            Text::naked(" normal ").into_token(),
+            bold("b"),
+            Text::naked(" and ").into_token(),
+            bold("c"),
+        ]
+    );
+    assert_eq!(
+        "**a**, **b**; __c__ text".parse_md(),
+        vec![
+            bold("a"),
+            Text::naked(", ").into_token(),
+            bold("b"),
+            Text::naked("; ").into_token(),
+            bold("c"),
+            Text::naked(" text").into_token(),
+        ]
+    );
+    assert_eq!(
+        &render_as_html("*a* x _b_ y **c** z ***d***".parse_md()),
+        "<p><i>a</i> x <i>b</i> y <b>c</b> z <b><i>d</i></b></p>"
+    );
+}
+
+#[test]
+fn nested_emphasis() {
+    let md = "**outer _inner_ outer**";
+    let tokens = md.parse_md();
+    assert_eq!(
+        tokens,
+        vec![Token::Text(Text {
+            value: "outer _inner_ outer",
+            bold: true,
+            ..Default::default()
+        })]
+    );
+    match &tokens[0] {
+        Token::Text(t) => assert_eq!(
+            t.parse_nested::<()>(),
+            Some(vec![
+                Text::naked("outer ").into_token(),
+                Token::Text(Text {
+                    value: "inner",
+                    italic: true,
+                    ..Default::default()
+                }),
+                Text::naked(" outer").into_token(),
+            ])
+        ),
+        _ => unreachable!(),
+    }
+    assert_eq!(
+        &render_as_html_with_config(&tokens, HtmlConfig::default().semantic_emphasis(true)),
+        "<p><strong>outer <em>inner</em> outer</strong></p>"
+    );
+    assert_eq!(&render_as_plain(&tokens), "outer inner outer");
+    assert_eq!(
+        &render_as_html("*a `code` b*".parse_md()),
+        "<p><i>a <code>code</code> b</i></p>"
+    );
+    assert_eq!(
+        &render_as_html("*a *b* c*".parse_md()),
+        "<p><i>a <i>b</i> c</i></p>"
+    );
+    assert_eq!(
+        &render_as_html("__foo, __bar__, baz__".parse_md()),
+        "<p><b>foo, <b>bar</b>, baz</b></p>"
+    );
+    assert_eq!(
+        &render_as_html("*foo**bar*".parse_md()),
+        "<p><i>foo**bar</i></p>"
+    );
+
+    // Nested markup is inline, so block syntax in emphasis is text.
+    let html = |md: &str| render_as_html(md.parse_md());
+    assert_eq!(html("**- a**"), "<p><b>- a</b></p>");
+    assert_eq!(html("*1. step*"), "<p><i>1. step</i></p>");
+    assert_eq!(html("**# no**"), "<p><b># no</b></p>");
+    assert_eq!(
+        html("**a\n:::details S\nb\n:::\nc**"),
+        "<p><b>a\n:::details S\nb\n:::\nc</b></p>"
+    );
+    assert_eq!(
+        stats::word_count("**see [the docs](u) first**".parse_md(), false),
+        4
+    );
+}
+
+#[test]
+fn emphasis_limits() {
+    // Huge runs are text, instead of trying to match every delimiter in them.
+    let md = format!("{0}a{0} *b*", "*".repeat(100_000));
+    let md = md.as_str();
+    assert_eq!(
+        md.parse_md(),
+        vec![
+            Text::naked(&md[..200_002]).into_token(),
+            Token::Text(Text {
+                value: "b",
+                italic: true,
+                ..Default::default()
+            }),
+        ]
+    );
+
+    let md = "***a*** *b*";
+    let config = ParserConfig::default().max_delimiter_run(2);
+    assert_eq!(
+        md.parse_md_with_config(config),
+        vec![
+            Text::naked("***a*** ").into_token(),
+            Token::Text(Text {
+                value: "b",
+                italic: true,
+                ..Default::default()
+            }),
+        ]
+    );
+
+    // The end of the emphasis is two runs away.
+    let md = "*a **b** c*";
+    assert_eq!(md.parse_md().len(), 1);
+    let config = ParserConfig::default().max_emphasis_lookahead(2);
+    assert_eq!(
+        md.parse_md_with_config(config),
+        vec![
+            Text::naked("*a ").into_token(),
+            Token::Text(Text {
+                value: "b",
+                bold: true,
+                ..Default::default()
+            }),
+            Text::naked(" c*").into_token(),
+        ]
+    );
+
+    // Lots of delimiters that can't be closed in reach are text.
+    let md = "*a ".repeat(50_000);
+    let md = md.as_str();
+    assert_eq!(md.parse_md(), vec![Text::naked(md).into_token()]);
+}
+
+#[test]
+fn link_limits() {
+    // The end of the link is past the lookahead.
+    let md = "[name](https://example.org)";
+    assert_eq!(
+        md.parse_md(),
+        vec![Token::link("name", "https://example.org")]
+    );
+    let config = ParserConfig::default().max_link_lookahead(10);
+    assert_eq!(
+        md.parse_md_with_config(config),
+        vec![Text::naked(md).into_token()]
+    );
+
+    let md = "a[^footnote]";
+    assert_eq!(md.parse_md()[1], Token::FootnoteRef("footnote"));
+    assert_eq!(
+        md.parse_md_with_config(config.max_link_lookahead(5)),
+        vec![Text::naked(md).into_token()]
+    );
+
+    // Lots of unclosed links, images, footnote labels, math and sections are text, and take
+    // linear time to parse.
+    let config = ParserConfig::default().max_link_lookahead(64);
+    for unclosed in ["[", "![", "[](", "$"] {
+        let md = unclosed.repeat(30_000);
+        let md = md.as_str();
+        assert_eq!(
+            md.parse_md_with_config(config),
+            vec![Text::naked(md).into_token()]
+        );
+    }
+    for unclosed in ["[^", ":::details a\n"] {
+        let md = unclosed.repeat(30_000);
+        let tokens = md.parse_md_with_config(config);
+        assert!(tokens
+            .iter()
+            .all(|token| matches!(token, Token::Text(_) | Token::LineBreak)));
+    }
+}
+
+#[test]
+fn disabled_constructs() {
+    let config = ParserConfig::default()
+        .headers(false)
+        .code_fences(false)
+        .images(false);
+
+    assert_eq!(
+        "# x".parse_md_with_config(config),
+        vec![Text::naked("# x").into_token()]
+    );
+    assert_eq!(
+        "![a](b) [c](d)".parse_md_with_config(config),
+        vec![Text::naked("![a](b) ").into_token(), Token::link("c", "d")]
+    );
+    assert!("```\ncode\n```"
+        .parse_md_with_config(config)
+        .iter()
+        .all(|token| matches!(token, Token::Text(t) if !t.code) || token.is_line_break()));
+    assert_eq!(
+        "a\n\n    code".parse_md_with_config(config),
+        vec![
+            Text::naked("a").into_token(),
+            Token::LineBreak,
+            Token::LineBreak,
+            Text::naked("code").into_token(),
+        ]
+    );
+
+    // Inline markup is still parsed.
+    assert_eq!(
+        "*a* `b` ## c".parse_md_with_config(config),
+        vec![
+            Token::Text(Text {
+                italic: true,
+                ..Text::naked("a")
+            }),
+            Text::naked(" ").into_token(),
+            Text::code("b").into_token(),
+            Text::naked(" ## c").into_token(),
+        ]
+    );
+
+    // Images nested in link names and emphasis stay text when rendered.
+    let html = |md: &str| render_as_html(md.parse_md_with_config(config));
+    assert_eq!(
+        html("[![a](https://evil/b.png)](c)"),
+        "<p><a href=\"c\">![a](https://evil/b.png)</a></p>"
+    );
+    assert_eq!(html("**![a](b.png)**"), "<p><b>![a](b.png)</b></p>");
+    assert_eq!(
+        html("**a *![x](y)* b**"),
+        "<p><b>a <i>![x](y)</i> b</b></p>"
+    );
+    assert_eq!(
+        html("[see ![a](b)](d)"),
+        "<p><a href=\"d\">see ![a](b)</a></p>"
+    );
+}
+
+#[test]
+fn emphasis_across_lines() {
+    assert_eq!(
+        "*unclosed\n\nnext paragraph".parse_md(),
+        vec![
+            Text::naked("*unclosed").into_token(),
+            Token::LineBreak,
+            Token::LineBreak,
+            Text::naked("next paragraph").into_token(),
+        ]
+    );
+    assert_eq!(
+        "*a\nb*".parse_md(),
+        vec![Token::Text(Text {
+            value: "a\nb",
+            italic: true,
+            ..Default::default()
+        })]
+    );
+}
+
+#[test]
+fn emphasis_flanking() {
+    // Examples from the emphasis section of the CommonMark spec.
+    let html = |md: &str| render_as_html(md.parse_md());
+    assert_eq!(html("*foo bar*"), "<p><i>foo bar</i></p>");
+    assert_eq!(html("a * foo bar*"), "<p>a * foo bar*</p>");
+    assert_eq!(html("a*\"foo\"*"), "<p>a*\"foo\"*</p>");
+    assert_eq!(html("a * b * c"), "<p>a * b * c</p>");
+    assert_eq!(html("foo*bar*"), "<p>foo<i>bar</i></p>");
+    assert_eq!(html("_foo bar_"), "<p><i>foo bar</i></p>");
+    assert_eq!(html("_ foo bar_"), "<p>_ foo bar_</p>");
+    assert_eq!(html("foo_bar_"), "<p>foo_bar_</p>");
+    assert_eq!(html("5_6_78"), "<p>5_6_78</p>");
+    assert_eq!(html("*foo bar *"), "<p>*foo bar *</p>");
+    assert_eq!(html("*(*foo)"), "<p>*(*foo)</p>");
+    assert_eq!(html("_foo_bar"), "<p>_foo_bar</p>");
+    assert_eq!(html("_(bar)_."), "<p><i>(bar)</i>.</p>");
+    assert_eq!(html("**foo bar**"), "<p><b>foo bar</b></p>");
+    assert_eq!(html("** foo bar**"), "<p>** foo bar**</p>");
+    assert_eq!(html("foo**bar**"), "<p>foo<b>bar</b></p>");
+    assert_eq!(html("foo__bar__"), "<p>foo__bar__</p>");
+    assert_eq!(html("**foo bar **"), "<p>**foo bar **</p>");
+    assert_eq!(html("__foo__bar"), "<p>__foo__bar</p>");
+    assert_eq!(html("*foo**bar*"), "<p><i>foo**bar</i></p>");
+    assert_eq!(html("**foo*"), "<p>*<i>foo</i></p>");
+    assert_eq!(html("***foo*"), "<p>**<i>foo</i></p>");
+
+    // Renderers put a space after emphasis, so intraword emphasis is checked on the tokens.
+    let emphasis = |value, bold, italic| {
+        Token::Text(Text {
+            value,
+            bold,
+            italic,
+            ..Default::default()
+        })
+    };
+    assert_eq!(
+        "a*b*c".parse_md(),
+        vec![
+            Text::naked("a").into_token(),
+            emphasis("b", false, true),
+            Text::naked("c").into_token(),
+        ]
+    );
+    assert_eq!(
+        "5*6*78".parse_md(),
+        vec![
+            Text::naked("5").into_token(),
+            emphasis("6", false, true),
+            Text::naked("78").into_token(),
+        ]
+    );
+    assert_eq!(
+        "**foo**bar".parse_md(),
+        vec![
+            emphasis("foo", true, false),
+            Text::naked("bar").into_token()
+        ]
+    );
+    assert_eq!(
+        "foo***bar***baz".parse_md(),
+        vec![
+            Text::naked("foo").into_token(),
+            emphasis("bar", true, true),
+            Text::naked("baz").into_token(),
+        ]
+    );
+    assert_eq!(
+        "*foo***".parse_md(),
+        vec![emphasis("foo", false, true), Text::naked("**").into_token()]
+    );
+}
+
+#[test]
+fn underscore_emphasis() {
+    assert_eq!(
+        "_i_".parse_md(),
+        vec![Token::Text(Text {
+            value: "i",
+            italic: true,
+            ..Default::default()
+        })],
+    );
+    assert_eq!(
+        "__b__".parse_md(),
+        vec![Token::Text(Text {
+            value: "b",
+            bold: true,
+            ..Default::default()
+        })],
+    );
+    assert_eq!(
+        "snake_case_word".parse_md(),
+        vec![Text::naked("snake_case_word").into_token()],
+    );
+    assert_eq!(
+        "_a_ and *b*".parse_md(),
+        vec![
+            Token::Text(Text {
+                value: "a",
+                italic: true,
+                ..Default::default()
+            }),
+            Text::naked(" and ").into_token(),
+            Token::Text(Text {
+                value: "b",
+                italic: true,
+                ..Default::default()
+            }),
+        ],
+    );
+}
+
+#[test]
+fn math() {
+    assert_eq!(
+        "$a+b$".parse_md(),
+        vec![Token::Math {
+            content: "a+b",
+            display: false,
+        }],
+    );
+    assert_eq!(
+        "$$E=mc^2$$".parse_md(),
+        vec![Token::Math {
+            content: "E=mc^2",
+            display: true,
+        }],
+    );
+    assert_eq!(
+        "costs $5 today".parse_md(),
+        vec![Text::naked("costs $5 today").into_token()],
+    );
+}
+
+#[test]
+fn html_math() {
+    assert_eq!(
+        &render_as_html("$a<b$".parse_md()),
+        r#"<p><span class="math inline">\(a&lt;b\)</span></p>"#
+    );
+    assert_eq!(
+        &render_as_html("$$E=mc^2$$".parse_md()),
+        r#"<div class="math display">\[E=mc^2\]</div>"#
+    );
+}
+
+#[test]
+fn footnotes() {
+    assert_eq!(
+        "a[^1]\n[^1]: note".parse_md(),
+        vec![
+            Text::naked("a").into_token(),
+            Token::FootnoteRef("1"),
+            Token::LineBreak,
+            Token::FootnoteDef {
+                id: "1",
+                body: "note",
+            },
+        ],
+    );
+    assert_eq!(
+        "a [^ b".parse_md(),
+        vec![Text::naked("a [^ b").into_token()]
+    );
+}
+
+#[test]
+fn html_footnote() {
+    assert_eq!(
+        &render_as_html("a[^1]\n\n[^1]: *note*".parse_md()),
+        "<p>a<sup><a href=\"#fn-1\" id=\"fnref-1\">1</a></sup></p>\n\n\n\
+        <section class=\"footnotes\">\n<ol>\n\
+        <li id=\"fn-1\"><i>note</i><a href=\"#fnref-1\">↩</a></li>\n\
+        </ol>\n</section>"
+    );
+}
+
+#[test]
+fn html_footnote_defined_earlier() {
+    assert_eq!(
+        &render_as_html("[^x]: note\ntext[^x] and[^y]".parse_md()),
+        "<p>text<sup><a href=\"#fn-x\" id=\"fnref-x\">1</a></sup> and[^y]</p>\n\
+        <section class=\"footnotes\">\n<ol>\n\
+        <li id=\"fn-x\">note<a href=\"#fnref-x\">↩</a></li>\n\
+        </ol>\n</section>"
+    );
+}
+
+#[test]
+fn html_footnote_definitions() {
+    // Definitions at the start leave no blank lines, refs in footnotes are linked, and only
+    // the first definition of an id is used.
+    assert_eq!(
+        &render_as_html("[^a]: see [^b]\n\n[^b]: b\ntext[^a]\n[^a]: dup".parse_md()),
+        "<p>text<sup><a href=\"#fn-a\" id=\"fnref-a\">1</a></sup></p>\n\n\
+        <section class=\"footnotes\">\n<ol>\n\
+        <li id=\"fn-a\">see <sup><a href=\"#fn-b\" id=\"fnref-b\">2</a></sup>\
+        <a href=\"#fnref-a\">↩</a></li>\n\
+        <li id=\"fn-b\">b<a href=\"#fnref-b\">↩</a></li>\n\
+        </ol>\n</section>"
+    );
+    assert_eq!(
+        &render_as_html("a\n[^a]: x\nb".parse_md()),
+        "<p>a</p>\n<p>b</p>\n<section class=\"footnotes\">\n<ol>\n\
+        <li id=\"fn-a\">x<a href=\"#fnref-a\">↩</a></li>\n\
+        </ol>\n</section>"
+    );
+}
+
+#[test]
+fn html_footnote_id_escaped() {
+    let id = r#"x"onclick="alert(1)"#;
+    let html = render_as_html(format!("a[^{0}]\n\n[^{0}]: body", id).parse_md());
+    assert!(!html.contains("onclick=\""));
+    let escaped = "x&quot;onclick=&quot;alert(1)";
+    assert!(html.contains(&format!(r##"href="#fn-{0}" id="fnref-{0}""##, escaped)));
+    assert!(html.contains(&format!(
+        r##"<li id="fn-{0}">body<a href="#fnref-{0}">"##,
+        escaped
+    )));
+}
+
+#[test]
+fn html_block() {
+    const MD: &str = "<div class=\"x\">\n*not emphasis*\n</div>\ntext";
+    let config = ParserConfig::default().html(true);
+    assert_eq!(
+        MD.parse_md_with_config(config),
+        vec![
+            Token::Html("<div class=\"x\">\n*not emphasis*\n</div>"),
+            Token::LineBreak,
+            Text::naked("text").into_token(),
+        ]
+    );
+    assert_eq!(
+        "<DIV>\nunclosed\n\nafter".parse_md_with_config(config),
+        vec![
+            Token::Html("<DIV>\nunclosed"),
+            Token::LineBreak,
+            Token::LineBreak,
+            Text::naked("after").into_token(),
+        ]
+    );
+    assert_eq!(
+        "<DIV>\nmixed case\n</div>\ntext".parse_md_with_config(config),
+        vec![
+            Token::Html("<DIV>\nmixed case\n</div>"),
+            Token::LineBreak,
+            Text::naked("text").into_token(),
+        ]
+    );
+    assert_eq!(
+        "<div></div>".parse_token_with_config::<()>(0, &[], &config),
+        Some((Token::Html("<div></div>"), 11))
+    );
+    assert_ne!(
+        "<div></div>"
+            .parse_token::<()>(0, &[])
+            .map(|(token, _)| token.kind()),
+        Some(crate::parser::TokenKind::Html)
+    );
+    assert_eq!(
+        "<span>inline</span>".parse_md_with_config(config)[0],
+        Token::Url {
+            name: None,
+            url: "span".into(),
+            is_image: false,
+            width: None,
+            height: None,
+            title: None,
+        }
+    );
+    assert!(!MD
+        .parse_md()
+        .iter()
+        .any(|token| matches!(token, Token::Html(_))));
+}
+
+#[test]
+fn html_html_block() {
+    const MD: &str = "<div>\n<b>raw</b>\n</div>";
+    let tokens = MD.parse_md_with_config(ParserConfig::default().html(true));
+    assert_eq!(&render_as_html(tokens), MD);
+}
+
+#[test]
+fn definition_list() {
+    assert_eq!(
+        "term\n: definition".parse_md(),
+        vec![Token::DefinitionList(vec![Definition {
+            term: "term",
+            descriptions: vec!["definition"],
+        }])]
+    );
+    assert_eq!(
+        "term\n: first\n: second\nother\n: third\n\ntext".parse_md()[0],
+        Token::DefinitionList(vec![
+            Definition {
+                term: "term",
+                descriptions: vec!["first", "second"],
+            },
+            Definition {
+                term: "other",
+                descriptions: vec!["third"],
+            },
+        ])
+    );
+    assert_eq!(
+        ": not a definition".parse_md(),
+        vec![Text::naked(": not a definition").into_token()]
+    );
+}
+
+#[test]
+fn html_definition_list() {
+    assert_eq!(
+        &render_as_html("term\n: definition".parse_md()),
+        "<dl>\n<dt>term</dt>\n<dd>definition</dd>\n</dl>"
+    );
+    assert_eq!(
+        &render_as_html("term\n: first\n: second".parse_md()),
+        "<dl>\n<dt>term</dt>\n<dd>first</dd>\n<dd>second</dd>\n</dl>"
+    );
+}
+
+#[test]
+fn details() {
+    assert_eq!(
+        ":::details **More**\n# inner\n*text*\n:::\nafter".parse_md(),
+        vec![
+            Token::Details {
+                summary: Text {
+                    value: "More",
+                    bold: true,
+                    ..Default::default()
+                },
+                body: vec![
+                    Token::Header(1),
+                    Text::naked("inner").into_token(),
+                    Token::LineBreak,
+                    Token::Text(Text {
+                        value: "text",
+                        italic: true,
+                        ..Default::default()
+                    }),
+                ],
+            },
+            Token::LineBreak,
+            Text::naked("after").into_token(),
+        ]
+    );
+    assert_eq!(
+        ":::details unclosed".parse_md(),
+        vec![Text::naked(":::details unclosed").into_token()]
+    );
+
+    // Sections nested deeper than the limit are text, but the sections in them aren't.
+    let md = ":::details a\n:::details b\n:::details c\n:::\n:::\n:::";
+    assert!(matches!(md.parse_md()[..], [Token::Details { .. }]));
+    let config = ParserConfig::default().max_details_depth(2);
+    let tokens = md.parse_md_with_config(config);
+    assert_eq!(tokens[0], Text::naked(":::details a").into_token());
+    assert!(matches!(tokens[2], Token::Details { .. }));
+}
+
+#[test]
+fn html_details() {
+    assert_eq!(
+        &render_as_html(":::details More\n- a\n\ntext\n:::".parse_md()),
+        "<details>\n<summary>More</summary>\n<ul>\n<li>a</li>\n</ul>\n\n<p>text</p>\n</details>"
+    );
+    // Tokens in the body end with the section.
+    assert_eq!(
+        &render_as_html(":::details S\n*a\n:::\nb*".parse_md()),
+        "<details>\n<summary>S</summary>\n<p>*a</p>\n</details>\n<p>b*</p>"
+    );
+    // `:::` lines in code fences don't close the section.
+    assert_eq!(
+        &render_as_html(":::details S\n```\n:::\n```\n:::\nafter".parse_md()),
+        "<details>\n<summary>S</summary>\n<pre><code>:::\n</code></pre>\n</details>\n<p>after</p>"
+    );
+}
+
+#[test]
+fn alert() {
+    use parser::AlertKind;
+
+    assert_eq!(
+        "> [!NOTE]\n> Some *text*\n>more\nafter".parse_md(),
+        vec![
+            Token::Alert {
+                kind: AlertKind::Note,
+                body: vec![
+                    Text::naked("Some ").into_token(),
+                    Token::Text(Text {
+                        italic: true,
+                        ..Text::naked("text")
+                    }),
+                    Token::LineBreak,
+                    Text::naked("more").into_token(),
+                ],
+            },
+            Token::LineBreak,
+            Text::naked("after").into_token(),
+        ]
+    );
+    assert_eq!(
+        "> [!warning]".parse_md(),
+        vec![Token::Alert {
+            kind: AlertKind::Warning,
+            body: vec![],
+        }]
+    );
+    // Blockquotes aren't supported, so unknown alerts are left as text.
+    assert_eq!(
+        "> [!FOO]\n> text".parse_md(),
+        vec![
+            Text::naked("> [!FOO]").into_token(),
+            Token::LineBreak,
+            Text::naked("> text").into_token(),
+        ]
+    );
+    assert_eq!(
+        "a > [!NOTE]".parse_md(),
+        vec![Text::naked("a > [!NOTE]").into_token()]
+    );
+}
+
+#[test]
+fn html_alert() {
+    assert_eq!(
+        &render_as_html("> [!TIP]\n> - a\n>\n> text".parse_md()),
+        "<div class=\"alert alert-tip\">\n<p class=\"alert-title\">Tip</p>\n<ul>\n<li>a</li>\n</ul>\n\n<p>text</p>\n</div>"
+    );
+    // Markup can't span lines of the body, so the `>` markers never end up in it.
+    assert_eq!(
+        &render_as_html("> [!NOTE]\n> *a\n> b*".parse_md()),
+        "<div class=\"alert alert-note\">\n<p class=\"alert-title\">Note</p>\n<p>*a\nb*</p>\n</div>"
+    );
+    assert_eq!(
+        &render_as_html("> [!NOTE]\n> ```\n> code\n> ```".parse_md()),
+        "<div class=\"alert alert-note\">\n<p class=\"alert-title\">Note</p>\n<p>```\ncode\n```</p>\n</div>"
+    );
+}
+
+#[test]
+fn html_custom_token() {
+    use parser::{AtToken, CustomFn};
+
+    fn star<'a>(md: &'a &str, at: usize) -> Option<AtToken<'a, char>> {
+        md.get_range_str(at..)
+            .starts_with("(*)")
+            .then(|| (Token::Custom('*'), at + 3))
+    }
+
+    let tokens = "(*) (*) b".parse_md_custom(&[star as CustomFn<_, _>]);
+    let html = html::render_as_html_custom(&tokens, html::Config::default(), |c, buf| {
+        write!(buf, "<span>{}</span>", c).unwrap()
+    });
+    assert_eq!(html, "<p><span>*</span> <span>*</span> b</p>");
+    assert_eq!(
+        &html::render_as_html_custom(&tokens, html::Config::default(), |_, _| {}),
+        "<p>  b</p>"
+    );
+}
+
+#[test]
+fn word_count() {
+    let tokens = "# A short title\nSome [linked words](https://example.org) and `code`.\n\n- one item\n```\nfn main() {}\n```".parse_md();
+    assert_eq!(stats::word_count(&tokens, false), 10);
+    assert_eq!(stats::word_count(&tokens, true), 14);
+    assert_eq!(stats::reading_time(&tokens, 200, false), 1);
+    assert_eq!(stats::reading_time(&tokens, 4, true), 4);
+    assert_eq!(stats::reading_time("".parse_md(), 200, true), 0);
+}
+
+#[test]
+fn indented_code() {
+    assert_eq!(
+        "    let a = 1;\n\tlet b = 2;".parse_md(),
+        vec![Token::CodeFence {
+            code: "let a = 1;\nlet b = 2;".into(),
+            attrs: "",
+        }]
+    );
+    assert_eq!(
+        &render_as_html("Some text:\n\n    fn main() {\n\n        run();\n    }\n\nMore text".parse_md()),
+        "<p>Some text:</p>\n\n<pre><code>fn main() {\n\n    run();\n}</code></pre>\n\n<p>More text</p>"
+    );
+    assert_eq!(
+        &render_as_html("Some text\n    continued".parse_md()),
+        "<p>Some text\ncontinued</p>"
+    );
+    assert_eq!(
+        &render_as_html("- item\n\n    continued\n# Header".parse_md()),
+        "<ul>\n<li><p>item</p>\n\n<p>continued</p></li>\n</ul>\n<h1>Header</h1>"
+    );
+}
+
+#[test]
+fn images() {
+    use links::ImageRef;
+
+    let tokens =
+        "# ![Logo](logo.svg)\nSome text ![](photo.jpg \"A photo\")\n\n[not an image](page.md)"
+            .parse_md();
+    assert_eq!(
+        links::images(&tokens),
+        vec![
+            ImageRef {
+                url: "logo.svg".into(),
+                alt: Some("Logo"),
+            },
+            ImageRef {
+                url: "photo.jpg".into(),
+                alt: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn links() {
+    use links::LinkRef;
+
+    let tokens =
+        "[Top](#top), [next page](./page.md) and [elsewhere](https://example.org)\n![image](https://example.org/a.png) <user@example.org>"
+            .parse_md();
+    assert_eq!(
+        links::links(&tokens),
+        vec![
+            LinkRef {
+                url: "#top".into(),
+                name: Some("Top"),
+                is_external: false,
+            },
+            LinkRef {
+                url: "./page.md".into(),
+                name: Some("next page"),
+                is_external: false,
+            },
+            LinkRef {
+                url: "https://example.org".into(),
+                name: Some("elsewhere"),
+                is_external: true,
+            },
+            LinkRef {
+                url: "mailto:user@example.org".into(),
+                name: Some("user@example.org"),
+                is_external: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn tab_width() {
+    let code = |code| Token::CodeFence { code, attrs: "" };
+
+    // With tab stops every 4 columns, these lines are all indented by 4 columns.
+    assert_eq!(
+        "\tone\n  \ttwo\n   \tthree\n    four".parse_md(),
+        vec![code("one\ntwo\nthree\nfour".into())]
+    );
+    assert_eq!(" \t\tcode".parse_md(), vec![code("\tcode".into())]);
+    let config = ParserConfig::default().tab_width(8);
+    assert_eq!(
+        "  \tcode".parse_md_with_config(config),
+        vec![code("    code".into())]
+    );
+    let config = ParserConfig::default().tab_width(2);
+    assert_eq!(
+        " \tcode".parse_md_with_config(config),
+        vec![Text::naked("code").into_token()]
+    );
+
+    // A tab and spaces continue a list item the same way.
+    let continued = vec![
+        Token::ListItem {
+            place: None,
+            marker: '-',
+        },
+        Text::naked("item").into_token(),
+        Token::LineBreak,
+        Token::ListContinuation,
+        Text::naked("more").into_token(),
+    ];
+    assert_eq!("- item\n\tmore".parse_md(), continued);
+    assert_eq!("- item\n \tmore".parse_md(), continued);
+    assert_eq!("- item\n  more".parse_md(), continued);
+    assert_eq!(
+        "- item\n\tmore".parse_md_with_config(ParserConfig::default().tab_width(1)),
+        vec![
+            Token::ListItem {
+                place: None,
+                marker: '-',
+            },
+            Text::naked("item").into_token(),
+            Token::LineBreak,
+            Text::naked("more").into_token(),
+        ]
+    );
+}
+
+#[test]
+fn plain() {
+    const MD: &str = "# A *fine* title\nSome **bold**, `code` and [a link](https://example.org).\n\n- first\n1. second <https://x.org>\n```rust\nlet x = 1;\n```";
+    let plain = render_as_plain(MD.parse_md());
+    assert_eq!(
+        plain,
+        "A fine title\nSome bold, code and a link.\n\nfirst\nsecond https://x.org\nlet x = 1;\n"
+    );
+    assert!(!plain.contains(['*', '#', '<', '[', '`']));
+    assert_eq!(
+        render_as_plain("www.x.org <www.y.org> <me@x.org>".parse_md()),
+        "www.x.org www.y.org me@x.org"
+    );
+}
+
+#[test]
+fn slack() {
+    let slack = |md: &str| render_as_slack(md.parse_md());
+    assert_eq!(slack("**bold**"), "*bold*");
+    assert_eq!(slack("*italic*"), "_italic_");
+    assert_eq!(slack("***both***"), "*_both_*");
+    assert_eq!(slack("`*code*`"), "`*code*`");
+    assert_eq!(slack("**a *b* c**"), "*a _b_ c*");
+    assert_eq!(slack("# A *fine* title\ntext"), "*A fine title*\ntext");
+    assert_eq!(
+        slack("[a link](https://example.org)"),
+        "<https://example.org|a link>"
+    );
+    assert_eq!(slack("<https://example.org>"), "<https://example.org>");
+    assert_eq!(
+        slack("<www.example.org>"),
+        "<http://www.example.org|www.example.org>"
+    );
+    assert_eq!(
+        slack("<me@example.org>"),
+        "<mailto:me@example.org|me@example.org>"
+    );
+    assert_eq!(
+        slack("[a](https://example.org/?a=1|b>c&d)"),
+        "<https://example.org/?a=1%7Cb&gt;c&amp;d|a>"
+    );
+    assert_eq!(slack("- a\n1. b"), "• a\n1. b");
+    assert_eq!(slack("a & b"), "a &amp; b");
+    assert_eq!(slack("```rust\nlet x = 1;\n```"), "```\nlet x = 1;\n```");
+}
+
+#[test]
+fn max_header_depth() {
+    const MD: &str = "####### title";
+    assert_eq!(MD.parse_md(), vec![Text::naked(MD).into_token()]);
+
+    let clamp = ParserConfig::default().clamp_headers(true);
+    let header = vec![Token::Header(6), Text::naked("title").into_token()];
+    assert_eq!(MD.parse_md_with_config(clamp), header);
+    assert_eq!(&render_as_html(&header), "<h6>title</h6>");
+
+    let deeper = ParserConfig::default().max_header_depth(8);
+    let header = vec![Token::Header(7), Text::naked("title").into_token()];
+    assert_eq!(MD.parse_md_with_config(deeper), header);
+    assert_eq!(&render_as_html(&header), "<h6>title</h6>");
+
+    let shallow = ParserConfig::default().max_header_depth(2);
+    assert_eq!(
+        "### title".parse_md_with_config(shallow),
+        vec![Text::naked("### title").into_token()]
+    );
+    assert_eq!(
+        "### title".parse_md_with_config(shallow.clamp_headers(true)),
+        vec![Token::Header(2), Text::naked("title").into_token()]
+    );
+}
+
+#[test]
+fn header() {
+    const HEADER: &str = "# asdasd";
+    const HEADER_3: &str = "### asdasd";
+    const NOT_HEADER: &str = "#asdasd";
+    const HEADER_AFTER_HEADER: &str = "# #asdf";
+
+    let parsed = HEADER.parse_md();
+    assert_eq!(
+        parsed,
+        vec![Token::Header(1), Text::naked("asdasd").into_token(),],
+    );
+
+    let parsed = HEADER_3.parse_md();
+    assert_eq!(
+        parsed,
+        vec![Token::Header(3), Text::naked("asdasd").into_token()],
+    );
+
+    let parsed = NOT_HEADER.parse_md();
+    assert_eq!(parsed, vec![Text::naked(NOT_HEADER).into_token()]);
+
+    let parsed = HEADER_AFTER_HEADER.parse_md();
+    assert_eq!(
+        parsed,
+        vec![Token::Header(1), Text::naked("#asdf").into_token()],
+    );
+}
+
+fn ordered_test(parsed: Vec<Token<()>>, place: usize) {
+    assert_eq!(
+        parsed,
+        vec![
+            Token::ListItem {
+                place: Some(place),
+                marker: '.',
+            },
+            Text::naked("ada").into_token(),
+        ],
+    );
+}
+
+#[test]
+fn ordered_lists() {
+    for place in 0..=100 {
+        ordered_test(format!("{}. ada", place).parse_md(), place);
+    }
+}
+
+#[test]
+#[should_panic]
+fn ordered_list_wrong() {
+    for place in 0..=100 {
+        ordered_test(format!("{}.ada", place).parse_md(), place);
+    }
+}
+
+#[test]
+fn unordered_lists() {
+    fn unordered_test(parsed: Vec<Token<()>>, marker: char) {
+        assert_eq!(
+            parsed,
+            vec![
+                Token::ListItem {
+                    place: None,
+                    marker
+                },
+                Text::naked("ada").into_token()
+            ]
+        );
+    }
+
+    unordered_test("- ada".parse_md(), '-');
+    unordered_test("+ ada".parse_md(), '+');
+    unordered_test("* ada".parse_md(), '*');
+}
+
+#[test]
+fn list_markers() {
+    let markers = |md: &str| {
+        md.parse_md()
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::ListItem { place, marker } => Some((place, marker)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        markers("+ a\n* b\n- c\n\n1. d\n10. e"),
+        [
+            (None, '+'),
+            (None, '*'),
+            (None, '-'),
+            (Some(1), '.'),
+            (Some(10), '.'),
+        ]
+    );
+
+    // The marker doesn't change how the item is rendered.
+    assert_eq!(
+        &render_as_html("+ item\n\ntext".parse_md()),
+        &render_as_html("- item\n\ntext".parse_md())
+    );
+}
+
+#[test]
+fn list_numbering_styles() {
+    let markers = |md: &str| {
+        md.parse_md()
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::ListItem { place, marker } => Some((place, marker)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        markers("a. x\nb. x\n\nC.  x\n\ni. x\niv. x\n\nI.  x\nXII. x\n\ng. x\nh. x\ni. x"),
+        [
+            (Some(1), 'a'),
+            (Some(2), 'a'),
+            (Some(3), 'A'),
+            (Some(1), 'i'),
+            (Some(4), 'i'),
+            (Some(1), 'I'),
+            (Some(12), 'I'),
+            (Some(7), 'a'),
+            (Some(8), 'a'),
+            (Some(9), 'a'),
+        ]
+    );
+    // Initials, mixed case, words and malformed numerals aren't list items.
+    assert_eq!(markers("A. Lincoln\nAb. x\nab. x\niiii. x\nic. x"), []);
+    assert_eq!(markers("*x* a. y"), []);
+    // Longer roman numerals only continue a roman list.
+    assert_eq!(
+        markers(
+            "mix. the flour
+CD. x
+
+a. x
+mix. x
+
+I.  x
+iv. x"
+        ),
+        [(Some(1), 'a'), (Some(1), 'I')]
+    );
+
+    assert_eq!(parser::list_item_label(2, 'a'), "b");
+    assert_eq!(parser::list_item_label(27, 'a'), "aa");
+    assert_eq!(parser::list_item_label(1994, 'I'), "MCMXCIV");
+    assert_eq!(parser::list_item_label(0, 'i'), "0");
+    assert_eq!(parser::list_item_label(5, '.'), "5");
+}
+
+#[test]
+fn html_list_numbering_styles() {
+    assert_eq!(
+        &render_as_html("a. item\nb. item".parse_md()),
+        "<ol type=\"a\">\n<li value=\"1\">item</li>\n<li value=\"2\">item</li>\n</ol>"
+    );
+    assert_eq!(
+        &render_as_html("1. item\n2. item".parse_md()),
+        "<ol>\n<li value=\"1\">item</li>\n<li value=\"2\">item</li>\n</ol>"
+    );
+    // A list numbered differently starts a new list.
+    assert_eq!(
+        &render_as_html("i. a\nii. b\n1. c".parse_md()),
+        "<ol type=\"i\">\n<li value=\"1\">a</li>\n<li value=\"2\">b</li>\n</ol>\n<ol>\n<li value=\"1\">c</li>\n</ol>"
+    );
+    assert_eq!(render_as_slack("B.  a\nc. b".parse_md()), "B. a\nc. b");
+}
+
+#[test]
+fn list_item_continuation() {
+    assert_eq!(
+        "- a\n\n  b".parse_md(),
+        vec![
+            Token::ListItem {
+                place: None,
+                marker: '-',
+            },
+            Text::naked("a").into_token(),
+            Token::LineBreak,
+            Token::LineBreak,
+            Token::ListContinuation,
+            Text::naked("b").into_token(),
+        ]
+    );
+    assert_eq!(
+        "text\n\n  b".parse_md()[2..],
+        [Token::LineBreak, Text::naked("b").into_token()]
+    );
+}
+
+#[test]
+fn html_loose_list_item() {
+    assert_eq!(
+        &render_as_html("- a\n\n  b\n- c\n\nd".parse_md()),
+        "<ul>\n<li><p>a</p>\n\n<p>b</p></li>\n<li><p>c</p></li>\n</ul>\n\n<p>d</p>"
+    );
+    assert_eq!(
+        &render_as_html_with_config(
+            "- a\n\n  b\n- c\n\nd".parse_md(),
+            HtmlConfig::default().loose_lists(false)
+        ),
+        "<ul>\n<li><p>a</p>\n\n<p>b</p></li>\n<li>c</li>\n</ul>\n\n<p>d</p>"
+    );
+    assert_eq!(
+        &render_as_html("- a\n  b\n\nc".parse_md()),
+        "<ul>\n<li>a\nb</li>\n</ul>\n\n<p>c</p>"
+    );
+}
+
+#[test]
+fn html_list_tightness() {
+    // Items that aren't separated by blank lines make a tight list.
+    assert_eq!(
+        &render_as_html("- a\n- b\n\nafter".parse_md()),
+        "<ul>\n<li>a</li>\n<li>b</li>\n</ul>\n\n<p>after</p>"
+    );
+    // Blank lines between the items make a loose list.
+    assert_eq!(
+        &render_as_html("- a\n\n- b\n\n\n- c\n\nafter".parse_md()),
+        "<ul>\n<li><p>a</p></li>\n<li><p>b</p></li>\n<li><p>c</p></li>\n</ul>\n\n<p>after</p>"
+    );
+    assert_eq!(
+        &render_as_html("1. a\n\n2. b\n\n- c".parse_md()),
+        "<ol>\n<li value=\"1\"><p>a</p></li>\n<li value=\"2\"><p>b</p></li>\n</ol>\n\n<ul>\n<li>c</li>\n</ul>"
+    );
+    assert_eq!(
+        &render_as_html_with_config(
+            "- a\n\n- b".parse_md(),
+            HtmlConfig::default().loose_lists(false)
+        ),
+        "<ul>\n<li>a</li>\n</ul>\n\n<ul>\n<li>b</li>\n</ul>"
+    );
+    // A different bullet starts a new list, even without a blank line.
+    assert_eq!(
+        &render_as_html("- a\n\n- b\n\n* c\n+ d".parse_md()),
+        "<ul>\n<li><p>a</p></li>\n<li><p>b</p></li>\n</ul>\n\n<ul>\n<li>c</li>\n</ul>\n<ul>\n<li>d</li>\n</ul>"
+    );
+}
+
+#[test]
+fn html_paragraph_no_newline() {
+    assert_eq!(
+        &render_as_html("asdfadsfas".parse_md()),
+        "<p>asdfadsfas</p>"
+    )
+}
+
+#[test]
+fn html_paragraph_newline() {
+    assert_eq!(
+        &render_as_html("asdfadsfas\n".parse_md()),
+        "<p>asdfadsfas</p>\n"
+    )
+}
+
+#[test]
+fn html_paragraph_two_newline() {
+    assert_eq!(
+        &render_as_html("asdfadsfas\n\n".parse_md()),
+        "<p>asdfadsfas</p>\n\n"
+    )
+}
+
+#[test]
+fn html_paragraph_newline_paragraph() {
+    assert_eq!(
+        &render_as_html("asdfadsfas\nasdfasd".parse_md()),
+        "<p>asdfadsfas\nasdfasd</p>"
+    )
+}
+
+#[test]
+fn html_paragraph_two_newline_paragraph() {
+    assert_eq!(
+        &render_as_html("asdfadsfas\n\nasdfas".parse_md()),
+        "<p>asdfadsfas</p>\n\n<p>asdfas</p>"
+    )
+}
+
+#[test]
+fn html_inline_spacing() {
+    assert_eq!(
+        &render_as_html("Some *uninspiring* text.".parse_md()),
+        "<p>Some <i>uninspiring</i> text.</p>"
+    );
+    assert_eq!(
+        &render_as_html("see <https://example.org>, `a`b".parse_md()),
+        "<p>see <a href=\"https://example.org\">https://example.org</a>, <code>a</code>b</p>"
+    );
+    assert_eq!(
+        &render_as_html("**bold**ly, a*b*c and *x*^2^".parse_md()),
+        "<p><b>bold</b>ly, a<i>b</i>c and <i>x</i><sup>2</sup></p>"
+    );
+    assert_eq!(
+        &render_as_html_with_config(
+            "[*c* ![a](b)](d)".parse_md_with_config(ParserConfig::default().images(false)),
+            HtmlConfig::default()
+        ),
+        "<p><a href=\"d\"><i>c</i> ![a](b)</a></p>"
+    );
+}
+
+#[test]
+fn html_semantic_emphasis() {
+    let md = "**bold** *italic* ***both***".parse_md();
+    assert_eq!(
+        &render_as_html(&md),
+        "<p><b>bold</b> <i>italic</i> <b><i>both</i></b></p>"
+    );
+    assert_eq!(
+        &render_as_html_with_config(&md, HtmlConfig::default().semantic_emphasis(true)),
+        "<p><strong>bold</strong> <em>italic</em> <strong><em>both</em></strong></p>"
+    );
+}
+
+#[test]
+fn html_code_fence_escaped() {
+    assert_eq!(
+        &render_as_html("```\na < b && c```".parse_md()),
+        "<pre><code>a &lt; b &amp;&amp; c</code></pre>"
+    )
+}
+
+#[test]
+fn html_code_fence_highlighter() {
+    fn highlight(code: &str, lang: &str) -> String {
+        format!("{}: {}", lang.to_uppercase(), code)
+    }
+
+    let config = HtmlConfig::default().highlighter(highlight);
+    assert_eq!(
+        &render_as_html_with_config("```rust,norun\nlet a = 1;```".parse_md(), config),
+        r#"<pre><code class="language-rust">RUST: let a = 1;</code></pre>"#
+    )
+}
+
+#[test]
+fn html_code_fence_lang_escaped() {
+    let html = render_as_html("```\"><script>alert(1)</script>\ncode\n```".parse_md());
+    assert!(!html.contains("<script>"));
+    assert!(html.starts_with(
+        r#"<pre><code class="language-&quot;&gt;&lt;script&gt;alert(1)&lt;/script&gt;">"#
+    ));
+}
+
+#[test]
+fn toc() {
+    let toc = generate_toc("# A\n## B".parse_md());
+    assert_eq!(
+        toc,
+        vec![
+            TocEntry {
+                depth: 1,
+                text: "A".into(),
+                slug: "a".into(),
+            },
+            TocEntry {
+                depth: 2,
+                text: "B".into(),
+                slug: "b".into(),
+            },
+        ]
+    );
+    assert_eq!(
+        crate::toc::slugify("What's *new* in v0.4?"),
+        "whats-new-in-v04"
+    );
+
+    let slugs = |md: &str| {
+        generate_toc(md.parse_md())
+            .into_iter()
+            .map(|entry| entry.slug)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        slugs(
+            "# Intro
+# Intro 1
+# Intro
+# Intro"
+        ),
+        ["intro", "intro-1", "intro-2", "intro-3"]
+    );
+    assert_eq!(
+        slugs(
+            "# Intro
+# Intro
+# Intro 1"
+        ),
+        ["intro", "intro-1", "intro-1-1"]
+    );
+
+    let toc = generate_toc(
+        "# Hello **World**!
+## `code`, *text* and [a link](x)"
+            .parse_md(),
+    );
+    assert_eq!(toc[0].text, "Hello World!");
+    assert_eq!(toc[0].slug, "hello-world");
+    assert_eq!(toc[1].text, "code, text and a link");
+}
+
+#[test]
+fn title() {
+    assert_eq!(
+        "# Hello **World**\n## Other".title().as_deref(),
+        Some("Hello World")
+    );
+    assert_eq!("text\n- item".title(), None);
+}
+
+#[test]
+fn html_toc() {
+    let toc = generate_toc("# A\n## B\n# C".parse_md());
+    assert_eq!(
+        html::render_toc_as_html(&toc),
+        "<ul>\n<li><a href=\"#a\">A</a><ul>\n<li><a href=\"#b\">B</a></li>\n</ul>\n</li>\n<li><a href=\"#c\">C</a></li>\n</ul>\n"
+    );
+}
+
+#[test]
+fn html_header_ids() {
+    let md = "# Intro\n## Intro".parse_md();
+    assert_eq!(&render_as_html(&md), "<h1>Intro</h1>\n<h2>Intro</h2>");
+
+    let config = HtmlConfig::default().header_ids(true);
+    assert_eq!(
+        &render_as_html_with_config(&md, config),
+        "<h1 id=\"intro\">Intro</h1>\n<h2 id=\"intro-1\">Intro</h2>"
+    );
+    let slugs: Vec<String> = generate_toc(&md).into_iter().map(|e| e.slug).collect();
+    assert_eq!(slugs, vec!["intro", "intro-1"]);
+}
+
+#[test]
+fn visit_and_fold() {
+    use crate::visit::{fold, walk, Folder, Visitor};
+
+    #[derive(Default)]
+    struct Urls<'a>(Vec<Cow<'a, str>>);
+
+    impl<'a> Visitor<'a, ()> for Urls<'a> {
+        fn visit_url(
+            &mut self,
+            _name: Option<&Text<'a>>,
+            url: Cow<'a, str>,
+            _title: Option<&'a str>,
+            _is_image: bool,
+            _width: Option<usize>,
+            _height: Option<usize>,
+        ) {
+            self.0.push(url);
+        }
+    }
+
+    struct RedactCode;
+
+    impl<'a> Folder<'a, ()> for RedactCode {
+        fn fold_code_fence(&mut self, _code: Cow<'a, str>, attrs: &'a str) -> Token<'a, ()> {
+            Token::CodeFence {
+                code: "redacted".into(),
+                attrs,
+            }
+        }
+    }
+
+    let tokens = "<a> text <b>\n```rust\nsecret```".parse_md();
+    let mut urls = Urls::default();
+    walk(&tokens, &mut urls);
+    assert_eq!(urls.0, vec!["a", "b"]);
+
+    let tokens = fold(tokens, &mut RedactCode);
+    assert_eq!(
+        tokens.last(),
+        Some(&Token::CodeFence {
+            code: "redacted".into(),
+            attrs: "rust",
+        })
+    );
+}
+
+#[test]
+fn html_full_document() {
+    use crate::html::{render_as_full_html, Stylesheet};
+
+    let html = render_as_full_html(
+        "text".parse_md(),
+        HtmlConfig::default(),
+        "A & B",
+        Some(Stylesheet::Link("style.css")),
+    );
+    assert!(html.starts_with("<!DOCTYPE html>\n"));
+    assert!(html.contains("<meta charset=\"utf-8\">"));
+    assert!(html.contains("<title>A &amp; B</title>"));
+    assert!(html.contains("<link rel=\"stylesheet\" href=\"style.css\">"));
+    assert!(html.contains("<body>\n<p>text</p>\n</body>"));
+
+    let html = render_as_full_html(
+        "text".parse_md(),
+        HtmlConfig::default(),
+        "",
+        Some(Stylesheet::Inline("p { color: red; }")),
+    );
+    assert!(html.contains("<style>\np { color: red; }\n</style>"));
+}
+
+#[test]
+fn html_paragraph_before_header() {
+    assert_eq!(
+        &render_as_html("text\n# heading".parse_md()),
+        "<p>text</p>\n<h1>heading</h1>"
+    );
+}
+
+#[test]
+fn html_paragraph_before_code_fence() {
+    assert_eq!(
+        &render_as_html("text\n```\ncode\n```".parse_md()),
+        "<p>text</p>\n<pre><code>code\n</code></pre>"
+    );
+    assert_eq!(
+        &render_as_html("text ```\ncode```".parse_md()),
+        "<p>text</p><pre><code>code</code></pre>"
+    );
+}
+
+#[test]
+fn normalize_breaks() {
+    use parser::normalize_breaks;
+
+    assert_eq!(
+        normalize_breaks(&"a\n\n\n\nb".parse_md()),
+        vec![
+            Text::naked("a").into_token(),
+            Token::LineBreak,
+            Token::LineBreak,
+            Text::naked("b").into_token(),
+        ]
+    );
+    let tokens = "a\nb\n\nc\n\n\n".parse_md();
+    assert_eq!(normalize_breaks(&tokens), tokens[..tokens.len() - 1]);
+    assert_eq!(
+        &render_as_html(normalize_breaks(
+            &":::details More\na\n\n\n\nb\n:::".parse_md()
+        )),
+        "<details>\n<summary>More</summary>\n<p>a</p>\n\n<p>b</p>\n</details>"
+    );
+}
+
+#[test]
+fn paragraph_tokens() {
+    let config = ParserConfig::default().paragraphs(true);
+    assert_eq!(
+        "a\n\nb\n# c".parse_md_with_config(config),
+        vec![
+            Token::ParagraphStart,
+            Text::naked("a").into_token(),
+            Token::ParagraphEnd,
+            Token::LineBreak,
+            Token::LineBreak,
+            Token::ParagraphStart,
+            Text::naked("b").into_token(),
+            Token::ParagraphEnd,
+            Token::LineBreak,
+            Token::Header(1),
+            Text::naked("c").into_token(),
+        ]
+    );
+}
+
+#[test]
+fn paragraph_tokens_render_the_same() {
+    let config = ParserConfig::default().paragraphs(true);
+    for md in [
+        "asdfadsfas",
+        "asdfadsfas\n",
+        "asdfadsfas\n\n",
+        "asdfadsfas\nasdfasd",
+        "asdfadsfas\n\nasdfas",
+        "text\n# heading",
+        "text\n```\ncode\n```",
+        "- item\ntext",
+        MD,
+    ] {
+        let marked = md.parse_md_with_config(config);
+        assert_eq!(render_as_html(&marked), render_as_html(md.parse_md()));
+        assert_eq!(render_as_plain(&marked), render_as_plain(md.parse_md()));
+        assert_eq!(render_as_slack(&marked), render_as_slack(md.parse_md()));
+        #[cfg(feature = "svg")]
+        assert_eq!(
+            render_as_svg(&marked, SvgConfig::default()),
+            render_as_svg(md.parse_md(), SvgConfig::default())
+        );
+    }
+}
+
+#[test]
+fn weird_md() {
+    const WEIRD_MD: &str = include_str!("../examples/weird.md");
+    let output = vec![
+        Text::naked("asdf ").into_token(),
+        Text::code("asdfasdf").into_token(),
+        Token::LineBreak,
+        Token::LineBreak,
+        Token::CodeFence {
+            code:
+                "asdfasdf\n\n\n\n# asdfasdf\n\n!!! ** ** *11*   *\n\n\\\\1***13\n\n##!\n\n``\n`\n\n"
+                    .into(),
+            attrs: "",
+        },
+        Token::LineBreak,
+        Token::LineBreak,
+        Text::naked("123123").into_token(),
+        Token::LineBreak,
+    ];
+    assert_eq!(WEIRD_MD.parse_md(), output)
+}
+
+#[test]
+fn text_seperating() {
+    assert_eq!(
+        "asdfadsf `asdf` <example>".parse_md(),
+        vec![
+            Text::naked("asdfadsf ").into_token(),
+            Text::code("asdf").into_token(),
+            Text::naked(" ").into_token(),
+            Token::Url {
+                name: None,
+                url: "example".into(),
+                title: None,
+                is_image: false,
+                width: None,
+                height: None,
+            }
+        ]
+    )
+}
+
+const MD: &str = include_str!("../examples/all.md");
+
+#[test]
+fn to_html() {
+    let html = render_as_html(MD.parse_md());
+    assert_eq!(&html, include_str!("../examples/all.html"));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_list_bullet() {
+    let md = "- a\n1. b".parse_md();
+    let svg = render_as_svg(&md, SvgConfig::default());
+    assert!(svg.contains(">\u{2022} a</tspan>"));
+    assert!(svg.contains(">1. b</tspan>"));
+
+    let config = SvgConfig::default()
+        .bullet("\u{25b8}")
+        .ordered_separator(") ");
+    let svg = render_as_svg(&md, config);
+    assert!(svg.contains(">\u{25b8} a</tspan>"));
+    assert!(svg.contains(">1) b</tspan>"));
+}
+
+#[test]
+#[cfg(feature = "emoji")]
+fn emoji() {
+    assert_eq!(
+        ":tada: shipped it :rocket:".parse_md_emoji(),
+        vec![
+            Text::naked("\u{1f389}").into_token(),
+            Text::naked(" shipped it ").into_token(),
+            Text::naked("\u{1f680}").into_token(),
+        ]
+    );
+    // Unknown shortcodes, and shortcodes in the middle of a word, stay as they are.
+    assert_eq!(
+        "a :not_an_emoji: b".parse_md_emoji(),
+        vec![Text::naked("a :not_an_emoji: b").into_token()]
+    );
+    assert_eq!(
+        "a:smile: b".parse_md_emoji(),
+        vec![Text::naked("a:smile: b").into_token()]
+    );
+    assert_eq!(
+        &render_as_html("**Done** :+1:".parse_md_emoji()),
+        "<p><b>Done</b> \u{1f44d}</p>"
+    );
+    assert_eq!(
+        &render_as_html(":tada: shipped it :rocket:".parse_md_emoji()),
+        "<p>\u{1f389} shipped it \u{1f680}</p>"
+    );
+    assert_eq!(
+        &render_as_html("*yes* :tada: **done**".parse_md_emoji()),
+        "<p><i>yes</i> \u{1f389} <b>done</b></p>"
+    );
+}
+
+#[test]
+#[cfg(feature = "wasm")]
+fn wasm() {
+    assert_eq!(
+        wasm::parse_to_html("# Title\nSome *text*"),
+        "<h1>Title</h1>\n<p>Some <i>text</i></p>"
+    );
+    let svg = wasm::parse_to_svg("# Title\nSome *text*", 300);
+    assert!(svg.starts_with(r#"<svg width="300" height="#));
+    assert!(svg.contains(">Title</tspan>"));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_header_align() {
+    let tokens = "# Title\ntext".parse_md();
+    let render = |dimensions, align| {
+        render_as_svg(
+            &tokens,
+            SvgConfig::default()
+                .dimensions(dimensions)
+                .header_align(align),
+        )
+    };
+
+    let svg = render(SvgViewportDimensions::OnlyWidth(300), SvgTextAnchor::Middle);
+    assert!(svg.contains(r#"<text x="150" text-anchor="middle" y="#));
+    // Only headers are aligned.
+    assert!(svg.contains(r#"<text x="0" y="#));
+
+    let svg = render(SvgViewportDimensions::Integer(300, 100), SvgTextAnchor::End);
+    assert!(svg.contains(r#"<text x="300" text-anchor="end" y="#));
+    let svg = render(
+        SvgViewportDimensions::OnlyWidthRaw("20em"),
+        SvgTextAnchor::Middle,
+    );
+    assert!(svg.contains(r#"<text x="50%" text-anchor="middle" y="#));
+
+    let svg = render(SvgViewportDimensions::OnlyWidth(300), SvgTextAnchor::Start);
+    assert!(!svg.contains("text-anchor"));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_title() {
+    let svg = render_as_svg(
+        "text".parse_md(),
+        SvgConfig::default()
+            .title("Fish & <chips>")
+            .description("A \"menu\""),
+    );
+    assert!(svg.contains(
+        r#"version="1.1"><title>Fish &amp; &lt;chips&gt;</title><desc>A "menu"</desc><text"#
+    ));
+
+    // The first header is the title if none is set.
+    let svg = render_as_svg("text\n# Some & title\n".parse_md(), SvgConfig::default());
+    assert!(svg.contains("<title>Some &amp; title</title>"));
+    assert!(!svg.contains("<desc>"));
+
+    let svg = render_as_svg("text".parse_md(), SvgConfig::default());
+    assert!(!svg.contains("<title>"));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_escaped() {
+    let svg = render_as_svg(
+        "Fish & chips > `a<b` [x](https://x.org/\"onload=\"alert(1))".parse_md(),
+        SvgConfig::default(),
+    );
+    assert!(svg.contains(">Fish &amp; chips &gt;</tspan>"));
+    assert!(svg.contains(">a&lt;b</tspan>"));
+    assert!(svg.contains(r#"<a xlink:href="https://x.org/&quot;onload=&quot;alert(1" "#));
+
+    let svg = render_as_svg("- `<li>`".parse_md(), SvgConfig::default().bullet("<"));
+    assert!(svg.contains(">&lt; &lt;li&gt;</tspan>"));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_link_tooltip() {
+    let svg = render_as_svg(
+        r#"[a *link*](https://example.org "Fish & <chips>")"#.parse_md(),
+        SvgConfig::default(),
+    );
+    assert!(svg.contains(r#"target="_blank"><title>Fish &amp; &lt;chips&gt;</title><tspan"#));
+
+    // The name is the tooltip if the link has no title.
+    let svg = render_as_svg(
+        "[a link](https://example.org)".parse_md(),
+        SvgConfig::default(),
+    );
+    assert!(svg.contains(r#"target="_blank"><title>a link</title><tspan"#));
+
+    let svg = render_as_svg("<https://example.org>".parse_md(), SvgConfig::default());
+    assert!(!svg.contains("<title>"));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_minify() {
+    let md = include_str!("../examples/all.md").parse_md();
+    let normal = render_as_svg(&md, SvgConfig::default());
+    let minified = render_as_svg(&md, SvgConfig::default().minify(true));
+    assert!(minified.len() < normal.len());
+    assert_eq!(
+        normal
+            .replace(r#" dx="0""#, "")
+            .replace(r#" dy="0""#, "")
+            .replace(r#"<text x="0""#, "<text")
+            .replace(r#" version="1.1""#, ""),
+        minified
+    );
+    for tag in ["svg", "text", "tspan", "a"] {
+        assert_eq!(
+            minified.matches(&format!("<{} ", tag)).count()
+                + minified.matches(&format!("<{}>", tag)).count(),
+            minified.matches(&format!("</{}>", tag)).count()
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_line_height() {
+    let md = "a\nb".parse_md();
+    let config = SvgConfig::default().dimensions(SvgViewportDimensions::OnlyWidth(100));
+    let svg = render_as_svg(&md, config);
+    assert!(svg.contains(r#"height="76""#));
+    assert!(svg.contains(r#"<text x="0" y="19">"#));
+    assert!(svg.contains(r#"<text x="0" y="38">"#));
+
+    let config = SvgConfig::default()
+        .dimensions(SvgViewportDimensions::OnlyWidth(100))
+        .line_height(240);
+    let svg = render_as_svg(&md, config);
+    assert!(svg.contains(r#"height="153""#));
+    assert!(svg.contains(r#"<text x="0" y="38">"#));
+    assert!(svg.contains(r#"<text x="0" y="76">"#));
+
+    let config = SvgConfig::default().paragraph_spacing(50);
+    let svg = render_as_svg("a\n\nb".parse_md(), config);
+    assert!(svg.contains(r#"<text x="0" y="48">"#));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_theme() {
+    let md = "# a\n<https://example.org> `b`".parse_md();
+    let svg = render_as_svg(&md, SvgConfig::default());
+    assert!(svg.contains(r#"fill="blue">https://example.org</tspan>"#));
+
+    let config = SvgConfig::default()
+        .text_color("white")
+        .header_color("yellow")
+        .link_color("cyan")
+        .code_color("gray");
+    let svg = render_as_svg(&md, config);
+    assert!(svg.contains(r#"fill="yellow">a</tspan>"#));
+    assert!(svg.contains(r#"fill="cyan">https://example.org</tspan>"#));
+    assert!(svg.contains(r#"fill="gray">b</tspan>"#));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_background_padding() {
+    let md = "a\nb".parse_md();
+    let config = SvgConfig::default()
+        .dimensions(SvgViewportDimensions::OnlyWidth(100))
+        .background("black")
+        .padding(10);
+    let svg = render_as_svg(&md, config);
+    assert!(svg.contains(r#"height="96""#));
+    assert!(svg.contains(
+        r#"<rect width="100%" height="100%" fill="black"/><g transform="translate(10 10)"><text x="0" y="19">"#
+    ));
+    assert!(svg.ends_with("</text></g></svg>"));
+}
+
+#[test]
+#[cfg(feature = "svg")]
+fn to_svg() {
+    let svg = render_as_svg(MD.parse_md(), SvgConfig::default());
+    assert_eq!(&svg, include_str!("../examples/all.svg"));
+}
+
+#[test]
+fn position() {
+    use crate::parser::Position;
+
+    let pos = |line, column| Position { line, column };
+    let source = "# Title\nSome *ünicode* text\n\n\tend";
+    assert_eq!(Position::from_offset(source, 0), pos(1, 1));
+    assert_eq!(Position::from_offset(source, 7), pos(1, 8));
+    assert_eq!(Position::from_offset(source, 8), pos(2, 1));
+    assert_eq!(Position::from_offset(source, 14), pos(2, 7));
+    // inside `ü`, which is two bytes
+    assert_eq!(Position::from_offset(source, 15), pos(2, 7));
+    assert_eq!(Position::from_offset(source, 16), pos(2, 8));
+    assert_eq!(Position::from_offset(source, 30), pos(4, 1));
+    assert_eq!(Position::from_offset(source, 31), pos(4, 2));
+    assert_eq!(Position::from_offset(source, 100), pos(4, 5));
+    assert_eq!(source.position(16), pos(2, 8));
+}
+
+#[test]
+fn front_matter() {
+    use crate::parser::FrontMatterFormat;
+
+    let md = "---\ntitle: Hello\ntags: [a, b]\n---\n# Hello";
+    assert_eq!(
+        md.parse_md(),
+        vec![
+            Token::FrontMatter {
+                format: FrontMatterFormat::Yaml,
+                raw: "title: Hello\ntags: [a, b]",
+            },
+            Token::Header(1),
+            Text::naked("Hello").into_token(),
+        ]
+    );
+    assert_eq!(&render_as_html(md.parse_md()), "<h1>Hello</h1>");
+
+    assert_eq!(
+        "+++\ntitle = \"*Hello*\"\n+++\ntext".parse_md(),
+        vec![
+            Token::FrontMatter {
+                format: FrontMatterFormat::Toml,
+                raw: "title = \"*Hello*\"",
+            },
+            Text::naked("text").into_token(),
+        ]
+    );
+    assert_eq!(
+        "---\n---".parse_md(),
+        vec![Token::FrontMatter {
+            format: FrontMatterFormat::Yaml,
+            raw: "",
+        }]
+    );
+
+    // Without a closing fence, or not at the start, it's not front matter.
+    assert_eq!(
+        "---\ntext".parse_md(),
+        vec![
+            Text::naked("---").into_token(),
+            Token::LineBreak,
+            Text::naked("text").into_token(),
+        ]
+    );
+    assert_eq!(
+        "text\n---\na: b\n---".parse_md()[0],
+        Text::naked("text").into_token()
+    );
+    assert!(!"text\n---\na: b\n---"
+        .parse_md()
+        .iter()
+        .any(|token| matches!(token, Token::FrontMatter { .. })));
+}
+
+#[test]
+fn html_nodes() {
+    use html::Node;
+
+    let element = |tag: &str, attrs: &[(&str, &str)], children| Node::Element {
+        tag: String::from(tag),
+        attrs: attrs
+            .iter()
+            .map(|(name, value)| (String::from(*name), String::from(*value)))
+            .collect(),
+        children,
+    };
+    let text = |text: &str| Node::Text(String::from(text));
+    assert_eq!(
+        html::render_as_nodes("# Title\nSome *text* [link](https://x.org)".parse_md()),
+        vec![
+            element("h1", &[], vec![text("Title")]),
+            text("\n"),
+            element(
+                "p",
+                &[],
+                vec![
+                    text("Some "),
+                    element("i", &[], vec![text("text")]),
+                    text(" "),
+                    element("a", &[("href", "https://x.org")], vec![text("link")]),
+                ]
+            ),
+        ]
+    );
+
+    let serialize = |nodes: Vec<Node>| nodes.iter().map(Node::to_html_string).collect::<String>();
+    for md in [
+        MD,
+        include_str!("../examples/weird.md"),
+        "a < b > c <i>",
+        "- unclosed list",
+        "<!-- comment -->\n![img](a.png)",
+    ] {
+        let tokens = md.parse_md();
+        assert_eq!(
+            serialize(html::render_as_nodes(&tokens)),
+            render_as_html(&tokens)
+        );
+    }
+    let config = HtmlConfig::default().keep_comments(true);
+    let tokens = "<!-- comment -->\ntext".parse_md();
+    assert_eq!(
+        serialize(html::render_as_nodes_with_config(&tokens, config)),
+        render_as_html_with_config(&tokens, config)
+    );
+}
+
+#[test]
+fn html_render_to_writer() {
+    use core::fmt::Write;
+    use html::BlockWhitespace;
+
+    /// Records what each write wrote.
+    #[derive(Default)]
+    struct Writes(Vec<String>);
+
+    impl Write for Writes {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            self.0.push(s.into());
+            Ok(())
+        }
+    }
+
+    let md = "# Title\n\nSome text[^1].\n\n- a\n\n- b\n\n```\ncode\n```\n\n# Title\n\n[^1]: Note.";
+    let tokens = md.parse_md();
+    for block_whitespace in [
+        BlockWhitespace::Source,
+        BlockWhitespace::Pretty,
+        BlockWhitespace::Compact,
+    ] {
+        let config = HtmlConfig::default()
+            .header_ids(true)
+            .block_whitespace(block_whitespace);
+        let mut writes = Writes::default();
+        html::render_to_writer(&tokens, config, &mut writes).unwrap();
+        assert_eq!(
+            writes.0.concat(),
+            render_as_html_with_config(&tokens, config)
+        );
+        // The header, the paragraph, the list, the code, the other header and the footnotes.
+        assert_eq!(writes.0.len(), 6, "{:?}", writes.0);
+    }
+
+    let tokens = include_str!("../examples/all.md").parse_md();
+    let mut writes = Writes::default();
+    html::render_to_writer(&tokens, HtmlConfig::default(), &mut writes).unwrap();
+    assert_eq!(writes.0.concat(), render_as_html(&tokens));
+}
+
+#[test]
+fn html_trailing_newline() {
+    use html::{BlockWhitespace, TrailingNewline};
+
+    let render = |md: &str, trailing_newline, block_whitespace| {
+        let config = HtmlConfig::default()
+            .trailing_newline(trailing_newline)
+            .block_whitespace(block_whitespace);
+        let tokens = md.parse_md();
+        let mut streamed = String::new();
+        html::render_to_writer(&tokens, config, &mut streamed).unwrap();
+        let html = render_as_html_with_config(&tokens, config);
+        assert_eq!(html, streamed);
+        html
+    };
+
+    for (block_whitespace, html) in [
+        (BlockWhitespace::Source, "<p>a</p>\n\n<h1>b</h1>"),
+        (BlockWhitespace::Pretty, "<p>a</p>\n<h1>b</h1>"),
+    ] {
+        for md in ["a\n\n# b", "a\n\n# b\n", "a\n\n# b\n\n\n"] {
+            assert_eq!(
+                render(md, TrailingNewline::Always, block_whitespace),
+                format!("{}\n", html)
+            );
+            assert_eq!(render(md, TrailingNewline::Never, block_whitespace), html);
+        }
+    }
+    assert_eq!(
+        render("a\n", TrailingNewline::Source, BlockWhitespace::Source),
+        "<p>a</p>\n"
     );
     assert_eq!(
-        "`coding``".parse_md(),
-        vec![Text::code("coding").into_token()]
+        render("", TrailingNewline::Always, BlockWhitespace::Source),
+        ""
     );
-    assert_eq!("````".parse_md(), vec![]);
 }
 
 #[test]
-fn code_fence() {
+fn html_block_whitespace() {
+    use html::BlockWhitespace;
+
+    let tokens = "- a\n\n  more\n- b\n\nsome\ntext\n```\ncode\n  indented\n```".parse_md();
+    let render =
+        |value| render_as_html_with_config(&tokens, HtmlConfig::default().block_whitespace(value));
     assert_eq!(
-        "```\ntest```".parse_md(),
-        vec![Token::CodeFence {
-            attrs: "",
-            code: "test",
-        }]
+        render(BlockWhitespace::Source),
+        "<ul>\n<li><p>a</p>\n\n<p>more</p></li>\n<li><p>b</p></li>\n</ul>\n\n<p>some\ntext</p>\n<pre><code>code\n  indented\n</code></pre>"
     );
     assert_eq!(
-        "```rust\ntest```".parse_md(),
-        vec![Token::CodeFence {
-            attrs: "rust",
-            code: "test",
-        }]
+        render(BlockWhitespace::Pretty),
+        "<ul>\n  <li>\n    <p>a</p>\n    <p>more</p>\n  </li>\n  <li>\n    <p>b</p>\n  </li>\n</ul>\n<p>some\ntext</p>\n<pre><code>code\n  indented\n</code></pre>"
     );
     assert_eq!(
-        "```rust,norun\ntest```".parse_md(),
-        vec![Token::CodeFence {
-            attrs: "rust,norun",
-            code: "test",
-        }]
+        render(BlockWhitespace::Compact),
+        "<ul><li><p>a</p><p>more</p></li><li><p>b</p></li></ul><p>some\ntext</p><pre><code>code\n  indented\n</code></pre>"
+    );
+
+    // Text next to blocks in a block stays on the line of the start tag.
+    let tokens = "- item\n  ```\n  code\n  ```\n\nafter".parse_md();
+    assert_eq!(
+        render_as_html_with_config(
+            &tokens,
+            HtmlConfig::default().block_whitespace(BlockWhitespace::Pretty)
+        ),
+        "<ul>\n  <li>item\n    <pre><code>code\n</code></pre>\n  </li>\n</ul>\n<p>after</p>"
     );
 }
 
 #[test]
-fn bold_or_italic_text() {
-    fn text_test(parsed: Vec<Token<()>>, bold: bool, italic: bool) {
-        assert_eq!(
-            parsed,
-            vec![Token::Text(Text {
-                value: "ada",
-                bold,
-                italic,
-                code: false,
-            })],
-        );
-    }
-
-    text_test("ada".parse_md(), false, false);
-    text_test("*ada*".parse_md(), false, true);
-    text_test("**ada**".parse_md(), true, false);
-    text_test("***ada***".parse_md(), true, true);
+fn html_nodes_raw_html() {
+    use html::{BlockWhitespace, Node};
 
+    // Raw HTML is kept as text, so it can't be mistaken for the elements around it.
+    let md = "<div title=\"a>b\">\n<p class='x'>raw</p>\n</div>\n\n- ![a>\"b](x.png)\n\nafter";
+    let tokens = md.parse_md_with_config(ParserConfig::default().html(true));
+    let nodes = html::render_as_nodes(&tokens);
     assert_eq!(
-        "***ada**".parse_md(),
-        vec![Token::Text(Text {
-            value: "*ada",
-            bold: true,
-            ..Default::default()
-        })],
+        nodes[0],
+        Node::Text(String::from(
+            "<div title=\"a>b\">\n<p class='x'>raw</p>\n</div>\n\n"
+        ))
     );
+    let Node::Element { tag, children, .. } = &nodes[1] else {
+        panic!("{:?}", nodes);
+    };
+    assert_eq!(tag, "ul");
+    assert!(matches!(
+        &children[1],
+        Node::Element { children, .. } if matches!(
+            &children[0],
+            Node::Element { attrs, .. } if attrs[1] == (String::from("alt"), String::from("a&gt;&quot;b"))
+        )
+    ));
     assert_eq!(
-        "**ada*".parse_md(),
-        vec![Token::Text(Text {
-            value: "*ada",
-            italic: true,
-            ..Default::default()
-        })],
+        nodes.iter().map(Node::to_html_string).collect::<String>(),
+        render_as_html(&tokens)
     );
+
     assert_eq!(
-        "**ada***".parse_md(),
-        vec![Token::Text(Text {
-            value: "ada",
-            bold: true,
-            ..Default::default()
-        })],
+        render_as_html_with_config(
+            &tokens,
+            HtmlConfig::default().block_whitespace(BlockWhitespace::Pretty)
+        ),
+        "<div title=\"a>b\">\n<p class='x'>raw</p>\n</div>\n<ul>\n  <li><img src=\"x.png\" alt=\"a&gt;&quot;b\"></li>\n</ul>\n<p>after</p>"
     );
+}
+
+#[test]
+fn html_escape() {
     assert_eq!(
-        "*ada**".parse_md(),
-        vec![Token::Text(Text {
-            value: "ada",
-            italic: true,
-            ..Default::default()
-        })],
+        html::escape(r#"<a href="x">Tom & Jerry's</a>"#),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
     );
-
     assert_eq!(
-        "**g** *g*".parse_md(),
-        vec![
-            Token::Text(Text {
-                value: "g",
-                bold: true,
-                ..Default::default()
-            }),
-            Token::Text(Text {
-                value: "g",
-                italic: true,
-                ..Default::default()
-            })
-        ],
+        html::escape("nothing special, ünicode"),
+        "nothing special, ünicode"
     );
+    assert_eq!(html::escape(""), "");
+
+    let mut buf = String::from("<p>");
+    html::write_escaped(&mut buf, "1 < 2").unwrap();
+    assert_eq!(buf, "<p>1 &lt; 2");
 }
 
 #[test]
-fn header() {
-    const HEADER: &str = "# asdasd";
-    const HEADER_3: &str = "### asdasd";
-    const NOT_HEADER: &str = "#asdasd";
-    const HEADER_AFTER_HEADER: &str = "# #asdf";
+fn parse_smart_pointers() {
+    use alloc::{rc::Rc, sync::Arc};
 
-    let parsed = HEADER.parse_md();
-    assert_eq!(
-        parsed,
-        vec![Token::Header(1), Text::naked("asdasd").into_token(),],
-    );
+    const MD: &str = "# Title\nSome **bold** [text](https://example.org).\n- item\n";
+    let expected = MD.parse_md();
+    assert_eq!(Cow::Borrowed(MD).parse_md(), expected);
+    assert_eq!(Cow::<str>::Owned(String::from(MD)).parse_md(), expected);
+    assert_eq!(Rc::<str>::from(MD).parse_md(), expected);
+    assert_eq!(Arc::<str>::from(MD).parse_md(), expected);
 
-    let parsed = HEADER_3.parse_md();
-    assert_eq!(
-        parsed,
-        vec![Token::Header(3), Text::naked("asdasd").into_token()],
-    );
+    // Tokens borrow from the pointer, so they can be kept while it is alive.
+    let document: Arc<str> = Arc::from(MD);
+    let tokens = document.parse_md();
+    assert_eq!(document.position(8).line, 2);
+    assert_eq!(render_as_html(&tokens), render_as_html(&expected));
+}
 
-    let parsed = NOT_HEADER.parse_md();
-    assert_eq!(parsed, vec![Text::naked(NOT_HEADER).into_token()]);
+#[test]
+fn scan_token() {
+    use crate::parser::{Config, TokenKind};
 
-    let parsed = HEADER_AFTER_HEADER.parse_md();
+    const MD: &str = "---\na: b\n---\n# A *fine* title\nSome **bold**, `code` and [a link](https://x.org).\n\n- item\n  more\n1. second $x$\n```rust\nlet x = 1;\n```\n[^1]: note\n\n    code\n\n    more\n\n:::details More\n*a* <me@x.org>\n:::\n> [!NOTE]\n> www.x.org\nterm\n: a\n: b\nmix. iv. x";
+    let config = Config::default();
+    let mut at = 0;
+    let mut kinds = Vec::new();
+    while let Some((token, nat)) = MD.parse_token_with_config::<()>(at, &[], &config) {
+        assert_eq!(MD.scan_token(at), Some((token.kind(), nat)));
+        kinds.push(token.kind());
+        at = nat;
+    }
+    assert_eq!(MD.scan_token(at), None);
     assert_eq!(
-        parsed,
-        vec![Token::Header(1), Text::naked("#asdf").into_token()],
+        kinds,
+        MD.parse_md().iter().map(Token::kind).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        &kinds[..3],
+        [TokenKind::FrontMatter, TokenKind::Header, TokenKind::Text]
     );
 }
 
-fn ordered_test(parsed: Vec<Token<()>>, place: usize) {
+#[test]
+fn indented_fence() {
+    let md = "- item\n  ```rust\n  fn a() {\n      b\n  }\n  ```\n\nafter";
+    let code = |tokens: Vec<Token<'_, ()>>| {
+        tokens.into_iter().find_map(|token| match token {
+            Token::CodeFence { code, attrs } => Some((String::from(code), String::from(attrs))),
+            _ => None,
+        })
+    };
     assert_eq!(
-        parsed,
-        vec![
-            Token::ListItem(Some(place)),
-            Text::naked("ada").into_token(),
-        ],
+        code(md.parse_md()),
+        Some((String::from("fn a() {\n    b\n}\n"), String::from("rust")))
+    );
+    // Lines that are indented less than the fence lose all of their indentation.
+    assert_eq!(
+        code("  ```\n a\nb\n   c\n  ```".parse_md()),
+        Some((String::from("a\nb\n c\n"), String::new()))
+    );
+    assert_eq!(
+        code(md.parse_md_with_config(ParserConfig::default().strip_fence_indent(false))),
+        Some((
+            String::from("  fn a() {\n      b\n  }\n  "),
+            String::from("rust")
+        ))
     );
 }
 
 #[test]
-fn ordered_lists() {
-    for place in 0..=100 {
-        ordered_test(format!("{}. ada", place).parse_md(), place);
+fn token_classification() {
+    use crate::parser::FrontMatterFormat;
+
+    let text = Text::naked("a");
+    let blocks: [Token<'_, ()>; 8] = [
+        Token::Header(1),
+        Token::ListItem {
+            place: None,
+            marker: '-',
+        },
+        Token::CodeFence {
+            code: Cow::Borrowed("a"),
+            attrs: "",
+        },
+        Token::Html("<div></div>"),
+        Token::FootnoteDef { id: "1", body: "a" },
+        Token::DefinitionList(Vec::new()),
+        Token::Details {
+            summary: text.clone(),
+            body: Vec::new(),
+        },
+        Token::Math {
+            content: "x",
+            display: true,
+        },
+    ];
+    let inlines: [Token<'_, ()>; 5] = [
+        text.clone().into_token(),
+        Token::Url {
+            name: None,
+            url: "https://x.org".into(),
+            title: None,
+            is_image: false,
+            width: None,
+            height: None,
+        },
+        Token::Math {
+            content: "x",
+            display: false,
+        },
+        Token::FootnoteRef("1"),
+        Token::Custom(()),
+    ];
+    let others: [Token<'_, ()>; 6] = [
+        Token::ListContinuation,
+        Token::Comment("a"),
+        Token::FrontMatter {
+            format: FrontMatterFormat::Yaml,
+            raw: "a: b",
+        },
+        Token::LineBreak,
+        Token::ParagraphStart,
+        Token::ParagraphEnd,
+    ];
+    for token in &blocks {
+        assert!(token.is_block() && !token.is_inline(), "{:?}", token);
+    }
+    for token in &inlines {
+        assert!(!token.is_block() && token.is_inline(), "{:?}", token);
+    }
+    for token in &others {
+        assert!(!token.is_block() && !token.is_inline(), "{:?}", token);
+    }
+    for token in blocks.iter().chain(&inlines).chain(&others) {
+        assert_eq!(token.is_line_break(), token == &Token::LineBreak);
     }
 }
 
 #[test]
-#[should_panic]
-fn ordered_list_wrong() {
-    for place in 0..=100 {
-        ordered_test(format!("{}.ada", place).parse_md(), place);
+fn token_at() {
+    let md = "# Title\nSome *text*\n- item";
+    let mut tokens = Vec::new();
+    let mut at = 0;
+    while let Some((token, next)) = md.token_at(at) {
+        assert!(next > at);
+        tokens.push(token);
+        at = next;
     }
+    assert_eq!(at, md.len());
+    assert_eq!(tokens, md.parse_md());
+
+    // Re-parsing from the start of a line gives the same tokens as the whole document.
+    let line = md.find("Some").unwrap();
+    assert_eq!(
+        md.token_at(line),
+        Some((Text::naked("Some ").into_token(), 13))
+    );
+    assert_eq!(md.token_at(md.len()), None);
 }
 
 #[test]
-fn unordered_lists() {
-    fn unordered_test(parsed: Vec<Token<()>>) {
-        assert_eq!(
-            parsed,
-            vec![Token::ListItem(None), Text::naked("ada").into_token()]
-        );
-    }
+fn diagnostics() {
+    let messages = |md: &str| {
+        md.parse_md_with_diagnostics()
+            .1
+            .into_iter()
+            .map(|diagnostic| (diagnostic.span, diagnostic.message))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        messages("text\n```rust\nlet x = 1;"),
+        [(5..8, "unclosed code fence treated as literal")]
+    );
+    assert_eq!(
+        messages("some *unclosed emphasis"),
+        [(5..6, "unclosed `*` treated as literal")]
+    );
+    assert_eq!(
+        messages("a `b and [c](d"),
+        [
+            (2..3, "unclosed code span treated as literal"),
+            (9..10, "malformed link treated as literal"),
+        ]
+    );
+    // Leftover delimiters of emphasis that did close are reported too.
+    assert_eq!(
+        messages("**a*"),
+        [(0..1, "unclosed `*` treated as literal")]
+    );
 
-    unordered_test("- ada".parse_md());
-    unordered_test("+ ada".parse_md());
-    unordered_test("* ada".parse_md());
+    // Literal delimiters that can't start markup are fine.
+    assert_eq!(messages("a * b, snake_case and [c]"), []);
+    let md = "# Title\n*a* `b` [c](d)\n```\ncode\n```";
+    let (tokens, diagnostics) = md.parse_md_with_diagnostics();
+    assert_eq!(tokens, md.parse_md());
+    assert_eq!(diagnostics, []);
 }
 
 #[test]
-fn html_paragraph_no_newline() {
+fn parse_md_lossless() {
+    let tokens = "**bold**".parse_md_lossless();
     assert_eq!(
-        &render_as_html("asdfadsfas".parse_md()),
-        "<p>asdfadsfas </p>"
-    )
+        tokens,
+        [(
+            Token::Text(Text {
+                bold: true,
+                ..Text::naked("bold")
+            }),
+            "**bold**"
+        )]
+    );
+
+    let md =
+        "---\ntitle: a\n---\n# Title\n  - *a* `b` [c](d \"e\")\n\n```rust\ncode\n```\ntrailing  ";
+    let tokens = md.parse_md_lossless();
+    assert_eq!(
+        tokens
+            .iter()
+            .map(|(token, _)| token.clone())
+            .collect::<Vec<_>>(),
+        md.parse_md()
+    );
+    assert_eq!(tokens.iter().map(|(_, raw)| *raw).collect::<String>(), md);
+    let raw = |kind| tokens.iter().find(|(t, _)| t.kind() == kind).unwrap().1;
+    assert_eq!(raw(crate::parser::TokenKind::Url), "[c](d \"e\")");
+    assert_eq!(
+        raw(crate::parser::TokenKind::CodeFence),
+        "```rust\ncode\n```"
+    );
 }
 
 #[test]
-fn html_paragraph_newline() {
+fn coalesce_text() {
+    use parser::coalesce_text;
+
+    let source = "one two **three** four";
+    let naked = |range: core::ops::Range<usize>| Text::naked(&source[range]).into_token();
+    let bold = Token::Text(Text {
+        bold: true,
+        ..Text::naked(&source[10..15])
+    });
+    let tokens: Vec<Token<()>> = vec![naked(0..4), naked(4..8), bold.clone(), naked(18..22)];
     assert_eq!(
-        &render_as_html("asdfadsfas\n".parse_md()),
-        "<p>asdfadsfas </p>\n"
-    )
+        coalesce_text(source, &tokens),
+        [naked(0..8), bold.clone(), naked(18..22)]
+    );
+
+    // Text that isn't next to the other one, or isn't in the source, isn't merged.
+    let tokens: Vec<Token<()>> = vec![
+        naked(0..3),
+        naked(4..7),
+        Text::naked(" x").into_token(),
+        Text::naked(" y").into_token(),
+    ];
+    assert_eq!(coalesce_text(source, &tokens), tokens);
+
+    let tokens = vec![
+        Token::Details {
+            summary: Text::naked("s"),
+            body: vec![naked(0..4), naked(4..7)],
+        },
+        naked(7..8),
+        naked(8..10),
+    ];
+    assert_eq!(
+        coalesce_text(source, &tokens),
+        [
+            Token::Details {
+                summary: Text::naked("s"),
+                body: vec![naked(0..7)],
+            },
+            naked(7..10),
+        ]
+    );
+    assert_eq!(coalesce_text(source, &source.parse_md()), source.parse_md());
 }
 
 #[test]
-fn html_paragraph_two_newline() {
+fn text_eq() {
+    let content_eq = |a: &str, b: &str| {
+        let (mut a, mut b) = (a.parse_md(), b.parse_md());
+        let is_space = |t: &Token<()>| matches!(t, Token::Text(t) if t.value.trim().is_empty());
+        a.retain(|t| !is_space(t));
+        b.retain(|t| !is_space(t));
+        a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| a.text_eq(b))
+    };
+
+    assert_ne!("**a**".parse_md(), "a".parse_md());
+    assert!(content_eq("**a**", "a"));
+    assert!(content_eq("**a** b", "a *b*"));
+    assert!(content_eq("`a` ==b==", "*a* b"));
+    assert!(content_eq("[**a**](x)", "[a](x)"));
+    assert!(!content_eq("[a](x)", "[a](y)"));
+    assert!(!content_eq("**a**", "b"));
+    assert!(!content_eq("# a", "## a"));
+    assert!(content_eq(
+        ":::details **S**\nsome *text*\n:::",
+        ":::details S\n**some** text\n:::"
+    ));
+    assert!(!content_eq(
+        ":::details S\nsome text\n:::",
+        ":::details S\nother text\n:::"
+    ));
+    assert!(content_eq("> [!NOTE]\n> `a` b", "> [!NOTE]\n> a **b**"));
+    assert!(!content_eq("> [!NOTE]\n> a", "> [!TIP]\n> a"));
+}
+
+#[test]
+fn wrap_text() {
+    let wrap = |text, width| crate::wrap_text(text, width).collect::<Vec<_>>();
+    assert_eq!(wrap("abc def", 3), ["abc", "def"]);
+    assert_eq!(wrap("abc def", 7), ["abc def"]);
+    assert_eq!(wrap("abcdefgh", 3), ["abc", "def", "gh"]);
+    assert_eq!(wrap("a abcdefgh b", 3), ["a", "abc", "def", "gh", "b"]);
     assert_eq!(
-        &render_as_html("asdfadsfas\n\n".parse_md()),
-        "<p>asdfadsfas </p>\n\n"
-    )
+        wrap("the quick brown fox jumps", 10),
+        ["the quick", "brown fox", "jumps"]
+    );
+    assert_eq!(wrap("a\n\n  b   c  ", 3), ["a", "", "b", "c"]);
+    assert_eq!(wrap("h\u{e9}llo", 2), ["h\u{e9}", "ll", "o"]);
+    assert_eq!(wrap("", 3), Vec::<&str>::new());
+    assert_eq!(wrap("ab", 0), ["a", "b"]);
 }
 
 #[test]
-fn html_paragraph_newline_paragraph() {
+fn display_width() {
+    assert_eq!(crate::display_width("hello"), 5);
+    assert_eq!(crate::display_width("\u{65e5}\u{672c}\u{8a9e}"), 6);
+    assert_eq!(crate::display_width("\u{ff21}b"), 3);
+    assert_eq!(crate::display_width("e\u{301}\u{200b}"), 1);
+    assert_eq!(crate::display_width(""), 0);
+
+    let tokens = "**bold** `\u{65e5}\u{672c}` [e\u{301}](b)\n# a\n```\nab\nabcd\n```".parse_md();
+    let widths: Vec<_> = tokens.iter().map(Token::<()>::display_width).collect();
+    assert_eq!(widths, [4, 0, 4, 0, 1, 0, 0, 1, 0, 4]);
+    assert_eq!(Text::code("\u{65e5} a").display_width(), 4);
+
+    let wrap = |text, width| crate::wrap_text(text, width).collect::<Vec<_>>();
     assert_eq!(
-        &render_as_html("asdfadsfas\nasdfasd".parse_md()),
-        "<p>asdfadsfas \nasdfasd </p>"
-    )
+        wrap("\u{65e5}\u{672c} \u{8a9e}", 4),
+        ["\u{65e5}\u{672c}", "\u{8a9e}"]
+    );
+    assert_eq!(
+        wrap("\u{65e5}\u{672c}\u{8a9e}", 3),
+        ["\u{65e5}", "\u{672c}", "\u{8a9e}"]
+    );
+    assert_eq!(wrap("e\u{301}e\u{301}", 1), ["e\u{301}", "e\u{301}"]);
 }
 
 #[test]
-fn html_paragraph_two_newline_paragraph() {
+fn token_constructors() {
     assert_eq!(
-        &render_as_html("asdfadsfas\n\nasdfas".parse_md()),
-        "<p>asdfadsfas </p>\n\n<p>asdfas </p>"
-    )
+        "<https://example.org> [a](b) ![c](d) [](e)".parse_md(),
+        vec![
+            Token::url("https://example.org"),
+            Text::naked(" ").into_token(),
+            Token::link("a", "b"),
+            Text::naked(" ").into_token(),
+            Token::image("c", "d"),
+            Text::naked(" ").into_token(),
+            Token::link("", "e"),
+        ]
+    );
+    assert_eq!(Token::<()>::link("", "e"), Token::url("e"));
+    assert_eq!(
+        "# a\n###### b".parse_md(),
+        vec![
+            Token::header(1),
+            Text::naked("a").into_token(),
+            Token::LineBreak,
+            Token::header(6),
+            Text::naked("b").into_token(),
+        ]
+    );
 }
 
 #[test]
-fn weird_md() {
-    const WEIRD_MD: &str = include_str!("../examples/weird.md");
-    let output = vec![
-        Text::naked("asdf ").into_token(),
-        Text::code("asdfasdf").into_token(),
-        Token::LineBreak,
-        Token::LineBreak,
-        Token::CodeFence {
-            code:
-                "asdfasdf\n\n\n\n# asdfasdf\n\n!!! ** ** *11*   *\n\n\\\\1***13\n\n##!\n\n``\n`\n\n",
-            attrs: "",
-        },
-        Token::LineBreak,
-        Token::LineBreak,
-        Text::naked("123123").into_token(),
-        Token::LineBreak,
+fn token_display() {
+    use alloc::string::ToString;
+
+    assert_eq!(Token::<()>::Header(2).to_string(), "## ");
+    let item = |place, marker| {
+        let token: Token<()> = Token::ListItem { place, marker };
+        token.to_string()
+    };
+    assert_eq!(item(None, '-'), "- ");
+    assert_eq!(item(Some(3), '.'), "3. ");
+    assert_eq!(item(Some(3), 'a'), "c. ");
+    assert_eq!(item(Some(4), 'I'), "IV. ");
+    assert_eq!(Token::<()>::ListContinuation.to_string(), "  ");
+    assert_eq!(Token::<()>::LineBreak.to_string(), "\n");
+    assert_eq!(Token::<()>::ParagraphStart.to_string(), "");
+    assert_eq!(Token::<()>::Custom(()).to_string(), "");
+
+    let single_tokens = [
+        "text",
+        "**bold**",
+        "*italic*",
+        "***both***",
+        "`code`",
+        "`` a`b ``",
+        "~sub~",
+        "^sup^",
+        "==highlight==",
+        "<https://example.org>",
+        "[name](url)",
+        "[**bold name**](url \"title\")",
+        "![alt](url =200x100)",
+        "$x^2$",
+        "$$x^2$$",
+        "[^1]",
+        "[^1]: body",
+        ":::details Summary\nsome *text*\n:::",
+        "> [!NOTE]\n> some\n> text",
+        "term\n: one\n: two",
+        "<!-- comment -->",
+        "---\nkey: value\n---",
+        "```rust\nfn main() {}\n```",
     ];
-    assert_eq!(WEIRD_MD.parse_md(), output)
+    for md in single_tokens {
+        let config = ParserConfig::default().html(true);
+        let tokens = md.parse_md_with_config(config);
+        assert_eq!(tokens.len(), 1, "{}", md);
+        assert_eq!(tokens[0].to_string(), md);
+    }
 }
 
 #[test]
-fn text_seperating() {
+fn preview() {
+    let short = "# Title\nA *short* document.".parse_md();
+    assert_eq!(render_preview(&short, 100), "Title A short document.");
+    assert_eq!(render_preview(&short, 23), "Title A short document.");
+
+    let long = "Some text\n```\nlong code\n```\nthat goes on and on, and on.".parse_md();
     assert_eq!(
-        "asdfadsf `asdf` <example>".parse_md(),
-        vec![
-            Text::naked("asdfadsf ").into_token(),
-            Text::code("asdf").into_token(),
-            Token::Url {
-                name: None,
-                url: "example",
-                is_image: false
-            }
-        ]
-    )
+        render_preview(&long, 100),
+        "Some text that goes on and on, and on."
+    );
+    assert_eq!(render_preview(&long, 21), "Some text that goes…");
+    assert_eq!(render_preview(&long, 23), "Some text that goes on…");
+    assert_eq!(render_preview(&long, 4), "Som…");
 }
 
-const MD: &str = include_str!("../examples/all.md");
-
 #[test]
-fn to_html() {
-    let html = render_as_html(&MD.parse_md());
-    assert_eq!(&html, include_str!("../examples/all.html"));
+fn document() {
+    struct Post {
+        document: Document,
+    }
+
+    let source = String::from("# Title\nSome *uninspiring* text.");
+    let post = Post {
+        document: Document::parse(source.clone()),
+    };
+    assert_eq!(post.document.source(), source);
+    assert_eq!(post.document.tokens(), source.parse_md());
+    assert_eq!(post.document.plain(), render_as_plain(source.parse_md()));
+    assert_eq!(
+        post.document.html(),
+        "<h1>Title</h1>\n<p>Some <i>uninspiring</i> text.</p>"
+    );
+    #[cfg(feature = "svg")]
+    assert_eq!(
+        post.document.svg(SvgConfig::default()),
+        render_as_svg(source.parse_md(), SvgConfig::default())
+    );
+
+    let parsed: Document = "<div>raw</div>".parse().unwrap();
+    assert_eq!(parsed.tokens(), "<div>raw</div>".parse_md());
+    let html = Document::parse_with_config("<div>raw</div>", ParserConfig::default().html(true));
+    assert_eq!(html.tokens(), [Token::Html("<div>raw</div>")]);
 }
 
 #[test]
-fn to_svg() {
-    let svg = render_as_svg(&MD.parse_md(), SvgConfig::default());
-    assert_eq!(&svg, include_str!("../examples/all.svg"));
+fn stream_parser() {
+    use alloc::{string::String, vec::Vec};
+    use parser::StreamParser;
+
+    fn streamed(md: &str, config: ParserConfig) -> (Vec<String>, usize) {
+        let mut parser = StreamParser::with_config(md.chars(), config);
+        let (mut tokens, mut blocks) = (Vec::new(), 0);
+        while let Some(block) = parser.next_block() {
+            tokens.extend(block.iter().map(|token| format!("{:?}", token)));
+            blocks += 1;
+        }
+        (tokens, blocks)
+    }
+
+    let docs = [
+        MD,
+        include_str!("../examples/weird.md"),
+        "- a\n\n  b",
+        "h. x\n\ni. y",
+        "---\na\n\nb\n---\ntext\n\n---\nx\n---",
+        "```\na\n\nb\n```",
+        ":::details S\na\n\nb\n:::",
+        "<!-- a\n\nb -->",
+        "a <b\n\nc> d",
+        "`a\n\nb`",
+        "$$a\n\nb$$",
+        "x\n\n    code\n\n    more",
+        "a\n \n\nb",
+    ];
+    for config in [
+        ParserConfig::default(),
+        ParserConfig::default().paragraphs(true),
+    ] {
+        for md in docs {
+            let whole = md
+                .parse_md_with_config(config)
+                .iter()
+                .map(|token| format!("{:?}", token))
+                .collect::<Vec<_>>();
+            assert_eq!(streamed(md, config).0, whole, "{:?}", md);
+        }
+    }
+    assert_eq!(streamed("a\n\nb\n\nc", ParserConfig::default()).1, 3);
+    assert_eq!(streamed("", ParserConfig::default()).1, 0);
+    assert!("`a\n\nb`"
+        .parse_md()
+        .iter()
+        .all(|token| matches!(token, Token::Text(text) if !text.code) || token.is_line_break()));
 }
@@ -0,0 +1,143 @@
+use crate::parser::{needs_space, Token};
+
+use super::*;
+use alloc::{collections::BTreeMap, format};
+
+/// An entry of a table of contents, generated from a header.
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TocEntry {
+    /// Depth of the header (ie. `1` for `#`, `2` for `##` etc.).
+    pub depth: usize,
+    /// Text of the header, without any formatting.
+    pub text: String,
+    /// Slug of the header, which can be used as an anchor.
+    pub slug: String,
+}
+
+/// Generates a table of contents from parsed tokens.
+///
+/// Slugs are unique within the table of contents; duplicate slugs get a `-1`, `-2` etc. suffix.
+///
+/// # Example
+/// ```
+/// # use linemd::{generate_toc, Parser};
+/// let toc = generate_toc("# Some\n## Uninspiring headers".parse_md());
+/// assert_eq!(toc[1].slug, "uninspiring-headers");
+/// ```
+pub fn generate_toc<'a, Custom: 'a>(tokens: impl AsRef<[Token<'a, Custom>]> + 'a) -> Vec<TocEntry> {
+    let tokens = tokens.as_ref();
+    let mut entries = Vec::new();
+    let mut slugs = Slugs::default();
+    let mut at = 0;
+    while at < tokens.len() {
+        if let Token::Header(depth) = &tokens[at] {
+            let (text, nat) = line_text(tokens, at + 1);
+            entries.push(TocEntry {
+                depth: *depth,
+                slug: slugs.unique(slugify(&text)),
+                text,
+            });
+            at = nat;
+        } else {
+            at += 1;
+        }
+    }
+    entries
+}
+
+/// Returns the text of the first header in parsed tokens, without any formatting.
+///
+/// Returns `None` if there are no headers.
+///
+/// # Example
+/// ```
+/// # use linemd::{toc, Parser};
+/// let title = toc::title("Some text\n# Uninspiring title".parse_md());
+/// assert_eq!(title.as_deref(), Some("Uninspiring title"));
+/// ```
+pub fn title<'a, Custom: 'a>(tokens: impl AsRef<[Token<'a, Custom>]> + 'a) -> Option<String> {
+    let tokens = tokens.as_ref();
+    tokens
+        .iter()
+        .position(|token| matches!(token, Token::Header(_)))
+        .map(|at| line_text(tokens, at + 1).0)
+}
+
+/// Creates a GitHub style slug from some text.
+///
+/// The text is lowercased, spaces are turned into hyphens and punctuation is stripped.
+///
+/// # Example
+/// ```
+/// # use linemd::toc::slugify;
+/// assert_eq!(slugify("Hello, World!"), "hello-world");
+/// ```
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.trim().chars() {
+        if c.is_whitespace() || c == '-' {
+            slug.push('-');
+        } else if c.is_alphanumeric() || c == '_' {
+            slug.extend(c.to_lowercase());
+        }
+    }
+    slug
+}
+
+/// Keeps track of used slugs in a document, to make them unique.
+#[derive(Default, Debug)]
+pub(crate) struct Slugs {
+    used: BTreeMap<String, usize>,
+}
+
+impl Slugs {
+    /// Returns the slug if it wasn't used before, or the slug with a numbered suffix otherwise.
+    ///
+    /// Suffixed slugs are used too, so `Intro 1` doesn't get the slug of a second `Intro`.
+    pub(crate) fn unique(&mut self, slug: String) -> String {
+        let Some(mut count) = self.used.get(&slug).copied() else {
+            self.used.insert(slug.clone(), 0);
+            return slug;
+        };
+        let unique = loop {
+            count += 1;
+            let unique = format!("{}-{}", slug, count);
+            if !self.used.contains_key(&unique) {
+                break unique;
+            }
+        };
+        self.used.insert(slug, count);
+        self.used.insert(unique.clone(), 0);
+        unique
+    }
+}
+
+/// Collects the text of tokens starting from `at` until a line break, without any formatting.
+///
+/// Tokens are spaced like renderers space them, so `Hello **World**!` is `Hello World!`.
+/// Returns the text and the index of the line break (or the end of tokens).
+pub(crate) fn line_text<Custom>(tokens: &[Token<'_, Custom>], mut at: usize) -> (String, usize) {
+    let mut text = String::new();
+    while at < tokens.len() {
+        let nested;
+        let value = match &tokens[at] {
+            Token::LineBreak => break,
+            Token::Text(t) => match t.parse_nested::<()>() {
+                Some(tokens) => {
+                    nested = line_text(&tokens, 0).0;
+                    &nested
+                }
+                None => t.value,
+            },
+            Token::Url { name, url, .. } => name.as_ref().map_or(url.as_ref(), |t| t.value),
+            Token::Math { content, .. } => content,
+            _ => "",
+        };
+        text.push_str(value.trim_end());
+        if needs_space(tokens, at) && !text.ends_with(' ') {
+            text.push(' ');
+        }
+        at += 1;
+    }
+    (String::from(text.trim()), at)
+}
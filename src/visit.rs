@@ -0,0 +1,299 @@
+use crate::parser::{AlertKind, Definition, FrontMatterFormat, Text, Token};
+
+use super::*;
+use alloc::borrow::Cow;
+
+/// A visitor over tokens, see [`walk`].
+///
+/// All methods do nothing by default, so you only need to implement the methods for
+/// tokens you are interested in.
+pub trait Visitor<'a, Custom> {
+    /// Visits a token. By default this calls the visit method for the token's kind.
+    fn visit_token(&mut self, token: &Token<'a, Custom>) {
+        match token {
+            Token::Text(text) => self.visit_text(text),
+            Token::Url {
+                name,
+                url,
+                title,
+                is_image,
+                width,
+                height,
+            } => self.visit_url(
+                name.as_ref(),
+                url.clone(),
+                *title,
+                *is_image,
+                *width,
+                *height,
+            ),
+            Token::Header(depth) => self.visit_header(*depth),
+            Token::ListItem { place, marker } => self.visit_list_item(*place, *marker),
+            Token::ListContinuation => self.visit_list_continuation(),
+            Token::Math { content, display } => self.visit_math(content, *display),
+            Token::FootnoteRef(id) => self.visit_footnote_ref(id),
+            Token::FootnoteDef { id, body } => self.visit_footnote_def(id, body),
+            Token::Details { summary, body } => self.visit_details(summary, body),
+            Token::Alert { kind, body } => self.visit_alert(*kind, body),
+            Token::DefinitionList(definitions) => self.visit_definition_list(definitions),
+            Token::Html(html) => self.visit_html(html),
+            Token::Comment(comment) => self.visit_comment(comment),
+            Token::FrontMatter { format, raw } => self.visit_front_matter(*format, raw),
+            Token::CodeFence { code, attrs } => self.visit_code_fence(code, attrs),
+            Token::LineBreak => self.visit_line_break(),
+            Token::ParagraphStart => self.visit_paragraph_start(),
+            Token::ParagraphEnd => self.visit_paragraph_end(),
+            Token::Custom(custom) => self.visit_custom(custom),
+        }
+    }
+    /// Visits a text token. The markup nested in the text isn't visited, see
+    /// [`Text::parse_nested`].
+    fn visit_text(&mut self, _text: &Text<'a>) {}
+    /// Visits an URL token.
+    fn visit_url(
+        &mut self,
+        _name: Option<&Text<'a>>,
+        _url: Cow<'a, str>,
+        _title: Option<&'a str>,
+        _is_image: bool,
+        _width: Option<usize>,
+        _height: Option<usize>,
+    ) {
+    }
+    /// Visits a header token.
+    fn visit_header(&mut self, _depth: usize) {}
+    /// Visits a list item token.
+    fn visit_list_item(&mut self, _place: Option<usize>, _marker: char) {}
+    /// Visits a list continuation token.
+    fn visit_list_continuation(&mut self) {}
+    /// Visits a math token.
+    fn visit_math(&mut self, _content: &'a str, _display: bool) {}
+    /// Visits a footnote reference token.
+    fn visit_footnote_ref(&mut self, _id: &'a str) {}
+    /// Visits a footnote definition token.
+    fn visit_footnote_def(&mut self, _id: &'a str, _body: &'a str) {}
+    /// Visits a collapsible section token. By default this visits the tokens of the body.
+    fn visit_details(&mut self, _summary: &Text<'a>, body: &[Token<'a, Custom>]) {
+        for token in body {
+            self.visit_token(token);
+        }
+    }
+    /// Visits an alert token. By default this visits the tokens of the body.
+    fn visit_alert(&mut self, _kind: AlertKind, body: &[Token<'a, Custom>]) {
+        for token in body {
+            self.visit_token(token);
+        }
+    }
+    /// Visits a definition list token.
+    fn visit_definition_list(&mut self, _definitions: &[Definition<'a>]) {}
+    /// Visits a raw HTML token.
+    fn visit_html(&mut self, _html: &'a str) {}
+    /// Visits a comment token.
+    fn visit_comment(&mut self, _comment: &'a str) {}
+    /// Visits a front matter token.
+    fn visit_front_matter(&mut self, _format: FrontMatterFormat, _raw: &'a str) {}
+    /// Visits a code fence token.
+    fn visit_code_fence(&mut self, _code: &str, _attrs: &'a str) {}
+    /// Visits a line break token.
+    fn visit_line_break(&mut self) {}
+    /// Visits a paragraph start token.
+    fn visit_paragraph_start(&mut self) {}
+    /// Visits a paragraph end token.
+    fn visit_paragraph_end(&mut self) {}
+    /// Visits a custom token.
+    fn visit_custom(&mut self, _custom: &Custom) {}
+}
+
+/// A folder over tokens, see [`fold`].
+///
+/// All methods return the token as-is by default, so you only need to implement the methods for
+/// tokens you want to replace.
+pub trait Folder<'a, Custom> {
+    /// Folds a token. By default this calls the fold method for the token's kind.
+    fn fold_token(&mut self, token: Token<'a, Custom>) -> Token<'a, Custom> {
+        match token {
+            Token::Text(text) => self.fold_text(text),
+            Token::Url {
+                name,
+                url,
+                title,
+                is_image,
+                width,
+                height,
+            } => self.fold_url(name, url, title, is_image, width, height),
+            Token::Header(depth) => self.fold_header(depth),
+            Token::ListItem { place, marker } => self.fold_list_item(place, marker),
+            Token::ListContinuation => self.fold_list_continuation(),
+            Token::Math { content, display } => self.fold_math(content, display),
+            Token::FootnoteRef(id) => self.fold_footnote_ref(id),
+            Token::FootnoteDef { id, body } => self.fold_footnote_def(id, body),
+            Token::Details { summary, body } => self.fold_details(summary, body),
+            Token::Alert { kind, body } => self.fold_alert(kind, body),
+            Token::DefinitionList(definitions) => self.fold_definition_list(definitions),
+            Token::Html(html) => self.fold_html(html),
+            Token::Comment(comment) => self.fold_comment(comment),
+            Token::FrontMatter { format, raw } => self.fold_front_matter(format, raw),
+            Token::CodeFence { code, attrs } => self.fold_code_fence(code, attrs),
+            Token::LineBreak => self.fold_line_break(),
+            Token::ParagraphStart => self.fold_paragraph_start(),
+            Token::ParagraphEnd => self.fold_paragraph_end(),
+            Token::Custom(custom) => self.fold_custom(custom),
+        }
+    }
+    /// Folds a text token.
+    fn fold_text(&mut self, text: Text<'a>) -> Token<'a, Custom> {
+        Token::Text(text)
+    }
+    /// Folds an URL token.
+    fn fold_url(
+        &mut self,
+        name: Option<Text<'a>>,
+        url: Cow<'a, str>,
+        title: Option<&'a str>,
+        is_image: bool,
+        width: Option<usize>,
+        height: Option<usize>,
+    ) -> Token<'a, Custom> {
+        Token::Url {
+            name,
+            url,
+            title,
+            is_image,
+            width,
+            height,
+        }
+    }
+    /// Folds a header token.
+    fn fold_header(&mut self, depth: usize) -> Token<'a, Custom> {
+        Token::Header(depth)
+    }
+    /// Folds a list item token.
+    fn fold_list_item(&mut self, place: Option<usize>, marker: char) -> Token<'a, Custom> {
+        Token::ListItem { place, marker }
+    }
+    /// Folds a list continuation token.
+    fn fold_list_continuation(&mut self) -> Token<'a, Custom> {
+        Token::ListContinuation
+    }
+    /// Folds a math token.
+    fn fold_math(&mut self, content: &'a str, display: bool) -> Token<'a, Custom> {
+        Token::Math { content, display }
+    }
+    /// Folds a footnote reference token.
+    fn fold_footnote_ref(&mut self, id: &'a str) -> Token<'a, Custom> {
+        Token::FootnoteRef(id)
+    }
+    /// Folds a footnote definition token.
+    fn fold_footnote_def(&mut self, id: &'a str, body: &'a str) -> Token<'a, Custom> {
+        Token::FootnoteDef { id, body }
+    }
+    /// Folds a collapsible section token. By default this folds the tokens of the body.
+    fn fold_details(
+        &mut self,
+        summary: Text<'a>,
+        body: Vec<Token<'a, Custom>>,
+    ) -> Token<'a, Custom> {
+        Token::Details {
+            summary,
+            body: body
+                .into_iter()
+                .map(|token| self.fold_token(token))
+                .collect(),
+        }
+    }
+    /// Folds an alert token. By default this folds the tokens of the body.
+    fn fold_alert(&mut self, kind: AlertKind, body: Vec<Token<'a, Custom>>) -> Token<'a, Custom> {
+        Token::Alert {
+            kind,
+            body: body
+                .into_iter()
+                .map(|token| self.fold_token(token))
+                .collect(),
+        }
+    }
+    /// Folds a definition list token.
+    fn fold_definition_list(&mut self, definitions: Vec<Definition<'a>>) -> Token<'a, Custom> {
+        Token::DefinitionList(definitions)
+    }
+    /// Folds a raw HTML token.
+    fn fold_html(&mut self, html: &'a str) -> Token<'a, Custom> {
+        Token::Html(html)
+    }
+    /// Folds a comment token.
+    fn fold_comment(&mut self, comment: &'a str) -> Token<'a, Custom> {
+        Token::Comment(comment)
+    }
+    /// Folds a front matter token.
+    fn fold_front_matter(&mut self, format: FrontMatterFormat, raw: &'a str) -> Token<'a, Custom> {
+        Token::FrontMatter { format, raw }
+    }
+    /// Folds a code fence token.
+    fn fold_code_fence(&mut self, code: Cow<'a, str>, attrs: &'a str) -> Token<'a, Custom> {
+        Token::CodeFence { code, attrs }
+    }
+    /// Folds a line break token.
+    fn fold_line_break(&mut self) -> Token<'a, Custom> {
+        Token::LineBreak
+    }
+    /// Folds a paragraph start token.
+    fn fold_paragraph_start(&mut self) -> Token<'a, Custom> {
+        Token::ParagraphStart
+    }
+    /// Folds a paragraph end token.
+    fn fold_paragraph_end(&mut self) -> Token<'a, Custom> {
+        Token::ParagraphEnd
+    }
+    /// Folds a custom token.
+    fn fold_custom(&mut self, custom: Custom) -> Token<'a, Custom> {
+        Token::Custom(custom)
+    }
+}
+
+/// Walks over tokens with a visitor.
+///
+/// # Example
+/// ```
+/// # use linemd::{visit::{walk, Visitor}, Parser};
+/// struct HeaderCounter(usize);
+///
+/// impl<'a> Visitor<'a, ()> for HeaderCounter {
+///     fn visit_header(&mut self, _depth: usize) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let mut counter = HeaderCounter(0);
+/// walk(&"# Some\n## Uninspiring headers".parse_md(), &mut counter);
+/// assert_eq!(counter.0, 2);
+/// ```
+pub fn walk<'a, Custom>(tokens: &[Token<'a, Custom>], visitor: &mut impl Visitor<'a, Custom>) {
+    for token in tokens {
+        visitor.visit_token(token);
+    }
+}
+
+/// Folds tokens with a folder, replacing every token with the one the folder returns.
+///
+/// # Example
+/// ```
+/// # use linemd::{parser::{Text, Token}, visit::{fold, Folder}, Parser};
+/// struct Unbold;
+///
+/// impl<'a> Folder<'a, ()> for Unbold {
+///     fn fold_text(&mut self, text: Text<'a>) -> Token<'a, ()> {
+///         Token::Text(Text { bold: false, ..text })
+///     }
+/// }
+///
+/// let tokens = fold("**Some uninspiring text.**".parse_md(), &mut Unbold);
+/// assert_eq!(tokens, "Some uninspiring text.".parse_md());
+/// ```
+pub fn fold<'a, Custom>(
+    tokens: Vec<Token<'a, Custom>>,
+    folder: &mut impl Folder<'a, Custom>,
+) -> Vec<Token<'a, Custom>> {
+    tokens
+        .into_iter()
+        .map(|token| folder.fold_token(token))
+        .collect()
+}
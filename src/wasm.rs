@@ -0,0 +1,22 @@
+use crate::{html, svg, Parser};
+
+use super::*;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Parses markdown and renders it as HTML, with the default configuration.
+///
+/// Exported to JavaScript as `parse_to_html`.
+#[wasm_bindgen]
+pub fn parse_to_html(input: &str) -> String {
+    html::render_as_html(input.parse_md())
+}
+
+/// Parses markdown and renders it as SVG, with the default configuration and the given width
+/// in pixels. The height is computed from the content.
+///
+/// Exported to JavaScript as `parse_to_svg`.
+#[wasm_bindgen]
+pub fn parse_to_svg(input: &str, width: u32) -> String {
+    let config = svg::Config::default().dimensions(svg::ViewportDimensions::OnlyWidth(width));
+    svg::render_as_svg(input.parse_md(), config)
+}
@@ -0,0 +1,123 @@
+/// Wraps text into lines that are at most `width` columns wide, breaking at whitespace.
+///
+/// Columns are counted like [`display_width`] does. Words that are longer than `width` are
+/// split wherever the line is full. Line breaks in the text are kept, and whitespace around the
+/// lines is trimmed. The lines are slices of `text`, so nothing is allocated. A `width` of
+/// zero is treated as one.
+///
+/// # Example
+/// ```
+/// # use linemd::wrap_text;
+/// let lines: Vec<_> = wrap_text("a short line, then unbreakable", 12).collect();
+/// assert_eq!(lines, ["a short", "line, then", "unbreakable"]);
+/// ```
+pub fn wrap_text(text: &str, width: usize) -> impl Iterator<Item = &str> {
+    let width = width.max(1);
+    text.lines().flat_map(move |line| WrapLine {
+        rest: Some(line),
+        width,
+    })
+}
+
+/// Iterator over the wrapped lines of a line of text. See [`wrap_text`].
+struct WrapLine<'a> {
+    rest: Option<&'a str>,
+    width: usize,
+}
+
+impl<'a> Iterator for WrapLine<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?.trim_start();
+
+        // Where the line can be broken, ie. the end of the last word that fit.
+        let mut break_at = None;
+        let mut was_whitespace = false;
+        let mut column = 0;
+        for (at, c) in rest.char_indices() {
+            column += char_width(c);
+            if c.is_whitespace() {
+                if !was_whitespace {
+                    break_at = Some(at);
+                }
+            } else if column > self.width && at > 0 {
+                let end = break_at.unwrap_or(at);
+                self.rest = Some(&rest[end..]);
+                return Some(rest[..end].trim_end());
+            }
+            was_whitespace = c.is_whitespace();
+        }
+
+        self.rest = None;
+        Some(rest.trim_end())
+    }
+}
+
+/// Estimates how many columns text takes up in a monospace font, like in a terminal.
+///
+/// Wide characters (like CJK characters and most emoji) take up two columns, combining marks,
+/// zero width characters and control characters none, and other characters one. This is an
+/// estimate, since it doesn't know about grapheme clusters (like flags) or the font.
+///
+/// # Example
+/// ```
+/// # use linemd::display_width;
+/// assert_eq!(display_width("abc"), 3);
+/// assert_eq!(display_width("\u{4f60}\u{597d}"), 4);
+/// assert_eq!(display_width("e\u{301}"), 1);
+/// ```
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Returns how many columns a character takes up, see [`display_width`].
+fn char_width(c: char) -> usize {
+    /// Combining marks and zero width characters.
+    const ZERO_WIDTH: &[(char, char)] = &[
+        ('\u{300}', '\u{36f}'),
+        ('\u{483}', '\u{489}'),
+        ('\u{591}', '\u{5bd}'),
+        ('\u{610}', '\u{61a}'),
+        ('\u{64b}', '\u{65f}'),
+        ('\u{1ab0}', '\u{1aff}'),
+        ('\u{1dc0}', '\u{1dff}'),
+        ('\u{200b}', '\u{200f}'),
+        ('\u{20d0}', '\u{20ff}'),
+        ('\u{fe00}', '\u{fe0f}'),
+        ('\u{fe20}', '\u{fe2f}'),
+        ('\u{feff}', '\u{feff}'),
+        ('\u{e0100}', '\u{e01ef}'),
+    ];
+    /// East Asian wide and fullwidth characters, and emoji.
+    const WIDE: &[(char, char)] = &[
+        ('\u{1100}', '\u{115f}'),
+        ('\u{2e80}', '\u{303e}'),
+        ('\u{3041}', '\u{33ff}'),
+        ('\u{3400}', '\u{4dbf}'),
+        ('\u{4e00}', '\u{9fff}'),
+        ('\u{a000}', '\u{a4cf}'),
+        ('\u{ac00}', '\u{d7a3}'),
+        ('\u{f900}', '\u{faff}'),
+        ('\u{fe30}', '\u{fe4f}'),
+        ('\u{ff00}', '\u{ff60}'),
+        ('\u{ffe0}', '\u{ffe6}'),
+        ('\u{1f300}', '\u{1f64f}'),
+        ('\u{1f900}', '\u{1f9ff}'),
+        ('\u{20000}', '\u{2fffd}'),
+        ('\u{30000}', '\u{3fffd}'),
+    ];
+    let is_in = |ranges: &[(char, char)]| {
+        ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&c))
+    };
+
+    if c.is_control() || is_in(ZERO_WIDTH) {
+        0
+    } else if is_in(WIDE) {
+        2
+    } else {
+        1
+    }
+}
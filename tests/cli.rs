@@ -0,0 +1,63 @@
+use std::process::{Command, Output};
+
+fn linemd(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_linemd"))
+        .args(args)
+        .output()
+        .expect("failed to run linemd")
+}
+
+#[test]
+fn svg_width() {
+    let output = linemd(&["--svg", "--width", "800", "examples/all.md"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with(r#"<svg width="800" height="#));
+}
+
+#[test]
+fn svg_fonts() {
+    let output = linemd(&[
+        "--svg",
+        "--font-family",
+        "serif",
+        "--font-size",
+        "12px",
+        "examples/all.md",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(r#" font-family="serif" font-size="12px""#));
+}
+
+#[test]
+fn invalid_width() {
+    let output = linemd(&["--svg", "--width", "wide", "examples/all.md"]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn output_file() {
+    let path = std::env::temp_dir().join(format!("linemd-output-{}.html", std::process::id()));
+    let output = linemd(&["-o", path.to_str().unwrap(), "examples/all.md"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written, include_str!("../examples/all.html"));
+}
+
+#[test]
+fn output_file_error() {
+    let output = linemd(&["--output", "/nonexistent/dir/out.html", "examples/all.md"]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn standalone() {
+    let output = linemd(&["--standalone", "examples/all.md"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("<!DOCTYPE html>"));
+    assert!(stdout.contains("<title>Header</title>"));
+}
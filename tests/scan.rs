@@ -0,0 +1,42 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use linemd::Parser;
+
+/// Counts the allocations of each thread, to check that scanning doesn't allocate.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn scan_token_doesnt_allocate() {
+    let md = "# Title\nSome **bold** and [a link](https://x.org).\n\n    code\n\n    more\n\n\
+        - ```\n  indented\n  ```\n:::details More\n*a* <me@x.org>\n:::\n\
+        > [!NOTE]\n> www.x.org\nterm\n: a\n: b\ni. x\nii. y";
+    let before = ALLOCATIONS.with(Cell::get);
+    let mut at = 0;
+    let mut count = 0;
+    while let Some((_, nat)) = md.scan_token(at) {
+        at = nat;
+        count += 1;
+    }
+    assert_eq!(ALLOCATIONS.with(Cell::get), before);
+    assert_eq!(count, md.parse_md().len());
+}
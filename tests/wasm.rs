@@ -0,0 +1,22 @@
+//! Tests the functions exported to JavaScript. Run them in Node.js with
+//! `CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner cargo test --target
+//! wasm32-unknown-unknown --features wasm --test wasm`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use linemd::wasm;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn parse_to_html() {
+    assert_eq!(
+        wasm::parse_to_html("# Title\nSome *text*"),
+        "<h1>Title</h1>\n<p>Some <i>text</i></p>"
+    );
+}
+
+#[wasm_bindgen_test]
+fn parse_to_svg() {
+    let svg = wasm::parse_to_svg("# Title\nSome *text*", 300);
+    assert!(svg.starts_with(r#"<svg width="300" height="#));
+    assert!(svg.contains(">Title</tspan>"));
+}